@@ -24,6 +24,9 @@ impl database::Command for Command {}
 pub enum Response {
 	/// Returns a `Chip`.
 	Chip(Chip),
+
+	/// The chip does not exist.
+	DoesNotExist,
 }
 
 impl database::Response for Response {}
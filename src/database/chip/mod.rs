@@ -14,7 +14,7 @@ use architecture::Chip;
 
 use crate::log::TimeReport;
 
-use database::{ Database as DatabaseTrait, Interface, common::DBInterface };
+use database::{ Database as DatabaseTrait, Interface, common::{ DBInterface, Status } };
 
 use futures::future::join_all;
 
@@ -32,14 +32,19 @@ use tokio::{
 };
 
 use tracing::{
-    debug, error, info,
+    debug, error, info, warn,
 
     instrument::WithSubscriber,
 };
 
 
 
-use self::cmds::DBCommand as Command;
+use self::cmds::{
+    DBCommand as Command,
+    Command as ChipCommand, Response as ChipResponse,
+};
+
+use super::statusupdate;
 use self::fmls::FamilyDatabase;
 use self::mnfs::ManufacturerDatabase;
 use self::vars::VariantDatabase;
@@ -163,7 +168,32 @@ impl Database {
     async fn eventloop(&mut self) {
         loop {
             match self.cmds.recv().await {
-                Some(_) => (),
+                Some(cmd) => {
+                    let (cmd, channel, mut status) = cmd.destructure();
+
+                    statusupdate(&mut status, Status::Acknowledged).await;
+
+                    match cmd {
+                        ChipCommand::GetChip(name) => {
+                            match self.chip.iter().find(|chip| chip.name().eq_ignore_ascii_case(&name)) {
+                                Some(chip) => {
+                                    debug!(origin="database", db="chip", "Found chip {}", name);
+                                    channel.send( ChipResponse::Chip( chip.clone() ) );
+                                },
+
+                                None => {
+                                    warn!(origin="database", db="chip", "Chip {} does not exist", name);
+                                    channel.send( ChipResponse::DoesNotExist );
+                                },
+                            }
+
+                            match status.send(Status::Completed).await {
+                                Err(e) => error!(origin="database", db="chip", "Could not send 'Completed' status update for 'GetChip' command: {}", e),
+                                _ => (),
+                            }
+                        },
+                    }
+                },
                 _ => break,
             }
         }
@@ -212,6 +242,54 @@ async fn suff(chips: Vec<Chip>, mut report: TimeReport) -> (HashMap<String, Vec<
     (suff, report)
 }
 
+/// Finds the SVD file of a chip in the `svd` folder of the database.
+/// Files are named after the chip or after the family that prefixes its name.
+pub async fn svdfile(chip: &str) -> Option<PathBuf> {
+    let folder = super::basefolder("svd").await?;
+
+    let mut entries = match read_dir(&folder).await {
+        Err(e) => {
+            error!(origin="database", db="chip", "Could not read entries in directory {}: {}", folder.display(), e);
+            return None;
+        },
+
+        Ok(e) => e,
+    };
+
+    let chip = chip.to_lowercase();
+
+    // The longest name that prefixes the chip is the most specific file.
+    let mut best: Option<(usize, PathBuf)> = None;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+
+        let stem = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("svd") => match path.file_stem().and_then(|s| s.to_str()) {
+                Some(stem) => stem.to_lowercase(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        if chip.starts_with(&stem) && best.as_ref().map(|(n, _)| stem.len() > *n).unwrap_or(true) {
+            best = Some( (stem.len(), path) );
+        }
+    }
+
+    match best {
+        Some((_, path)) => {
+            debug!(origin="database", db="chip", "Found SVD file {} for chip {}", path.display(), chip);
+            Some(path)
+        },
+
+        _ => {
+            warn!(origin="database", db="chip", "Chip {} has no SVD file", chip);
+            None
+        },
+    }
+}
+
 /// Loads all files below the current directory and parses them.
 async fn load(folder: PathBuf, chips: &mut Vec<Chip>, mut report: TimeReport) -> TimeReport {
     /// Search depth.
@@ -31,6 +31,9 @@ pub enum Command {
 
     /// Requests the current search engine.
     GetSearchEngine,
+
+    /// Replaces the project with the same name and saves it.
+    UpdateProject( ProjectSerial ),
 }
 
 impl database::Command for Command {}
@@ -48,6 +51,9 @@ pub enum Response {
     /// The project does not exist.
     DoesNotExist,
 
+    /// The project was updated but could not be saved to its file.
+    NotSaved,
+
     /// The request was done.
     Done,
 }
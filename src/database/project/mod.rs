@@ -298,14 +298,72 @@ impl Database {
                             }
                         },
 
+                        ProjectCommand::UpdateProject(mut project) => {
+                            // Get the file the project was loaded from.
+                            let previous = {
+                                let (projects, name) = join!{
+                                    self.projects.read(),
+                                    self.name.read(),
+                                };
+
+                                name.get(project.name()).map(|idx| projects[*idx].path.clone())
+                            };
+
+                            match previous {
+                                Some(path) => {
+                                    project.path = path;
+
+                                    // Save the project without holding the databases.
+                                    // Keep the previous file if the project could not be saved.
+                                    let saved = match save(&project).await {
+                                        Some(path) => {
+                                            project.path = Some(path);
+                                            true
+                                        },
+                                        _ => false,
+                                    };
+
+                                    // Get write access to the projects, the project may have been deleted while saving.
+                                    let (mut projects, name) = join!{
+                                        self.projects.write(),
+                                        self.name.read(),
+                                    };
+
+                                    match name.get(project.name()) {
+                                        Some(idx) => {
+                                            projects[*idx] = project;
+
+                                            channel.send( match saved {
+                                                true => ProjectResponse::Done,
+                                                _ => ProjectResponse::NotSaved,
+                                            });
+                                        },
+
+                                        None => {
+                                            error!(origin="database", db="project", "Project {} was deleted while it was being saved", project.name());
+                                            channel.send( ProjectResponse::DoesNotExist );
+                                        },
+                                    }
+                                },
+
+                                None => {
+                                    error!(origin="database", db="project", "Attempted to update non existent project {}", project.name());
+                                    channel.send( ProjectResponse::DoesNotExist );
+                                },
+                            }
+
+                            match status.send(Status::Completed).await {
+                                Err(e) => error!(origin="database", db="project", "Could not send 'Completed' status update for 'UpdateProject' command: {}", e),
+                                _ => (),
+                            }
+                        },
+
                         _ => (),
                     }
                 },
                 _ => break,
             }
         }
-
-        // TODO : Implement saving
     }
 }
 
@@ -437,7 +495,7 @@ async fn projectparse(path: PathBuf, filename: String) -> Option<(ProjectSerial,
     }
 
     // Parse the file.
-    let project = match ProjectSerial::parse(buffer) {
+    let mut project = match ProjectSerial::parse(buffer) {
         Err(e) => {
             error!(origin = "database", db="project", "Failed to parse project data in file {}: {}", path.display(), e);
             return None;
@@ -450,11 +508,51 @@ async fn projectparse(path: PathBuf, filename: String) -> Option<(ProjectSerial,
         },
     };
 
+    // Store the origin of the project.
+    project.path = Some(path);
+
     report.end(start);
 
     Some( (project, report) )
 }
 
+/// Saves a project to its file, or to a new file in the project folder.
+/// Returns the path of the file if it was saved.
+async fn save(project: &ProjectSerial) -> Option<PathBuf> {
+    // Get the path of the file.
+    let path = match &project.path {
+        Some(path) => path.clone(),
+
+        None => match super::basefolder("projects").await {
+            Some(base) => base.join( format!("{}.ron", project.name()) ),
+            _ => {
+                error!(origin="database", db="project", "Could not find ProjectDatabase folder to save project {}", project.name());
+                return None;
+            },
+        },
+    };
+
+    // Serialize the project.
+    let serial = match project.serialize() {
+        Err(e) => {
+            error!(origin="database", db="project", "Could not serialize project {}: {}", project.name(), e);
+            return None;
+        },
+        Ok(s) => s,
+    };
+
+    match tokio::fs::write(&path, serial).await {
+        Err(e) => {
+            error!(origin="database", db="project", "Could not save project {} to {}: {}", project.name(), path.display(), e);
+            None
+        },
+        _ => {
+            debug!(origin="database", db="project", "Saved project {} to {}", project.name(), path.display());
+            Some(path)
+        },
+    }
+}
+
 /// Generates the suffix search engine.
 async fn suff(projects: Arc<RwLock<Vec<ProjectSerial>>>) -> (HashMap<String, Vec<usize>>, TimeReport) {
     let mut report = TimeReport::new( String::from("projectdb-build-searchengine") );
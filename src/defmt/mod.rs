@@ -0,0 +1,259 @@
+//! defmt module.
+//! Decodes the defmt log frames sent by the target with the `.defmt` table of its ELF.
+
+
+
+use crate::elf::ElfFile;
+
+use defmt_decoder::{
+    DecodeError, Encoding, Frame, Locations, Table,
+};
+
+use tracing::{
+    debug, error, warn,
+};
+
+
+
+/// Name of the RTT channel used by defmt.
+pub const CHANNEL: &'static str = "defmt";
+
+
+
+/// Decoder of the defmt frames of a target.
+pub struct Decoder {
+    /// Format strings of the firmware.
+    table: Table,
+
+    /// Source locations of the log statements.
+    locations: Option<Locations>,
+
+    /// Received bytes that do not form a complete frame yet.
+    pending: Vec<u8>,
+
+    /// Number of frames that could not be decoded with the table.
+    malformed: usize,
+}
+
+impl Decoder {
+    /// Creates a decoder from the `.defmt` table of the ELF.
+    /// Returns `None` if the firmware does not log with defmt.
+    pub fn new(elf: &ElfFile) -> Result<Option<Self>, Error> {
+        let table = match Table::parse(elf.data()) {
+            Err(e) => {
+                error!(origin="defmt", "Could not parse the defmt table of {}: {}", elf.path().display(), e);
+                return Err( Error::Table( e.to_string() ) );
+            },
+
+            Ok(None) => return Ok( None ),
+
+            Ok(Some(table)) => table,
+        };
+
+        // Locations are optional, they require the debug information.
+        let locations = match table.get_locations(elf.data()) {
+            Err(e) => {
+                warn!(origin="defmt", "Could not read the locations of the defmt table: {}", e);
+                None
+            },
+
+            Ok(locations) if locations.is_empty() => {
+                warn!(origin="defmt", "The ELF has no debug information, defmt locations are not available");
+                None
+            },
+
+            Ok(locations) => Some(locations),
+        };
+
+        debug!(origin="defmt", "Loaded the defmt table of {}", elf.path().display());
+
+        Ok( Some( Decoder { table, locations, pending: Vec::new(), malformed: 0 } ) )
+    }
+
+    /// Decodes the received bytes into log entries.
+    /// Incomplete frames are kept until the rest of their bytes is received.
+    pub fn received(&mut self, data: &[u8]) -> Vec<LogEntry> {
+        self.pending.extend_from_slice(data);
+
+        let mut entries = Vec::new();
+
+        match self.table.encoding() {
+            Encoding::Raw => loop {
+                match self.table.decode(&self.pending) {
+                    Ok((frame, consumed)) => {
+                        entries.push( self.entry(&frame) );
+                        self.pending.drain(..consumed);
+                    },
+
+                    Err(DecodeError::UnexpectedEof) => break,
+
+                    // Raw frames cannot be resynchronized.
+                    Err(DecodeError::Malformed) => {
+                        self.malformed += 1;
+                        self.pending.clear();
+                        break;
+                    },
+                }
+            },
+
+            Encoding::Rzcobs => while let Some(end) = self.pending.iter().position(|b| *b == 0) {
+                // Each frame is delimited by a zero byte.
+                let frame: Vec<u8> = self.pending.drain(..=end).collect();
+
+                let mut decoder = self.table.new_stream_decoder();
+                decoder.received(&frame);
+
+                match decoder.decode() {
+                    Ok(frame) => entries.push( self.entry(&frame) ),
+                    Err(_) => self.malformed += 1,
+                }
+            },
+        }
+
+        if self.malformed > 0 && entries.is_empty() {
+            warn!(origin="defmt", "{} defmt frames could not be decoded, the firmware may not match the ELF", self.malformed);
+        }
+
+        entries
+    }
+
+    /// Returns the number of frames that did not match the table.
+    /// Frames that do not match indicate that the target runs a different firmware than the ELF.
+    pub fn malformed(&self) -> usize {
+        self.malformed
+    }
+
+    /// Builds the log entry of a frame.
+    fn entry(&self, frame: &Frame) -> LogEntry {
+        let location = self.locations.as_ref()
+            .and_then(|locations| locations.get(&frame.index()))
+            .map(|location| Location {
+                file: location.file.display().to_string(),
+                line: location.line as u32,
+                module: location.module.clone(),
+            });
+
+        LogEntry {
+            level: frame.level().map(Level::from),
+            timestamp: frame.display_timestamp().map(|t| t.to_string()),
+            message: frame.display_message().to_string(),
+            location,
+        }
+    }
+}
+
+
+
+/// A decoded log statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Level of the statement, `None` for `println` statements.
+    pub level: Option<Level>,
+
+    /// Timestamp of the statement, if the firmware provides one.
+    pub timestamp: Option<String>,
+
+    /// Formatted message.
+    pub message: String,
+
+    /// Source location of the statement.
+    pub location: Option<Location>,
+}
+
+impl LogEntry {
+    /// Returns the module of the statement.
+    pub fn module(&self) -> Option<&str> {
+        self.location.as_ref().map(|l| l.module.as_str())
+    }
+}
+
+impl core::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, "{} ", timestamp)?;
+        }
+
+        if let Some(level) = &self.level {
+            write!(f, "{:<5} ", level)?;
+        }
+
+        write!(f, "{}", self.message)?;
+
+        match &self.location {
+            Some(l) => write!(f, "  ({} @ {}:{})", l.module, l.file, l.line),
+            _ => Ok(()),
+        }
+    }
+}
+
+
+
+/// Source location of a log statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// Source file.
+    pub file: String,
+
+    /// Line in the source file.
+    pub line: u32,
+
+    /// Module path.
+    pub module: String,
+}
+
+
+
+/// Level of a log statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// List of all the levels.
+pub const LEVELS: [Level; 5] = [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+impl From<defmt_parser::Level> for Level {
+    fn from(level: defmt_parser::Level) -> Self {
+        match level {
+            defmt_parser::Level::Trace => Level::Trace,
+            defmt_parser::Level::Debug => Level::Debug,
+            defmt_parser::Level::Info  => Level::Info,
+            defmt_parser::Level::Warn  => Level::Warn,
+            defmt_parser::Level::Error => Level::Error,
+        }
+    }
+}
+
+impl core::fmt::Display for Level {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let s = match *self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info  => "INFO",
+            Level::Warn  => "WARN",
+            Level::Error => "ERROR",
+        };
+
+        f.pad(s)
+    }
+}
+
+
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The defmt table of the ELF could not be parsed.
+    Table(String),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Error::Table(e) => write!(f, "Could not parse the defmt table: {}", e),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! ELF module.
+//! Loads the ELF binaries of the projects and resolves their symbols.
+
+
+
+use object::{
+    Object, ObjectSection, ObjectSymbol, SymbolKind as ObjectSymbolKind,
+
+    elf::{ FileHeader32, EI_CLASS, ELFCLASS32, ELFCLASS64, ELFCLASSNONE, ELFMAG, PT_LOAD, SHF_ALLOC, SHT_NOBITS },
+    read::elf::{ FileHeader, ProgramHeader, SectionHeader },
+};
+
+use std::path::PathBuf;
+
+use tracing::{
+    debug, error,
+};
+
+
+
+#[derive(Debug)]
+pub struct ElfFile {
+    /// Path to the file.
+    path: PathBuf,
+
+    /// Raw contents of the file.
+    data: Vec<u8>,
+
+    /// Symbols of the file sorted by address.
+    symbols: Vec<Symbol>,
+
+    /// Loadable segments of the file.
+    segments: Vec<Segment>,
+}
+
+impl ElfFile {
+    /// Loads and parses the ELF file at the given path.
+    pub async fn load(path: PathBuf) -> Result<Self, Error> {
+        let data = match tokio::fs::read(&path).await {
+            Err(e) => {
+                error!(origin="elf", "Could not read ELF file {}: {}", path.display(), e);
+                return Err( Error::Io( e.to_string() ) );
+            },
+            Ok(d) => d,
+        };
+
+        Self::parse(path, data)
+    }
+
+    /// Parses the given ELF contents.
+    pub fn parse(path: PathBuf, data: Vec<u8>) -> Result<Self, Error> {
+        // Raw binary images have no load address.
+        if !data.starts_with(&ELFMAG) {
+            error!(origin="elf", "{} is not an ELF file", path.display());
+            return Err( Error::NotElf );
+        }
+
+        let symbols = {
+            let file = match object::File::parse(&*data) {
+                Err(e) => {
+                    error!(origin="elf", "Could not parse ELF file {}: {}", path.display(), e);
+                    return Err( Error::Parse( e.to_string() ) );
+                },
+                Ok(f) => f,
+            };
+
+            // Thumb functions have the lowest bit of their address set.
+            let thumb = file.architecture() == object::Architecture::Arm;
+
+            let mut symbols: Vec<Symbol> = file.symbols()
+                .filter_map(|s| {
+                    let raw = s.name().ok()?;
+
+                    if raw.len() == 0 { return None }
+
+                    let kind = match s.kind() {
+                        ObjectSymbolKind::Text => SymbolKind::Function,
+                        ObjectSymbolKind::Data => SymbolKind::Data,
+                        _ => SymbolKind::Other,
+                    };
+
+                    let address = match (kind, thumb) {
+                        (SymbolKind::Function, true) => (s.address() as u32) & !1,
+                        _ => s.address() as u32,
+                    };
+
+                    Some( Symbol {
+                        name: format!("{:#}", rustc_demangle::demangle(raw)),
+                        raw: String::from(raw),
+                        address,
+                        size: s.size() as u32,
+                        kind,
+                    })
+                })
+                .collect();
+
+            symbols.sort_by_key(|s| s.address);
+
+            symbols
+        };
+
+        let segments = match Self::loadable(&data) {
+            Err(e) => {
+                error!(origin="elf", "Could not load the segments of ELF file {}: {}", path.display(), e);
+                return Err( e );
+            },
+            Ok(s) => s,
+        };
+
+        debug!(origin="elf", "Loaded {} symbols and {} loadable segments from {}", symbols.len(), segments.len(), path.display());
+
+        Ok( ElfFile { path, data, symbols, segments } )
+    }
+
+    /// Extracts the loadable segments of a 32 bit ELF at their load address.
+    fn loadable(data: &[u8]) -> Result<Vec<Segment>, Error> {
+        // Only 32 bit targets can be flashed.
+        match data.get(EI_CLASS).cloned().unwrap_or(ELFCLASSNONE) {
+            ELFCLASS32 => (),
+            class => return Err( Error::Class(class) ),
+        }
+
+        let parse = |e: object::read::Error| Error::Parse( e.to_string() );
+
+        let header = FileHeader32::<object::Endianness>::parse(data).map_err(parse)?;
+        let endian = header.endian().map_err(parse)?;
+
+        let table = header.sections(endian, data).map_err(parse)?;
+
+        let mut segments = Vec::new();
+
+        for segment in header.program_headers(endian, data).map_err(parse)? {
+            if (segment.p_type(endian) != PT_LOAD) || (segment.p_filesz(endian) == 0) {
+                continue;
+            }
+
+            let bytes = match segment.data(endian, data) {
+                Err(_) => return Err( Error::Parse( String::from("Segment data out of bounds") ) ),
+                Ok(b) => b,
+            };
+
+            let address = segment.p_paddr(endian);
+            let offset = segment.p_offset(endian);
+            let end = offset + segment.p_filesz(endian);
+
+            // Sections stored in the segment, at their load address.
+            let sections = table.iter()
+                .filter(|s| (s.sh_flags(endian) & SHF_ALLOC) != 0)
+                .filter(|s| s.sh_type(endian) != SHT_NOBITS)
+                .filter(|s| (s.sh_offset(endian) >= offset) && (s.sh_offset(endian) < end))
+                .filter_map(|s| {
+                    let name = table.section_name(endian, s).ok()?;
+
+                    Some( Section {
+                        name: String::from_utf8_lossy(name).into_owned(),
+                        address: address + (s.sh_offset(endian) - offset),
+                        size: s.sh_size(endian),
+                    })
+                })
+                .collect();
+
+            segments.push( Segment {
+                address,
+                data: bytes.to_vec(),
+                sections,
+            });
+        }
+
+        segments.sort_by_key(|s| s.address);
+
+        Ok( segments )
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Returns the raw contents of the file.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns all the symbols of the file.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Returns the loadable segments of the file.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the total size in bytes of the loadable segments.
+    pub fn loadsize(&self) -> usize {
+        self.segments.iter().map(|s| s.data.len()).sum()
+    }
+
+    /// Returns the name of the section loaded at the given address.
+    pub fn section(&self, address: u32) -> Option<&str> {
+        self.segments.iter()
+            .flat_map(|s| s.sections.iter())
+            .find(|s| (address >= s.address) && (address < s.address.saturating_add(s.size)))
+            .map(|s| s.name.as_str())
+    }
+
+    /// Returns the contents of the section with the given name.
+    pub fn sectiondata(&self, name: &str) -> Option<&[u8]> {
+        let file = object::File::parse(&*self.data).ok()?;
+        let section = file.section_by_name(name)?;
+
+        // The file is kept in memory, so the range is always valid.
+        let (offset, size) = section.file_range()?;
+
+        self.data.get(offset as usize..(offset + size) as usize)
+    }
+
+    /// Returns the symbol with the given name, demangled or raw.
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter()
+            .find(|s| (s.name == name) || (s.raw == name))
+    }
+
+    /// Returns the symbol that contains the given address.
+    pub fn symbolat(&self, address: u32) -> Option<&Symbol> {
+        self.symbols.iter()
+            .filter(|s| s.kind != SymbolKind::Other)
+            .find(|s| (address >= s.address) && (address < s.address.saturating_add(s.size.max(1))))
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Demangled name of the symbol.
+    pub name: String,
+
+    /// Raw name of the symbol.
+    pub raw: String,
+
+    /// Address of the symbol.
+    pub address: u32,
+
+    /// Size of the symbol in bytes.
+    pub size: u32,
+
+    /// Kind of symbol.
+    pub kind: SymbolKind,
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Load address of the segment.
+    pub address: u32,
+
+    /// Contents of the segment.
+    pub data: Vec<u8>,
+
+    /// Sections stored in the segment.
+    pub sections: Vec<Section>,
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Name of the section.
+    pub name: String,
+
+    /// Load address of the section.
+    pub address: u32,
+
+    /// Size of the section in bytes.
+    pub size: u32,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Data,
+    Other,
+}
+
+
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The file could not be read.
+    Io(String),
+
+    /// The file is not a valid ELF.
+    Parse(String),
+
+    /// The ELF has an unsupported class.
+    Class(u8),
+
+    /// The file is not an ELF, e.g. a raw binary image.
+    NotElf,
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "Could not read ELF file: {}", e),
+            Error::Parse(e) => write!(f, "Could not parse ELF file: {}", e),
+            Error::Class(ELFCLASS64) => write!(f, "Unsupported ELF class: 64 bit ELF files cannot be flashed into 32 bit targets"),
+            Error::Class(class) => write!(f, "Unsupported ELF class {}: only 32 bit ELF files are supported", class),
+            Error::NotElf => write!(f, "The file is not an ELF file, raw binary images cannot be loaded as they have no load address"),
+        }
+    }
+}
@@ -171,14 +171,24 @@ impl iced::Application for Application {
                 let project = self.rootdb.project().expect("Project Database interfaces aquisition failed");
 
                 // Get the project database interface and update the views that need it.
-                let commands = self.views.2.update(
-                    msg::DatabaseViewMessage::InterfacesCreated(
-                        chip.clone(),
-                        theme.clone(),
-                        regex.clone(),
-                        project.clone()
-                    )
-                );
+                let commands = Command::batch([
+                    self.views.0.update(
+                        msg::ProbeMessage::ProjectInterface( project.clone() )
+                    ),
+
+                    self.views.0.update(
+                        msg::ProbeMessage::ChipInterface( chip.clone() )
+                    ),
+
+                    self.views.2.update(
+                        msg::DatabaseViewMessage::InterfacesCreated(
+                            chip.clone(),
+                            theme.clone(),
+                            regex.clone(),
+                            project.clone()
+                        )
+                    ),
+                ]);
 
                 // Store a copy of the database interfaces for the future.
                 self.interfaces = Some( ( chip, theme, regex, project, ) );
@@ -218,6 +228,9 @@ impl iced::Application for Application {
             // Propagate the message to the probe view.
             Message::Probe(msg) => return self.views.0.update(msg),
 
+            // Select the project in the probe view.
+            Message::ProjectSelected(name) => return self.views.0.update( msg::ProbeMessage::ProjectSelected(name) ),
+
             // Propagate the message to the database view.
             Message::Database(msg) => return self.views.2.update(msg),
 
@@ -272,7 +285,7 @@ impl iced::Application for Application {
 
     fn subscription(&self) -> Subscription<Message> {
         //msg::global::subscription()
-        Subscription::none()
+        self.views.0.subscription()
     }
 }
 
@@ -102,6 +102,9 @@ pub enum ProjectViewMessage {
     /// The chip of one of the currently editing project's binary was updated.
     TargetBinary(usize, String),
 
+    /// The SVD file of one of the currently editing project's targets was updated.
+    TargetSvd(usize, String),
+
     /// Update of an existing entry.
     Update,
 
@@ -12,6 +12,20 @@ pub use self::global::MessageCenter;
 pub use self::settings::SettingsMessage;
 
 
+
+use crate::{
+    database::{ ChipCommand, ChipResponse, ProjectCommand, ProjectResponse },
+    elf::ElfFile,
+    project::ProjectSerial,
+};
+
+use database::common::DBInterface;
+
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+
 #[derive(Debug, Clone)]
 pub enum Message {
     /// The topbar Database Button was pressed.
@@ -55,11 +69,250 @@ pub enum Message {
 
 #[derive(Debug, Clone)]
 pub enum ProbeMessage {
+    /// The interface to the project database was created.
+    ProjectInterface(Box<DBInterface<ProjectCommand, ProjectResponse>>),
+
+    /// The interface to the chip database was created.
+    ChipInterface(Box<DBInterface<ChipCommand, ChipResponse>>),
+
+    /// Contains the `Arc` to the project database data.
+    ProjectDatabase(Arc<RwLock<Vec<ProjectSerial>>>),
+
+    /// A project was selected.
+    ProjectSelected(String),
+
+    /// A probe was selected.
+    ProbeSelected(String),
+
+    /// A target of the current project was selected.
+    TargetSelected(String),
+
+    /// Connects to the selected probe and target.
+    Connect,
+
+    /// Disconnects from the open probe.
+    Disconnect,
+
+    /// The probe was attached to the target. Contains the probe and the command channel of the session.
+    Connected(probe_rs::DebugProbeInfo, crate::probe::CommandSender),
+
+    /// The probe could not be attached to the target.
+    ConnectFailed(String),
+
+    /// The core dump file input changed.
+    DumpFileChanged(String),
+
+    /// Saves the state of the halted core into the core dump file.
+    SaveCoreDump,
+
+    /// The core dump was written. Contains its path or the error.
+    CoreDumpSaved(Result<String, String>),
+
+    /// Opens the core dump file as a read-only target.
+    OpenCoreDump,
+
+    /// The core dump was loaded. Contains its path, the firmware ELF and the command channel of the session.
+    Offline(String, Option<std::path::PathBuf>, crate::probe::CommandSender),
+
+    /// A probe was plugged in.
+    ProbeAdded(probe_rs::DebugProbeInfo),
+
+    /// A probe was unplugged.
+    ProbeRemoved(probe_rs::DebugProbeInfo),
+
+    /// The update of the current project failed.
+    ProjectUpdateFailed,
+
+    /// The ELF of the current target was loaded.
+    ElfLoaded(Arc<ElfFile>),
+
+    /// The ELF of the current target could not be loaded.
+    ElfFailed(crate::elf::Error),
+
+    /// The breakpoint location input changed.
+    BreakpointInputChanged(String),
+
+    /// Adds a breakpoint at the location in the breakpoint input.
+    AddBreakpoint,
+
+    /// Enables or disables the breakpoint in the given row.
+    ToggleBreakpoint(usize, bool),
+
+    /// Removes the breakpoint in the given row.
+    RemoveBreakpoint(usize),
+
+    /// The watchpoint location input changed.
+    WatchpointInputChanged(String),
+
+    /// The watchpoint size input changed.
+    WatchpointSizeChanged(String),
+
+    /// The watchpoint kind was selected.
+    WatchKind(crate::probe::WatchKind),
+
+    /// Adds a watchpoint at the location in the watchpoint input.
+    AddWatchpoint,
+
+    /// Removes the watchpoint in the given comparator.
+    RemoveWatchpoint(usize),
+
+    /// The live watch location input changed.
+    LiveInputChanged(String),
+
+    /// The datatype of the new live watch was selected.
+    LiveDatatype(crate::probe::Datatype),
+
+    /// The sampling period input changed.
+    LivePeriodChanged(String),
+
+    /// Adds a live watch at the location in the live watch input.
+    AddLiveWatch,
+
+    /// Removes the live watch in the given row.
+    RemoveLiveWatch(usize),
+
+    /// Starts or stops the sampling of the live watches.
+    LiveSampling(bool),
+
+    /// A sample of the live watches.
+    LiveSample(crate::probe::LiveSample),
+
+    /// Pauses or resumes the plot of the live watches.
+    PlotPause(bool),
+
+    /// Zooms the time axis of the plot in or out.
+    PlotZoom(bool),
+
+    /// Clears the plot of the live watches.
+    PlotClear,
+
+    /// The snapshot name input changed.
+    SnapshotNameChanged(String),
+
+    /// Takes a snapshot of the read range.
+    SnapshotRange,
+
+    /// Takes a snapshot of all the RAM regions of the target.
+    SnapshotRam,
+
+    /// Removes the snapshot in the given row.
+    RemoveSnapshot(usize),
+
+    /// The earlier snapshot of the comparison was selected.
+    SnapshotBefore(String),
+
+    /// The later snapshot of the comparison was selected.
+    SnapshotAfter(String),
+
+    /// Compares the selected snapshots.
+    DiffSnapshots,
+
+    /// The SVD of the current target was loaded.
+    SvdLoaded(Arc<crate::svd::Device>),
+
+    /// The SVD of the current target could not be loaded.
+    SvdFailed(crate::svd::Error),
+
+    /// Expands or collapses the peripheral in the given row.
+    SelectPeripheral(usize),
+
+    /// Expands or collapses the fields of the register in the given row of the selected peripheral.
+    ToggleRegister(usize),
+
+    /// Reads the registers of the selected peripheral.
+    ReadPeripheral,
+
+    /// A named value was chosen for a field. Contains the register row, the field row and the name.
+    FieldChoice(usize, usize, String),
+
+    /// The value input of a field changed. Contains the register row, the field row and the input.
+    FieldInputChanged(usize, usize, String),
+
+    /// Writes the value input into the field in the given register and field rows.
+    WriteField(usize, usize),
+
+    /// Opens the source file of the faulting instruction.
+    OpenFaultSource,
+
     Datatype(crate::gui::views::probe::common::Datatype),
 
     Load,
+
+    /// A core of the target was selected.
+    CoreSelected(usize),
+
+    /// Cancels the current flash operation.
+    CancelLoad,
+
+    /// Compares the target memory with the ELF of the current target.
+    Verify,
+
+    /// Progress of the current flash operation.
+    Flash(crate::probe::FlashEvent),
+
+    /// The current flash operation finished with the given response.
+    FlashDone(crate::probe::Response),
+
+    /// Attaches to the RTT control block of the target.
+    RttAttach,
+
+    /// Event of the current RTT session.
+    Rtt(crate::probe::RttEvent),
+
+    /// Selects the RTT channel shown in the console.
+    RttChannel(usize),
+
+    /// The RTT input line changed.
+    RttInputChanged(String),
+
+    /// Sends the RTT input line to the target.
+    RttSend,
+
+    /// Clears the text of the selected RTT channel.
+    RttClear,
+
+    /// Enables or disables semihosting.
+    Semihosting(bool),
+
+    /// Event of the semihosting host.
+    Semihost(crate::probe::SemihostEvent),
+
+    /// Shows the semihosting console.
+    SemihostTab,
+
+    /// Starts or stops the SWO trace.
+    Trace(bool),
+
+    /// Decoded packets of the SWO trace.
+    TracePackets(Vec<crate::probe::TracePacket>),
+
+    /// Shows the text of an ITM stimulus port, or the timeline of the trace events.
+    TraceTab(Option<u8>),
+
+    /// The trace clock input changed.
+    TraceClockChanged(String),
+
+    /// The SWO baud rate input changed.
+    TraceBaudChanged(String),
+
+    /// The trace capture file input changed.
+    TraceFileChanged(String),
+
+    /// Decodes the trace capture file.
+    TraceOpen,
+
+    /// The trace capture file was decoded. Contains the path and its packets.
+    TraceDecoded(String, Result<Vec<crate::probe::TracePacket>, String>),
+
+    /// Minimum level of the defmt log entries shown.
+    LogLevel(crate::defmt::Level),
+
+    /// The defmt module filter changed.
+    LogModuleChanged(String),
+
     Stop,
     Reset,
+    ResetHalt,
     Run,
     Step,
     Dump,
@@ -77,9 +330,21 @@ pub enum ProbeMessage {
     ReadRangeEAddressChanged(String),
     NewReadRangeEAddress,
 
+    /// The value input of the register in the given row changed.
+    RegisterValueChanged(usize, String),
+
+    /// Writes the value input of the register in the given row.
+    WriteRegister(usize),
+
     /// An indication that the themes can be loaded now.
     ThemesAvailable(usize),
 
+    /// Periodic update of the core status.
+    Tick,
+
+    /// A response of the open probe.
+    Response(crate::probe::Response),
+
     /// A message to update the project database.
     UpdateProjectDatabase,
 }
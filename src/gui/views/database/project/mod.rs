@@ -232,6 +232,10 @@ impl ProjectDatabaseView {
                 (self.project.targets[i].2).1 = s;
             },
 
+            ProjectViewMessage::TargetSvd(i, s) => {
+                (self.project.targets[i].3).1 = s;
+            },
+
             ProjectViewMessage::UpdateDatabase => {
                 // Get read permission.
                 let projects = self.projects.blocking_read();
@@ -429,7 +433,7 @@ impl ProjectDatabaseView {
 
                 let targets = self.project.targets.iter_mut()
                     .enumerate()
-                    .fold(scrollable, |col, (i, (name, target, binary, button))| {
+                    .fold(scrollable, |col, (i, (name, target, binary, svd, button))| {
                         let nameinput = TextInput::new(
                                 &mut name.0,
                                 "Target name...",
@@ -460,6 +464,16 @@ impl ProjectDatabaseView {
                             .size(16)
                             .style(inputstyle.clone());
 
+                        let svdinput = TextInput::new(
+                                &mut svd.0,
+                                "Target SVD file...",
+                                &svd.1,
+                                move |s| { Message::Database( DatabaseViewMessage::Project( ProjectViewMessage::TargetSvd(i, s) ) ) }
+                            )
+                            .padding(5)
+                            .size(16)
+                            .style(inputstyle.clone());
+
                         let remove = Button::new(button, Text::new("Remove").size(18))
                             .on_press( Message::Database( DatabaseViewMessage::Project( ProjectViewMessage::RemoveTarget(i) ) ) )
                             .style(buttonstyle);
@@ -472,6 +486,7 @@ impl ProjectDatabaseView {
                             .push(nameinput)
                             .push(chipinput)
                             .push(binaryinput)
+                            .push(svdinput)
                             .push(remove);
 
                         col.push( Container::new(inner).style(bgstyle.clone()) )
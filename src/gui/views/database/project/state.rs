@@ -69,7 +69,7 @@ pub struct ProjectState {
     pub(super) scroll: scrollable::State,
 
     /// List of internal states of the targets.
-    pub(super) targets: Vec<(TextInputPair, TextInputPair, TextInputPair, button::State)>,
+    pub(super) targets: Vec<(TextInputPair, TextInputPair, TextInputPair, TextInputPair, button::State)>,
 }
 
 impl ProjectState {
@@ -90,6 +90,7 @@ impl ProjectState {
                 (text_input::State::new(), String::new()),
                 (text_input::State::new(), String::new()),
                 (text_input::State::new(), String::new()),
+                (text_input::State::new(), String::new()),
                 button::State::new(),
             )
         );
@@ -122,7 +123,7 @@ impl ProjectState {
 
         // Build the targets.
         let targets = self.targets.iter()
-            .fold(Vec::new(), |mut vec, (name, target, binary, _)| {
+            .fold(Vec::new(), |mut vec, (name, target, binary, svd, _)| {
                 // Assert the minimum information is present.
                 if !((name.1.len() == 0) || (target.1.len() == 0) || (target.1.len() == 0)) {
                     // Build the target information.
@@ -130,6 +131,7 @@ impl ProjectState {
                         name: name.1.clone(),
                         target: target.1.clone(),
                         binary: binary.1.clone(),
+                        svd: svd.1.clone(),
                     };
 
                     // Append it to the array.
@@ -148,6 +150,14 @@ impl ProjectState {
             },
 
             targets,
+
+            breakpoints: Vec::new(),
+
+            watches: Vec::new(),
+
+            snapshots: Vec::new(),
+
+            path: None,
         })
     }
 }
@@ -165,12 +175,14 @@ impl core::convert::From<ProjectSerial> for ProjectState {
                         name,
                         target,
                         binary,
+                        svd,
                     } = target;
 
                     (
                         (text_input::State::new(), name.clone()),
                         (text_input::State::new(), target.clone()),
                         (text_input::State::new(), binary.clone()),
+                        (text_input::State::new(), svd.clone()),
                         button::State::new(),
                     )
                 }).collect()
@@ -191,12 +203,14 @@ impl core::convert::From<&ProjectSerial> for ProjectState {
                         name,
                         target,
                         binary,
+                        svd,
                     } = target;
 
                     (
                         (text_input::State::new(), name.clone()),
                         (text_input::State::new(), target.clone()),
                         (text_input::State::new(), binary.clone()),
+                        (text_input::State::new(), svd.clone()),
                         button::State::new(),
                     )
                 }).collect()
@@ -0,0 +1,33 @@
+//! Backtrace of the probe GUI view.
+
+
+
+use crate::{
+    gui::msg::Message,
+
+    probe::{ Frame, FrameKind },
+};
+
+use iced::{ Column, Text };
+
+use super::CHANGED;
+
+
+
+/// Builds the backtrace of the halted core.
+pub(super) fn view<'a>(frames: &[Frame]) -> Column<'a, Message> {
+    let mut column = Column::new()
+        .push( Text::new( format!("Backtrace ({} frames):", frames.len()) ).size(14) );
+
+    // Frames found without CFI may be wrong.
+    for (i, frame) in frames.iter().enumerate() {
+        let text = Text::new( format!("  #{} {}", i, frame) ).size(14);
+
+        column = column.push( match frame.kind {
+            FrameKind::Exception(_) | FrameKind::Heuristic => text.color(CHANGED),
+            _ => text,
+        });
+    }
+
+    column
+}
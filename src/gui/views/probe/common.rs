@@ -2,24 +2,7 @@
 
 
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Datatype {
-    Int8,
-    UInt8,
-    Int16,
-    UInt16,
-    Int32,
-    UInt32,
-    Int64,
-    UInt64,
-
-    Float16,
-    Float32,
-
-    BFloat16,
-
-    Char,
-}
+pub use crate::probe::Datatype;
 
 
 impl core::fmt::Display for Datatype {
@@ -62,3 +45,19 @@ pub const DATATYPES: [Datatype; 12] = [
 
     Datatype::Char,
 ];
+
+
+
+/// Parses an address or a value in hexadecimal, binary, octal or decimal notation.
+pub fn parse(s: &str) -> Option<u32> {
+    let s = s.trim();
+
+    let (digits, radix) = match s.get(..2) {
+        Some("0x") | Some("0X") => (&s[2..], 16),
+        Some("0b") | Some("0B") => (&s[2..], 2),
+        Some("0o") | Some("0O") => (&s[2..], 8),
+        _ => (s, 10),
+    };
+
+    u32::from_str_radix(digits, radix).ok()
+}
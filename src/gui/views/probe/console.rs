@@ -0,0 +1,712 @@
+//! Console of the probe GUI view.
+
+
+
+use crate::{
+    defmt::{ self, Decoder, Level, LogEntry, LEVELS },
+
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::{ Command as ProbeCommand, RttControl, RttEvent, SemihostEvent },
+};
+
+use iced::{
+    Command, Column, Container, Element, Row,
+
+    Align, Length,
+
+    Checkbox, Scrollable, Text, PickList, TextInput,
+
+    button::{ self, Button },
+    pick_list, scrollable, text_input,
+};
+
+use std::sync::Arc;
+
+use tokio::sync::{
+    mpsc::{ self, UnboundedReceiver }, Mutex,
+};
+
+use tracing::{ info, warn };
+
+use super::{
+    trace::Trace, ProbeView, CHANGED,
+};
+
+
+
+/// Maximum number of characters kept for each channel.
+const MAXTEXT: usize = 64 * 1024;
+
+/// Maximum number of defmt log entries kept.
+const MAXENTRIES: usize = 4096;
+
+
+
+/// Output shown in the console.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Tab {
+    /// The selected RTT up channel.
+    Rtt,
+
+    /// The semihosting console.
+    Semihosting,
+
+    /// The text of an ITM stimulus port.
+    Itm(u8),
+
+    /// The timeline of the trace events.
+    Timeline,
+}
+
+
+
+pub(super) struct Console {
+    /// Identifier of the RTT session.
+    pub(super) id: u64,
+
+    /// Events of the RTT session.
+    pub(super) events: Arc<Mutex<UnboundedReceiver<RttEvent>>>,
+
+    /// Attached control block.
+    pub(super) control: Option<RttControl>,
+
+    /// Received text of each up channel, as (channel number, text).
+    channels: Vec<(usize, String)>,
+
+    /// Index of the selected up channel.
+    pub(super) selected: usize,
+
+    /// Indicates that the session was lost.
+    pub(super) lost: bool,
+
+    /// defmt decoder of the ELF and the number of the channel it decodes.
+    decoder: Option<(Option<usize>, Decoder)>,
+
+    /// Decoded defmt log entries.
+    entries: Vec<LogEntry>,
+
+    /// Error of the defmt table of the ELF.
+    defmterror: Option<String>,
+}
+
+impl Console {
+    /// Creates the console of a new RTT session with the defmt decoder of the ELF.
+    pub(super) fn new(id: u64, events: UnboundedReceiver<RttEvent>, decoder: Result<Option<Decoder>, defmt::Error>) -> Self {
+        let (decoder, defmterror) = match decoder {
+            Ok(decoder) => (decoder.map(|d| (None, d)), None),
+            Err(e) => (None, Some( e.to_string() )),
+        };
+
+        Console {
+            id,
+            events: Arc::new( Mutex::new(events) ),
+            control: None,
+            channels: Vec::new(),
+            selected: 0,
+            lost: false,
+            decoder,
+            entries: Vec::new(),
+            defmterror,
+        }
+    }
+
+    /// Sets the attached control block and creates one buffer for each up channel.
+    pub(super) fn attached(&mut self, control: RttControl) {
+        self.channels = control.up.iter()
+            .map(|c| (c.number, String::new()))
+            .collect();
+
+        // defmt uses the channel with its name, or the first channel.
+        if let Some((channel, _)) = &mut self.decoder {
+            *channel = control.up.iter()
+                .find(|c| c.name.as_deref() == Some(defmt::CHANNEL))
+                .or(control.up.first())
+                .map(|c| c.number);
+        }
+
+        self.selected = 0;
+        self.control = Some(control);
+    }
+
+    /// Updates the console with an event of the session.
+    pub(super) fn update(&mut self, event: RttEvent) {
+        match event {
+            RttEvent::Data(number, data) if self.isdefmt(number) => if let Some((_, decoder)) = &mut self.decoder {
+                self.entries.extend( decoder.received(&data) );
+
+                if self.entries.len() > MAXENTRIES {
+                    let cut = self.entries.len() - MAXENTRIES;
+                    self.entries.drain(..cut);
+                }
+            },
+
+            RttEvent::Data(number, data) => if let Some((_, text)) = self.channels.iter_mut().find(|(n, _)| *n == number) {
+                append(text, &data);
+            },
+
+            RttEvent::Lost => self.lost = true,
+        }
+    }
+
+    /// Returns the labels of the up channels.
+    pub(super) fn labels(&self) -> Vec<String> {
+        match &self.control {
+            Some(control) => control.up.iter().map(|c| c.label()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the text of the selected up channel.
+    /// defmt entries are filtered by their minimum level and a part of their module path.
+    pub(super) fn text(&self, level: Option<Level>, module: &str) -> String {
+        match self.channels.get(self.selected) {
+            Some((number, text)) if !self.isdefmt(*number) => return text.clone(),
+            None => return String::new(),
+            _ => (),
+        }
+
+        self.entries.iter()
+            .filter(|e| match (e.level, level) {
+                (Some(l), Some(min)) => l >= min,
+                _ => true,
+            })
+            .filter(|e| module.is_empty() || e.module().map(|m| m.contains(module)).unwrap_or(false))
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Indicates if the given up channel is decoded as defmt.
+    pub(super) fn isdefmt(&self, number: usize) -> bool {
+        match &self.decoder {
+            Some((channel, _)) => *channel == Some(number),
+            _ => false,
+        }
+    }
+
+    /// Indicates if the selected up channel is decoded as defmt.
+    pub(super) fn selecteddefmt(&self) -> bool {
+        match self.channels.get(self.selected) {
+            Some((number, _)) => self.isdefmt(*number),
+            _ => false,
+        }
+    }
+
+    /// Returns the notice of the defmt decoder: a table error or frames that do not match the ELF.
+    pub(super) fn defmtnotice(&self) -> Option<String> {
+        if let Some(e) = &self.defmterror {
+            return Some( e.clone() );
+        }
+
+        match &self.decoder {
+            Some((_, decoder)) if decoder.malformed() > 0 => Some( format!("{} defmt frames do not match the ELF, the target runs a different firmware", decoder.malformed()) ),
+            _ => None,
+        }
+    }
+
+    /// Returns the down channel paired with the selected up channel.
+    /// Falls back to the first down channel.
+    pub(super) fn down(&self) -> Option<usize> {
+        let control = self.control.as_ref()?;
+        let up = self.channels.get(self.selected).map(|(n, _)| *n);
+
+        control.down.iter()
+            .find(|c| Some(c.number) == up)
+            .or(control.down.first())
+            .map(|c| c.number)
+    }
+
+    /// Clears the text of the selected up channel.
+    pub(super) fn clear(&mut self) {
+        if self.selecteddefmt() {
+            self.entries.clear();
+        }
+
+        if let Some((_, text)) = self.channels.get_mut(self.selected) {
+            text.clear();
+        }
+    }
+}
+
+
+
+/// Widget states of the console.
+pub(super) struct ConsoleState {
+    /// Output scroll state.
+    scroll: scrollable::State,
+
+    /// Button states of the up channel tabs.
+    pub(super) channels: Vec<button::State>,
+
+    /// State of the RTT attach button.
+    attach: button::State,
+
+    /// State of the clear button.
+    clear: button::State,
+
+    /// State of the semihosting tab button.
+    semihost: button::State,
+
+    /// Input line state.
+    input: text_input::State,
+
+    /// Current value of the input line.
+    inputval: String,
+
+    /// State of the send button.
+    send: button::State,
+
+    /// defmt level picklist state.
+    loglevel: pick_list::State<Level>,
+
+    /// Minimum level of the defmt entries shown.
+    sellevel: Option<Level>,
+
+    /// defmt module filter state.
+    module: text_input::State,
+
+    /// Current value of the defmt module filter.
+    moduleval: String,
+}
+
+impl ConsoleState {
+    /// Creates the widget states of the console.
+    pub(super) fn new() -> Self {
+        ConsoleState {
+            scroll: Default::default(),
+            channels: Vec::new(),
+            attach: Default::default(),
+            clear: Default::default(),
+            semihost: Default::default(),
+            input: Default::default(),
+            inputval: String::new(),
+            send: Default::default(),
+            loglevel: Default::default(),
+            sellevel: Some(Level::Trace),
+            module: Default::default(),
+            moduleval: String::new(),
+        }
+    }
+
+    /// Builds the console with its tabs and the output of the selected tab.
+    /// The tabs and the configuration row of the trace are built with the trace session.
+    pub(super) fn view<'a>(&'a mut self, connected: bool, tab: Tab, console: Option<&Console>, semihost: Option<&Semihost>, trace: Option<&Trace>, tracewidgets: (Vec<Element<'a, Message>>, Row<'a, Message>)) -> Element<'a, Message> {
+        let (tracetabs, tracerow) = tracewidgets;
+
+        let labels = console.map(|c| c.labels()).unwrap_or_default();
+        let selected = match tab {
+            Tab::Rtt => console.map(|c| c.selected),
+            _ => None,
+        };
+        let down = console.and_then(|c| c.down()).is_some();
+
+        // Status of the RTT session.
+        let status = match console {
+            None => String::from("RTT not attached"),
+            Some(console) => match (&console.control, console.lost) {
+                (_, true) => String::from("RTT session lost"),
+                (None, _) => String::from("Searching for the RTT control block..."),
+                (Some(control), _) => format!("RTT control block at 0x{:08X}", control.address),
+            },
+        };
+
+        let attach = Button::new(&mut self.attach, Text::new("Attach RTT").size(14))
+            .height(Length::Shrink);
+
+        let attach = match connected {
+            true => attach.on_press(Message::Probe( ProbeMessage::RttAttach )),
+            _ => attach,
+        };
+
+        let clear = Button::new(&mut self.clear, Text::new("Clear").size(14))
+            .height(Length::Shrink);
+
+        let clear = match selected.is_some() || (tab != Tab::Rtt) {
+            true => clear.on_press(Message::Probe( ProbeMessage::RttClear )),
+            _ => clear,
+        };
+
+        // One tab for each up channel.
+        let tabs = self.channels.iter_mut()
+            .zip(labels.into_iter())
+            .enumerate()
+            .fold(Row::new().spacing(2), |row, (i, (state, label))| {
+                let label = match selected == Some(i) {
+                    true => format!("[{}]", label),
+                    _ => label,
+                };
+
+                let button = Button::new(state, Text::new(label).size(14))
+                    .on_press(Message::Probe( ProbeMessage::RttChannel(i) ));
+
+                row.push(button)
+            });
+
+        // Semihosting toggle and its tab.
+        let semihosting = Checkbox::new(
+            semihost.is_some(),
+            "Semihosting",
+            |b| { Message::Probe( ProbeMessage::Semihosting(b) ) }
+        )
+        .size(14)
+        .text_size(14);
+
+        let tabs = match semihost.is_some() {
+            true => {
+                let label = match tab {
+                    Tab::Semihosting => "[Semihosting]",
+                    _ => "Semihosting",
+                };
+
+                let button = Button::new(&mut self.semihost, Text::new(label).size(14))
+                    .on_press(Message::Probe( ProbeMessage::SemihostTab ));
+
+                tabs.push(button)
+            },
+
+            _ => tabs,
+        };
+
+        let tabs = tracetabs.into_iter()
+            .fold(tabs, |row, button| row.push(button));
+
+        let mut header = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(attach)
+            .push(semihosting)
+            .push(clear)
+            .push(tabs)
+            .push(Text::new(status).size(14));
+
+        // Filters of the defmt channel.
+        if console.map(|c| c.selecteddefmt()).unwrap_or(false) {
+            let level = PickList::new(
+                &mut self.loglevel,
+                &LEVELS[..],
+                self.sellevel.clone(),
+                |l| { Message::Probe( ProbeMessage::LogLevel(l) ) }
+            )
+            .padding(4)
+            .width(Length::Shrink);
+
+            let module = TextInput::new(
+                &mut self.module,
+                "Module",
+                &self.moduleval,
+                |s| { Message::Probe( ProbeMessage::LogModuleChanged(s) ) }
+            )
+            .padding(5)
+            .size(14)
+            .width(Length::Units(200));
+
+            header = header.push(level).push(module);
+        }
+
+        // Text of the selected tab.
+        let text = match (tab, semihost, console, trace) {
+            (Tab::Semihosting, Some(semihost), _, _) => Text::new( semihost.text() ).size(14),
+            (Tab::Rtt, _, Some(console), _) => Text::new( console.text(self.sellevel, &self.moduleval) ).size(14),
+            (Tab::Itm(port), _, _, Some(trace)) => Text::new( trace.port(port) ).size(14),
+            (Tab::Timeline, _, _, Some(trace)) => Text::new( trace.timeline() ).size(14),
+            _ => Text::new("").size(14),
+        };
+
+        // Problems with the defmt table, the exit of a semihosted application or lost trace packets.
+        let notice = match (tab, semihost, console, trace) {
+            (Tab::Semihosting, Some(semihost), _, _) => semihost.exit(),
+            (Tab::Rtt, _, Some(console), _) => console.defmtnotice(),
+            (Tab::Itm(_), _, _, Some(trace)) | (Tab::Timeline, _, _, Some(trace)) => trace.notice(),
+            _ => None,
+        }
+        .map(|n| Text::new(n).size(14).color(CHANGED));
+
+        let output = Scrollable::new(&mut self.scroll)
+            .padding(5)
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .scrollbar_margin(2)
+            .push(text);
+
+        // Input line of the down channel.
+        let input = TextInput::new(
+            &mut self.input,
+            "Send to the target",
+            &self.inputval,
+            |s| { Message::Probe( ProbeMessage::RttInputChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Fill);
+
+        let input = match down {
+            true => input.on_submit(Message::Probe( ProbeMessage::RttSend )),
+            _ => input,
+        };
+
+        let send = Button::new(&mut self.send, Text::new("Send").size(14))
+            .height(Length::Shrink);
+
+        let send = match down {
+            true => send.on_press(Message::Probe( ProbeMessage::RttSend )),
+            _ => send,
+        };
+
+        let line = Row::new()
+            .spacing(5)
+            .push(input)
+            .push(send);
+
+        let mut column = Column::new()
+            .spacing(5)
+            .push(header)
+            .push(tracerow);
+
+        if let Some(notice) = notice {
+            column = column.push(notice);
+        }
+
+        let column = column
+            .push(output)
+            .push(line);
+
+        Container::new(column)
+            .height(Length::FillPortion(15))
+            .width(Length::Fill)
+            .into()
+    }
+}
+
+
+
+pub(super) struct Semihost {
+    /// Identifier of the semihosting session.
+    pub(super) id: u64,
+
+    /// Events of the semihosting host.
+    pub(super) events: Arc<Mutex<UnboundedReceiver<SemihostEvent>>>,
+
+    /// Console output of the target.
+    text: String,
+
+    /// Exit of the application and its exit code, if reported.
+    exit: Option<Option<u32>>,
+}
+
+impl Semihost {
+    /// Creates the console of a new semihosting session.
+    pub(super) fn new(id: u64, events: UnboundedReceiver<SemihostEvent>) -> Self {
+        Semihost {
+            id,
+            events: Arc::new( Mutex::new(events) ),
+            text: String::new(),
+            exit: None,
+        }
+    }
+
+    /// Updates the console with an event of the host.
+    pub(super) fn update(&mut self, event: SemihostEvent) {
+        match event {
+            SemihostEvent::Output(data) => append(&mut self.text, &data),
+
+            SemihostEvent::Exit(code) => self.exit = Some(code),
+        }
+    }
+
+    /// Returns the console output of the target.
+    pub(super) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the description of the exit of the application.
+    pub(super) fn exit(&self) -> Option<String> {
+        match self.exit? {
+            Some(code) => Some( format!("Application exited with code {}", code) ),
+            None => Some( String::from("Application stopped") ),
+        }
+    }
+
+    /// Clears the console output.
+    pub(super) fn clear(&mut self) {
+        self.text.clear();
+        self.exit = None;
+    }
+}
+
+
+
+/// Appends the received bytes to the text of a console, dropping the oldest text above `MAXTEXT`.
+pub(super) fn append(text: &mut String, data: &[u8]) {
+    text.push_str( &String::from_utf8_lossy(data) );
+
+    if text.len() > MAXTEXT {
+        let mut cut = text.len() - MAXTEXT;
+
+        while !text.is_char_boundary(cut) {
+            cut += 1;
+        }
+
+        text.drain(..cut);
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the RTT session and the console.
+    pub(super) fn updateconsole(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::RttAttach => {
+                if self.probe.is_none() {
+                    warn!(origin="app", view="probe", "Cannot attach to RTT without an open probe");
+                    return Command::none();
+                }
+
+                // Use the control block symbol of the ELF or scan the RAM.
+                let address = self.elf.as_ref()
+                    .and_then(|elf| elf.symbol("_SEGGER_RTT"))
+                    .map(|symbol| symbol.address);
+
+                match address {
+                    Some(address) => info!(origin="app", view="probe", "Attaching to RTT control block at 0x{:08X}", address),
+                    _ => info!(origin="app", view="probe", "No '_SEGGER_RTT' symbol, scanning the RAM for the RTT control block"),
+                }
+
+                // Decode defmt frames with the table of the ELF.
+                let decoder = match &self.elf {
+                    Some(elf) => Decoder::new(elf),
+                    _ => Ok(None),
+                };
+
+                let (tx, events) = mpsc::unbounded_channel();
+
+                self.consoles += 1;
+                self.console = Some( Console::new(self.consoles, events, decoder) );
+                self.state.console.channels = Vec::new();
+
+                self.request( ProbeCommand::RttAttach(address, tx) )
+            },
+
+            ProbeMessage::Rtt(event) => {
+                if let Some(console) = &mut self.console {
+                    console.update(event);
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::RttChannel(i) => {
+                if let Some(console) = &mut self.console {
+                    console.selected = i;
+                }
+
+                self.tab = Tab::Rtt;
+
+                Command::none()
+            },
+
+            ProbeMessage::RttInputChanged(s) => {
+                self.state.console.inputval = s;
+                Command::none()
+            },
+
+            ProbeMessage::RttSend => {
+                let channel = match self.console.as_ref().and_then(|c| c.down()) {
+                    Some(channel) => channel,
+                    _ => {
+                        warn!(origin="app", view="probe", "The RTT session has no down channel");
+                        return Command::none();
+                    },
+                };
+
+                let mut data = std::mem::take(&mut self.state.console.inputval).into_bytes();
+                data.push(b'\n');
+
+                self.request( ProbeCommand::RttWrite(channel, data) )
+            },
+
+            ProbeMessage::LogLevel(level) => {
+                self.state.console.sellevel = Some(level);
+                Command::none()
+            },
+
+            ProbeMessage::LogModuleChanged(s) => {
+                self.state.console.moduleval = s;
+                Command::none()
+            },
+
+            ProbeMessage::RttClear => {
+                match (self.tab, &mut self.semihost, &mut self.console, &mut self.trace) {
+                    (Tab::Semihosting, Some(semihost), _, _) => semihost.clear(),
+                    (Tab::Rtt, _, Some(console), _) => console.clear(),
+                    (tab, _, _, Some(trace)) => trace.clear(tab),
+                    _ => (),
+                }
+
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Updates the semihosting session and its console.
+    pub(super) fn updatesemihost(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::Semihosting(true) => {
+                if self.probe.is_none() {
+                    warn!(origin="app", view="probe", "Cannot enable semihosting without an open probe");
+                    return Command::none();
+                }
+
+                // Files of the target are kept next to the project.
+                let root = match self.project.as_ref().and_then(|p| p.semihostingdir()) {
+                    Some(root) => root,
+                    _ => {
+                        warn!(origin="app", view="probe", "Semihosting requires a saved project");
+                        return Command::none();
+                    },
+                };
+
+                info!(origin="app", view="probe", "Enabling semihosting with files in {}", root.display());
+
+                let (tx, events) = mpsc::unbounded_channel();
+
+                self.consoles += 1;
+                self.semihost = Some( Semihost::new(self.consoles, events) );
+                self.tab = Tab::Semihosting;
+
+                self.request( ProbeCommand::Semihosting( Some( (root, tx) ) ) )
+            },
+
+            ProbeMessage::Semihosting(false) => {
+                self.semihost = None;
+                self.tab = Tab::Rtt;
+
+                self.request( ProbeCommand::Semihosting(None) )
+            },
+
+            ProbeMessage::Semihost(event) => {
+                let exit = matches!(event, SemihostEvent::Exit(_));
+
+                if let Some(semihost) = &mut self.semihost {
+                    semihost.update(event);
+                }
+
+                // The core stays halted at the exit of the application.
+                match exit {
+                    true => self.request( ProbeCommand::Status ),
+                    _ => Command::none(),
+                }
+            },
+
+            ProbeMessage::SemihostTab => {
+                self.tab = Tab::Semihosting;
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+}
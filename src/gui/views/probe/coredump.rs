@@ -0,0 +1,200 @@
+//! Core dumps of the probe GUI view.
+
+
+
+use crate::{
+    elf::ElfFile,
+
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::{ Command as ProbeCommand, CoreDump, OpenProbe },
+};
+
+use iced::{
+    Command, Row,
+
+    Align, Length,
+
+    Text, TextInput,
+
+    button::{ self, Button },
+    text_input,
+};
+
+use std::sync::Arc;
+
+use tracing::{
+    error, info, warn,
+
+    instrument::WithSubscriber,
+};
+
+use super::{ error, ProbeView, CHANGED };
+
+
+
+/// Widget states of the core dump row.
+#[derive(Default)]
+pub(super) struct CoreDumpState {
+    /// Core dump file input state.
+    file: text_input::State,
+
+    /// Current value of the core dump file input.
+    fileval: String,
+
+    /// State of the save core dump button.
+    save: button::State,
+
+    /// State of the open core dump button.
+    open: button::State,
+}
+
+impl CoreDumpState {
+    /// Builds the core dump row with the file of the post-mortem session.
+    /// The buttons are enabled if a core dump can be saved or opened.
+    pub(super) fn view<'a>(&'a mut self, cansave: bool, canopen: bool, offline: Option<&String>) -> Row<'a, Message> {
+        let file = TextInput::new(
+            &mut self.file,
+            "Core dump file",
+            &self.fileval,
+            |s| { Message::Probe( ProbeMessage::DumpFileChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Units(250));
+
+        let save = Button::new(&mut self.save, Text::new("Save core dump").size(14))
+            .height(Length::Shrink);
+
+        let save = match cansave {
+            true => save.on_press(Message::Probe( ProbeMessage::SaveCoreDump )),
+            _ => save,
+        };
+
+        let open = Button::new(&mut self.open, Text::new("Open core dump").size(14))
+            .height(Length::Shrink);
+
+        let open = match canopen {
+            true => open.on_press(Message::Probe( ProbeMessage::OpenCoreDump )),
+            _ => open,
+        };
+
+        let mut row = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(file)
+            .push(save)
+            .push(open);
+
+        if let Some(path) = offline {
+            row = row.push( Text::new( format!("Post-mortem session of {}", path) ).size(14).color(CHANGED) );
+        }
+
+        row
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the saving of core dumps and the post-mortem session.
+    pub(super) fn updatecoredump(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::DumpFileChanged(s) => {
+                self.state.coredump.fileval = s;
+                Command::none()
+            },
+
+            ProbeMessage::SaveCoreDump => {
+                let path = self.state.coredump.fileval.trim().to_string();
+
+                if path.is_empty() {
+                    warn!(origin="app", view="probe", "Enter the file of the core dump");
+                    return Command::none();
+                }
+
+                self.dumppath = Some(path);
+                self.request( ProbeCommand::CoreDump )
+            },
+
+            ProbeMessage::CoreDumpSaved(result) => {
+                match result {
+                    Ok(path) => info!(origin="app", view="probe", "Saved core dump {}", path),
+                    Err(e) => {
+                        error!(origin="app", view="probe", "{}", e);
+                        self.lasterror = Some( error::Error::CoreDump(e) );
+                    },
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::OpenCoreDump => {
+                if self.probe.is_some() || self.connecting {
+                    warn!(origin="app", view="probe", "Disconnect before opening a core dump");
+                    return Command::none();
+                }
+
+                let path = self.state.coredump.fileval.trim().to_string();
+
+                if path.is_empty() {
+                    return Command::none();
+                }
+
+                self.lasterror = None;
+
+                Command::perform(
+                    offline(path).with_current_subscriber(),
+                    |m| { Message::Probe( m ) }
+                )
+            },
+
+            ProbeMessage::Offline(path, elf, channel) => {
+                info!(origin="app", view="probe", "Opened core dump {}", path);
+
+                self.offline = Some(path);
+                self.probe = Some(channel);
+
+                // Use the firmware of the dump if it is not the ELF of the target.
+                let load = match elf {
+                    Some(elf) if self.elf.as_ref().map(|e| *e.path() != elf).unwrap_or(true) => Command::perform(
+                        ElfFile::load(elf).with_current_subscriber(),
+                        |r| match r {
+                            Ok(elf) => Message::Probe( ProbeMessage::ElfLoaded( Arc::new(elf) ) ),
+                            Err(e) => Message::Probe( ProbeMessage::ElfFailed(e) ),
+                        }
+                    ),
+
+                    _ => Command::none(),
+                };
+
+                Command::batch([
+                    self.request( ProbeCommand::Cores ),
+                    self.request( ProbeCommand::Status ),
+                    self.inspect(),
+                    load,
+                ])
+            },
+
+            _ => Command::none(),
+        }
+    }
+}
+
+
+
+/// Async function to load a core dump and spawn the probe command loop over it.
+async fn offline(path: String) -> ProbeMessage {
+    match CoreDump::load( std::path::Path::new(&path) ).await {
+        Ok(dump) => {
+            let elf = dump.elf.clone();
+            let (mut probe, channel) = OpenProbe::offline(dump);
+
+            tokio::spawn( async move { probe.run().await }.with_current_subscriber() );
+
+            ProbeMessage::Offline(path, elf, channel)
+        },
+
+        Err(e) => ProbeMessage::ConnectFailed(e),
+    }
+}
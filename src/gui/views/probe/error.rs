@@ -0,0 +1,63 @@
+//! Errors shown in the command display of the probe GUI view.
+
+
+
+use crate::{ elf, probe, svd };
+
+
+
+#[derive(Clone, Debug)]
+pub(super) enum Error {
+    /// Error reported by the probe.
+    Probe(probe::Error),
+
+    /// The current project could not be saved.
+    ProjectNotSaved,
+
+    /// The ELF of the current target could not be loaded.
+    Elf(elf::Error),
+
+    /// The binary of the current target does not exist.
+    NoBinary(String),
+
+    /// The current target has no ELF.
+    NoElf,
+
+    /// There is no open probe.
+    NoProbe,
+
+    /// The symbol is not in the ELF.
+    NoSymbol(String),
+
+    /// The symbol cannot be resolved without an ELF.
+    SymbolWithoutElf(String),
+
+    /// The SVD of the current target could not be loaded.
+    Svd(svd::Error),
+
+    /// The core dump could not be saved.
+    CoreDump(String),
+}
+
+impl From<probe::Error> for Error {
+    fn from(e: probe::Error) -> Self {
+        Error::Probe(e)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Error::Probe(e) => write!(f, "{}", e),
+            Error::ProjectNotSaved => write!(f, "Could not save the current project"),
+            Error::Elf(e) => write!(f, "{}", e),
+            Error::NoBinary(path) => write!(f, "The binary {} of the target does not exist", path),
+            Error::NoElf => write!(f, "The selected target has no ELF"),
+            Error::NoProbe => write!(f, "Connect to a probe first"),
+            Error::NoSymbol(name) => write!(f, "Symbol {} was not found in the ELF", name),
+            Error::SymbolWithoutElf(name) => write!(f, "Cannot resolve symbol {} without an ELF", name),
+            Error::Svd(e) => write!(f, "{}", e),
+            Error::CoreDump(e) => write!(f, "{}", e),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Fault analysis of the probe GUI view.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::Fault,
+};
+
+use iced::{
+    Command, Column, Row,
+
+    Align, Length,
+
+    Text,
+
+    button::{ self, Button },
+};
+
+use tracing::{ error, info };
+
+use super::{ ProbeView, CHANGED };
+
+
+
+/// Widget states of the fault analysis.
+#[derive(Default)]
+pub(super) struct FaultState {
+    /// State of the open source button.
+    source: button::State,
+}
+
+impl FaultState {
+    /// Builds the explanation of the fault with its registers and a link to the faulting line.
+    pub(super) fn view<'a>(&'a mut self, fault: &Fault) -> Column<'a, Message> {
+        let mut column = Column::new()
+            .push( Text::new( format!("{}:", fault.name()) ).size(14) );
+
+        for cause in fault.explain() {
+            column = column.push( Text::new( format!("  {}", cause) ).size(14).color(CHANGED) );
+        }
+
+        if let Some(r) = fault.registers {
+            column = column.push( Text::new( format!("  CFSR 0x{:08X}  HFSR 0x{:08X}  MMFAR 0x{:08X}  BFAR 0x{:08X}  AFSR 0x{:08X}", r.cfsr, r.hfsr, r.mmfar, r.bfar, r.afsr) ).size(14) );
+        }
+
+        if let Some(frame) = fault.frame {
+            column = column.push( Text::new( format!("  Stacked at 0x{:08X}: {}", frame.address, frame) ).size(14) );
+        }
+
+        // Link to the faulting line when the ELF has DWARF.
+        if let Some(location) = fault.location() {
+            let open = Button::new(&mut self.source, Text::new("Open source").size(14))
+                .on_press(Message::Probe( ProbeMessage::OpenFaultSource ))
+                .height(Length::Shrink);
+
+            column = column.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push( Text::new( format!("  Source: {}", location) ).size(14) )
+                    .push( open )
+            );
+        }
+
+        column
+    }
+}
+
+
+
+impl ProbeView {
+    /// Opens the source of the faulting instruction.
+    pub(super) fn updatefault(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::OpenFaultSource => {
+                if let Some((file, _)) = self.fault.as_ref().and_then(|f| f.source.as_ref()) {
+                    match opener::open(file) {
+                        Err(e) => error!(origin="app", view="probe", "Could not open source file {}: {}", file, e),
+                        _ => info!(origin="app", view="probe", "Opened source file {}", file),
+                    }
+                }
+
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Progress of a flash operation in the probe GUI view.
+
+
+
+use crate::probe::{ FlashEvent, FlashHandle };
+
+use std::sync::Arc;
+
+use tokio::sync::{
+    mpsc::UnboundedReceiver, Mutex,
+};
+
+
+
+pub(super) struct Flashing {
+    /// Identifier of the operation.
+    pub(super) id: u64,
+
+    /// Handle to cancel the operation.
+    pub(super) handle: FlashHandle,
+
+    /// Progress events of the operation.
+    pub(super) events: Arc<Mutex<UnboundedReceiver<FlashEvent>>>,
+
+    /// Current phase of the operation.
+    phase: &'static str,
+
+    /// Total number of bytes to program.
+    total: usize,
+
+    /// Number of bytes erased.
+    erased: usize,
+
+    /// Number of bytes programmed.
+    programmed: usize,
+
+    /// Number of bytes verified.
+    verified: usize,
+}
+
+impl Flashing {
+    /// Creates the progress of a new flash operation.
+    pub(super) fn new(id: u64, handle: FlashHandle, events: UnboundedReceiver<FlashEvent>) -> Self {
+        Flashing {
+            id,
+            handle,
+            events: Arc::new( Mutex::new(events) ),
+            phase: "Starting",
+            total: 0,
+            erased: 0,
+            programmed: 0,
+            verified: 0,
+        }
+    }
+
+    /// Updates the progress with an event of the operation.
+    pub(super) fn update(&mut self, event: FlashEvent) {
+        match event {
+            FlashEvent::Started(total) => self.total = total,
+            FlashEvent::Erasing        => self.phase = "Erasing",
+            FlashEvent::Erased(n)      => self.erased += n as usize,
+            FlashEvent::Programming    => self.phase = "Programming",
+            FlashEvent::Programmed(n)  => self.programmed += n as usize,
+            FlashEvent::Verifying      => self.phase = "Verifying",
+            FlashEvent::Verified(n)    => self.verified += n as usize,
+        }
+    }
+
+    /// Builds the description of the progress.
+    pub(super) fn text(&self) -> String {
+        format!("{}: {} bytes erased, {} / {} programmed, {} / {} verified", self.phase, self.erased, self.programmed.min(self.total), self.total, self.verified, self.total)
+    }
+
+    /// Returns the fraction of the operation completed.
+    pub(super) fn fraction(&self) -> f32 {
+        if self.total == 0 { return 0.0 }
+
+        // Programming and verification take most of the time.
+        let done = self.programmed.min(self.total) + self.verified;
+
+        done as f32 / (2 * self.total) as f32
+    }
+}
@@ -0,0 +1,151 @@
+//! Probe hot-plug detection.
+//! Polls the probe enumeration in the background and reports the probes added and removed.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+    probe,
+};
+
+use iced::Command;
+
+use iced_futures::futures::stream::{unfold, BoxStream};
+
+use iced_native::subscription::{
+    Recipe, Subscription,
+};
+
+use probe_rs::{ DebugProbeInfo, Probe };
+
+use std::{
+    collections::VecDeque,
+    time::Duration,
+};
+
+use tracing::{ debug, error, info };
+
+use super::ProbeView;
+
+
+
+/// Period of the probe enumeration.
+const PERIOD: Duration = Duration::from_millis(1000);
+
+
+
+/// Builds the hot-plug subscription.
+/// The probes already known by the view are given to avoid reporting them again.
+pub(super) fn subscription(known: Vec<DebugProbeInfo>) -> Subscription<Message> {
+    Subscription::from_recipe(HotPlug { known })
+}
+
+/// Returns `true` if both descriptions belong to the same probe.
+pub(super) fn same(a: &DebugProbeInfo, b: &DebugProbeInfo) -> bool {
+    (a.identifier == b.identifier) && (a.vendor_id == b.vendor_id) && (a.product_id == b.product_id) && (a.serial_number == b.serial_number)
+}
+
+/// Builds the display name of a probe.
+pub(super) fn name(info: &DebugProbeInfo) -> String {
+    match &info.serial_number {
+        Some(serial) => format!("{} ({})", info.identifier, serial),
+        _ => info.identifier.clone(),
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the probe list with the probes plugged in and unplugged.
+    /// Tears down the session of an unplugged probe.
+    pub(super) fn updatehotplug(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::ProbeAdded(info) => if !self.probes.iter().any(|p| same(p, &info)) {
+                info!(origin="app", view="probe", "Probe {} is available", name(&info));
+
+                self.probes.push(info);
+                self.probenames = self.probes.iter().map(name).collect();
+            },
+
+            ProbeMessage::ProbeRemoved(info) => {
+                info!(origin="app", view="probe", "Probe {} was removed", name(&info));
+
+                self.probes.retain(|p| !same(p, &info));
+                self.probenames = self.probes.iter().map(name).collect();
+
+                // Keep the selection while the probe is present.
+                if let Some(selected) = &self.selprobe {
+                    if !self.probenames.contains(selected) {
+                        self.selprobe = None;
+                    }
+                }
+
+                // Tear down the session of the removed probe.
+                if let Some(true) = self.connected.as_ref().map(|c| same(c, &info)) {
+                    error!(origin="app", view="probe", "The connected probe {} was unplugged", name(&info));
+
+                    self.disconnect();
+                    self.lasterror = Some( probe::Error::Disconnected.into() );
+                }
+            },
+
+            _ => (),
+        }
+
+        Command::none()
+    }
+}
+
+
+
+pub(super) struct HotPlug {
+    /// Probes present when the subscription started.
+    known: Vec<DebugProbeInfo>,
+}
+
+impl<H: std::hash::Hasher, I> Recipe<H, I> for HotPlug {
+    type Output = Message;
+
+    fn hash(&self, state: &mut H) {
+        use std::hash::Hash;
+
+        std::any::TypeId::of::<Self>().hash(state);
+    }
+
+    fn stream(self: Box<Self>, _: BoxStream<'static, I>) -> BoxStream<'static, Self::Output> {
+        Box::pin( unfold(
+            (self.known, VecDeque::new()),
+            move |(mut known, mut pending): (Vec<DebugProbeInfo>, VecDeque<ProbeMessage>)| async move {
+                loop {
+                    // Report the pending changes first.
+                    if let Some(msg) = pending.pop_front() {
+                        return Some( (Message::Probe(msg), (known, pending)) );
+                    }
+
+                    tokio::time::sleep(PERIOD).await;
+
+                    // The enumeration blocks on USB access.
+                    let current = match tokio::task::spawn_blocking(Probe::list_all).await {
+                        Err(e) => {
+                            error!(origin="app", view="probe", "Probe enumeration failed: {}", e);
+                            continue;
+                        },
+                        Ok(c) => c,
+                    };
+
+                    for info in current.iter().filter(|c| !known.iter().any(|k| same(k, c))) {
+                        debug!(origin="app", view="probe", "Probe {} plugged in", name(info));
+                        pending.push_back( ProbeMessage::ProbeAdded( info.clone() ) );
+                    }
+
+                    for info in known.iter().filter(|k| !current.iter().any(|c| same(k, c))) {
+                        debug!(origin="app", view="probe", "Probe {} unplugged", name(info));
+                        pending.push_back( ProbeMessage::ProbeRemoved( info.clone() ) );
+                    }
+
+                    known = current;
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,409 @@
+//! Live watch of the probe GUI view.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::{ Command as ProbeCommand, Error, LiveSample, LiveSender, Value },
+
+    project::{ Location, ProjectSerial, WatchInfo },
+};
+
+use iced::{
+    Command, Column, Row,
+
+    Align, Length,
+
+    Checkbox, Scrollable, Text, PickList, TextInput,
+
+    button::{ self, Button },
+    pick_list, scrollable, text_input,
+};
+
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::sync::{
+    mpsc::{ self, UnboundedReceiver }, Mutex,
+};
+
+use tracing::warn;
+
+use super::{
+    common::{ self, Datatype, DATATYPES },
+    ProbeView,
+};
+
+
+
+/// Sampling period used when the period input is empty.
+const DEFAULTPERIOD: Duration = Duration::from_millis(100);
+
+
+
+pub(super) struct Sampling {
+    /// Identifier of the sampling session.
+    pub(super) id: u64,
+
+    /// Samples of the watched variables.
+    pub(super) events: Arc<Mutex<UnboundedReceiver<LiveSample>>>,
+
+    /// Indices of the sampled watches of the project, in the order of the samples.
+    watches: Vec<usize>,
+
+    /// Indicates that the probe accepted the sampling.
+    pub(super) running: bool,
+
+    /// Last value of each sampled watch.
+    values: Vec<Result<Value, Error>>,
+
+    /// Time of the last sample since the start of the sampling.
+    pub(super) time: Duration,
+}
+
+impl Sampling {
+    /// Creates the sampling session of the given watches of the project and the channel of its samples.
+    pub(super) fn new(id: u64, watches: Vec<usize>) -> (Self, LiveSender) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let sampling = Sampling {
+            id,
+            events: Arc::new( Mutex::new(rx) ),
+            watches,
+            running: false,
+            values: Vec::new(),
+            time: Duration::from_secs(0),
+        };
+
+        (sampling, tx)
+    }
+
+    /// Updates the session with a sample.
+    pub(super) fn update(&mut self, sample: LiveSample) {
+        self.time = sample.time;
+        self.values = sample.values;
+    }
+
+    /// Returns the text of the last value of the given watch of the project.
+    pub(super) fn value(&self, watch: usize) -> Option<String> {
+        let i = self.watches.iter().position(|w| *w == watch)?;
+
+        match self.values.get(i)? {
+            Ok(value) => Some( value.to_string() ),
+            Err(e) => Some( format!("Error: {}", e) ),
+        }
+    }
+}
+
+
+
+/// Widget states of the live watch list.
+pub(super) struct LiveState {
+    /// Location input state.
+    location: text_input::State,
+
+    /// Current value of the location input.
+    locationval: String,
+
+    /// Datatype picklist state.
+    datatype: pick_list::State<Datatype>,
+
+    /// Currently selected datatype.
+    seldatatype: Option<Datatype>,
+
+    /// State of the add button.
+    add: button::State,
+
+    /// Sampling period input state.
+    period: text_input::State,
+
+    /// Current value of the sampling period input.
+    periodval: String,
+
+    /// List scroll state.
+    scroll: scrollable::State,
+
+    /// Remove button states of the list.
+    remove: Vec<button::State>,
+}
+
+impl LiveState {
+    /// Creates the widget states of the live watch list.
+    pub(super) fn new() -> Self {
+        LiveState {
+            location: Default::default(),
+            locationval: String::new(),
+            datatype: Default::default(),
+            seldatatype: Some(Datatype::UInt32),
+            add: Default::default(),
+            period: Default::default(),
+            periodval: String::new(),
+            scroll: Default::default(),
+            remove: Vec::new(),
+        }
+    }
+
+    /// Builds the live watch list of the project with the last sampled values.
+    pub(super) fn view<'a>(&'a mut self, project: Option<&ProjectSerial>, sampling: Option<&Sampling>) -> Column<'a, Message> {
+        let status = match sampling {
+            Some(s) if s.running => format!("Live watch (sampling, {:.1} s)", s.time.as_secs_f32()),
+            Some(_) => String::from("Live watch (starting...)"),
+            _ => String::from("Live watch"),
+        };
+
+        let toggle = Checkbox::new(
+            sampling.is_some(),
+            status,
+            |b| { Message::Probe( ProbeMessage::LiveSampling(b) ) }
+        )
+        .size(14)
+        .text_size(14)
+        .width(Length::Fill);
+
+        // The sampling period in milliseconds.
+        let period = TextInput::new(
+            &mut self.period,
+            "Period (ms)",
+            &self.periodval,
+            |s| { Message::Probe( ProbeMessage::LivePeriodChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Units(100));
+
+        let header = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(toggle)
+            .push(period);
+
+        // The location input.
+        let location = TextInput::new(
+            &mut self.location,
+            "Address or symbol",
+            &self.locationval,
+            |s| { Message::Probe( ProbeMessage::LiveInputChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Fill)
+        .on_submit(Message::Probe( ProbeMessage::AddLiveWatch ));
+
+        // The picklist for the datatype.
+        let datatype = PickList::new(
+            &mut self.datatype,
+            &DATATYPES[..],
+            self.seldatatype.clone(),
+            |d| { Message::Probe( ProbeMessage::LiveDatatype(d) ) }
+        )
+        .padding(4)
+        .width(Length::Shrink);
+
+        let add = Button::new(&mut self.add, Text::new("Add").size(14))
+            .on_press(Message::Probe( ProbeMessage::AddLiveWatch ))
+            .height(Length::Shrink);
+
+        let input = Row::new()
+            .spacing(5)
+            .push(location)
+            .push(datatype)
+            .push(add);
+
+        let scrollable = Scrollable::new(&mut self.scroll)
+            .spacing(2)
+            .padding(5)
+            .height(Length::Units(150))
+            .width(Length::Fill)
+            .scrollbar_margin(2)
+            .scrollbar_width(5)
+            .scroller_width(10);
+
+        let list = match project {
+            Some(project) => project.watches.iter()
+                .zip(self.remove.iter_mut())
+                .enumerate()
+                .fold(scrollable, |col, (i, (watch, remove))| {
+                    let value = sampling
+                        .and_then(|s| s.value(i))
+                        .unwrap_or_else(|| String::from("-"));
+
+                    let name = Text::new( format!("{} ({})", watch.location, watch.datatype) )
+                        .size(14)
+                        .width(Length::FillPortion(1));
+
+                    let value = Text::new(value)
+                        .size(14)
+                        .width(Length::FillPortion(1));
+
+                    let remove = Button::new(remove, Text::new("Remove").size(12))
+                        .on_press(Message::Probe( ProbeMessage::RemoveLiveWatch(i) ));
+
+                    col.push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Align::Center)
+                            .push(name)
+                            .push(value)
+                            .push(remove)
+                    )
+                }),
+
+            _ => scrollable,
+        };
+
+        Column::new()
+            .padding(5)
+            .spacing(5)
+            .max_width(475)
+            .push(header)
+            .push(input)
+            .push(list)
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the live watch list and its sampling.
+    pub(super) fn updatelive(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::LiveInputChanged(s) => {
+                self.state.live.locationval = s;
+                Command::none()
+            },
+
+            ProbeMessage::LiveDatatype(datatype) => {
+                self.state.live.seldatatype = Some(datatype);
+                Command::none()
+            },
+
+            ProbeMessage::LivePeriodChanged(s) => {
+                if s.is_empty() || common::parse(&s).is_some() {
+                    self.state.live.periodval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::AddLiveWatch => {
+                let input = self.state.live.locationval.trim().to_string();
+
+                if input.len() == 0 { return Command::none() }
+
+                let location = match common::parse(&input) {
+                    Some(address) => Location::Address(address),
+                    _ => Location::Symbol(input),
+                };
+
+                let datatype = self.state.live.seldatatype.unwrap_or(Datatype::UInt32);
+
+                match &mut self.project {
+                    Some(project) => project.watches.push( WatchInfo::new(location, datatype) ),
+                    _ => {
+                        warn!(origin="app", view="probe", "Cannot add a live watch without a project");
+                        return Command::none();
+                    },
+                }
+
+                self.state.live.locationval = String::new();
+                self.syncwatches();
+
+                // Sample the new watch too.
+                let restart = match self.sampling.is_some() {
+                    true => self.startsampling(),
+                    _ => Command::none(),
+                };
+
+                Command::batch([ self.saveproject(), restart ])
+            },
+
+            ProbeMessage::RemoveLiveWatch(i) => {
+                match &mut self.project {
+                    Some(project) if i < project.watches.len() => project.watches.remove(i),
+                    _ => return Command::none(),
+                };
+
+                self.syncwatches();
+
+                let restart = match self.sampling.is_some() {
+                    true => self.startsampling(),
+                    _ => Command::none(),
+                };
+
+                Command::batch([ self.saveproject(), restart ])
+            },
+
+            ProbeMessage::LiveSampling(true) => {
+                if self.probe.is_none() {
+                    warn!(origin="app", view="probe", "Cannot sample the live watches without an open probe");
+                    return Command::none();
+                }
+
+                self.startsampling()
+            },
+
+            ProbeMessage::LiveSampling(false) => {
+                self.sampling = None;
+                self.request( ProbeCommand::LiveStop )
+            },
+
+            ProbeMessage::LiveSample(sample) => {
+                let values: Vec<Option<f64>> = sample.values.iter()
+                    .map(|v| v.as_ref().ok().and_then(|v| v.number()))
+                    .collect();
+
+                self.plot.sample(sample.time.as_secs_f32(), &values);
+
+                if let Some(sampling) = &mut self.sampling {
+                    sampling.update(sample);
+                }
+
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Keeps the live watch list widget states in sync with the project.
+    pub(super) fn syncwatches(&mut self) {
+        let n = self.project.as_ref().map(|p| p.watches.len()).unwrap_or(0);
+
+        self.state.live.remove.resize_with(n, Default::default);
+    }
+
+    /// Starts sampling the live watches of the project that resolve to an address.
+    /// Replaces the current sampling.
+    fn startsampling(&mut self) -> Command<Message> {
+        let period = match self.state.live.periodval.trim() {
+            "" => DEFAULTPERIOD,
+            s => match common::parse(s) {
+                Some(ms) => Duration::from_millis(ms as u64),
+                _ => DEFAULTPERIOD,
+            },
+        };
+
+        let watches = match &self.project {
+            Some(project) => project.watches.clone(),
+            _ => return Command::none(),
+        };
+
+        let (indices, vars): (Vec<usize>, Vec<(u32, Datatype)>) = watches.iter()
+            .enumerate()
+            .filter_map(|(i, watch)| self.locate(&watch.location).map(|address| (i, (address, watch.datatype))))
+            .unzip();
+
+        // One series for each sampled watch.
+        self.plot.reset( indices.iter().map(|i| watches[*i].location.to_string()).collect() );
+
+        self.consoles += 1;
+
+        let (sampling, tx) = Sampling::new(self.consoles, indices);
+        self.sampling = Some(sampling);
+
+        self.request( ProbeCommand::LiveStart(vars, period, tx) )
+    }
+}
@@ -6,12 +6,39 @@
 
 
 
+mod backtrace;
 pub mod common;
+mod console;
+mod coredump;
+mod error;
+mod fault;
+mod flash;
+mod hotplug;
+mod live;
+mod peripherals;
+mod plot;
+mod snapshot;
 mod state;
+mod stream;
+mod trace;
 
 
 
 use crate::{
+    database::{ ChipCommand, ChipResponse, ProjectCommand, ProjectResponse },
+
+    elf::ElfFile,
+
+    svd,
+
+    project::{ BreakpointInfo, Change, Location, ProjectSerial, SnapshotInfo, TargetInfo },
+
+    probe::{
+        self,
+        CommandSender, Command as ProbeCommand, CoreInfo, Endianness, Fault, FlashHandle, Frame, Mismatch, OpenProbe, Register, Response,
+        WatchKind, Watchpoint, watchpoint::WATCHKINDS,
+    },
+
     gui::{
         msg::{
             Message, ProbeMessage,
@@ -21,32 +48,60 @@ use crate::{
             Theme,
             button, tooltip,
         },
+
+        widgets::Plot,
     },
 };
 
 use iced::{
-    Command, Column, Container, Element, Row,
+    Command, Column, Container, Element, Row, Subscription,
 
     Align, Length,
 
-    Text, PickList, TextInput,
+    Checkbox, Color, ProgressBar, Scrollable, Text, PickList, TextInput,
 
     button::{ Button },
     tooltip::{ Position, Tooltip },
 };
 
 use probe_rs::{
-    DebugProbeInfo, Probe,
+    CoreStatus, CoreType, DebugProbeInfo, HaltReason, Probe,
 };
 
+use architecture::Chip;
+
 use regex::Regex;
 
+use database::common::{
+    DBCommand, DBInterface,
+};
+
+use std::{
+    collections::{ HashMap, HashSet },
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::sync::RwLock;
+
+use tracing::{
+    debug, error, info, warn,
+
+    instrument::WithSubscriber,
+};
+
 use self::common::{
     Datatype, DATATYPES,
 };
 
 
 
+/// Color of the register values that changed since the last halt.
+const CHANGED: Color = Color { r: 0.85, g: 0.25, b: 0.20, a: 1.0 };
+
+
+
+
 pub struct ProbeView {
     /// Internal widget state.
     state: state::State,
@@ -68,6 +123,129 @@ pub struct ProbeView {
 
     /// Internal regex to validate input.
     regex: [Regex; 4],
+
+    /// Command channel of the open probe.
+    probe: Option<CommandSender>,
+
+    /// Probe of the open session.
+    connected: Option<DebugProbeInfo>,
+
+    /// Indicates that a connection is being established.
+    connecting: bool,
+
+    /// Last known status of the current core.
+    status: Option<CoreStatus>,
+
+    /// Cores of the target with their last known status.
+    cores: Vec<CoreInfo>,
+
+    /// Selected core.
+    selcore: Option<usize>,
+
+    /// Last error shown in the command display.
+    lasterror: Option<error::Error>,
+
+    /// Last register dump of the current core.
+    registers: Vec<Register>,
+
+    /// Register values at the previous halt.
+    baseline: HashMap<u16, u32>,
+
+    /// Indicates that the registers must be dumped on the next halt.
+    refresh: bool,
+
+    /// Interface to the `project` database.
+    interface: Option<DBInterface<ProjectCommand, ProjectResponse>>,
+
+    /// Interface to the `chip` database.
+    chipdb: Option<DBInterface<ChipCommand, ChipResponse>>,
+
+    /// Collection of projects.
+    projectdb: Arc<RwLock<Vec<ProjectSerial>>>,
+
+    /// Currently selected project.
+    project: Option<ProjectSerial>,
+
+    /// Names of the targets of the current project.
+    targets: Vec<String>,
+
+    /// Selected target.
+    seltarget: Option<String>,
+
+    /// ELF of the current target.
+    elf: Option<Arc<ElfFile>>,
+
+    /// Number of hardware breakpoint units and active breakpoints of the current core.
+    bpunits: Option<(u32, Vec<u32>)>,
+
+    /// Number of watchpoint comparators and active watchpoints of the current core.
+    watchpoints: Option<(u32, Vec<Watchpoint>)>,
+
+    /// Symbol names of the watched addresses.
+    watchlabels: HashMap<u32, String>,
+
+    /// Last watchpoint that fired and the PC of the halt.
+    watchhit: Option<(Option<Watchpoint>, u32)>,
+
+    /// Current flash operation.
+    flashing: Option<flash::Flashing>,
+
+    /// Number of flash operations started.
+    flashes: u64,
+
+    /// Ranges of target memory that did not match the ELF in the last verification.
+    mismatches: Option<Vec<Mismatch>>,
+
+    /// Current RTT session.
+    console: Option<console::Console>,
+
+    /// Number of console sessions started.
+    consoles: u64,
+
+    /// Current semihosting session.
+    semihost: Option<console::Semihost>,
+
+    /// Current trace session.
+    trace: Option<trace::Trace>,
+
+    /// Output shown in the console.
+    tab: console::Tab,
+
+    /// Current sampling of the live watches.
+    sampling: Option<live::Sampling>,
+
+    /// Plot of the numeric live watches.
+    plot: Plot,
+
+    /// Name of the snapshot being captured.
+    snapshotname: Option<String>,
+
+    /// Names of the last compared snapshots and their differences.
+    snapshotdiff: Option<(String, String, Vec<Change>)>,
+
+    /// Path of the core dump being saved.
+    dumppath: Option<String>,
+
+    /// Call stack of the halted core.
+    backtrace: Option<Vec<Frame>>,
+
+    /// Fault handled by the halted core.
+    fault: Option<Fault>,
+
+    /// SVD of the current target.
+    svd: Option<Arc<svd::Device>>,
+
+    /// Row of the expanded peripheral.
+    selperipheral: Option<usize>,
+
+    /// Rows of the expanded registers of the selected peripheral.
+    openregisters: HashSet<usize>,
+
+    /// Last values of the peripheral words read, by address.
+    svdvalues: HashMap<u32, u32>,
+
+    /// Path of the core dump of the current post-mortem session.
+    offline: Option<String>,
 }
 
 impl ProbeView {
@@ -81,52 +259,1043 @@ impl ProbeView {
 
         let probes = Probe::list_all();
 
-        let probenames = probes.iter().map(|p| p.identifier.clone()).collect();
+        let probenames = probes.iter().map(hotplug::name).collect();
+
+        ProbeView {
+            state: state::State::new(),
+            projects: Vec::new(),
+            selproject: None,
+            probes,
+            probenames,
+            selprobe: None,
+            regex: [hex, bin, oct, dec],
+            probe: None,
+            connected: None,
+            connecting: false,
+            status: None,
+            cores: Vec::new(),
+            selcore: None,
+            lasterror: None,
+            registers: Vec::new(),
+            baseline: HashMap::new(),
+            refresh: false,
+            interface: None,
+            chipdb: None,
+            projectdb: Arc::new( RwLock::new( Vec::new() ) ),
+            project: None,
+            targets: Vec::new(),
+            seltarget: None,
+            elf: None,
+            bpunits: None,
+            watchpoints: None,
+            watchlabels: HashMap::new(),
+            watchhit: None,
+            flashing: None,
+            flashes: 0,
+            mismatches: None,
+            console: None,
+            consoles: 0,
+            semihost: None,
+            trace: None,
+            tab: console::Tab::Rtt,
+            sampling: None,
+            plot: Plot::new(),
+            snapshotname: None,
+            snapshotdiff: None,
+            backtrace: None,
+            fault: None,
+            svd: None,
+            selperipheral: None,
+            openregisters: HashSet::new(),
+            svdvalues: HashMap::new(),
+            dumppath: None,
+            offline: None,
+        }
+    }
+
+    /// Updates the view.
+    pub fn update(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::Datatype(d) => {
+                self.state.seldatatype = Some(d);
+                Command::none()
+            },
+
+            ProbeMessage::ReadAddressChanged(s) => {
+                if self.validaddr(&s) {
+                    self.state.textinput.readaddrval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::ReadRangeSAddressChanged(s) => {
+                if self.validaddr(&s) {
+                    self.state.textinput.saddrval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::ReadRangeEAddressChanged(s) => {
+                if self.validaddr(&s) {
+                    self.state.textinput.eaddrval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::Load => {
+                if self.flashing.is_some() { return Command::none() }
+
+                let (elf, channel) = match (&self.elf, &self.probe) {
+                    (Some(elf), Some(channel)) => (elf.clone(), channel.clone()),
+                    (None, _) => {
+                        error!(origin="app", view="probe", "Cannot flash the target without an ELF");
+                        self.lasterror = Some( self.noelf() );
+                        return Command::none();
+                    },
+                    _ => {
+                        warn!(origin="app", view="probe", "Cannot flash the target without an open probe");
+                        self.lasterror = Some( error::Error::NoProbe );
+                        return Command::none();
+                    },
+                };
+
+                let (handle, events) = FlashHandle::new();
+
+                self.flashes += 1;
+                self.flashing = Some( flash::Flashing::new(self.flashes, handle.clone(), events) );
+                self.lasterror = None;
+                self.mismatches = None;
+
+                Command::perform(
+                    probe::request(channel, ProbeCommand::Flash(elf, handle)).with_current_subscriber(),
+                    |r| { Message::Probe( ProbeMessage::FlashDone(r) ) }
+                )
+            },
+
+            ProbeMessage::CancelLoad => {
+                if let Some(flashing) = &self.flashing {
+                    info!(origin="app", view="probe", "Cancelling the flash operation");
+                    flashing.handle.cancel();
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::Flash(event) => {
+                if let Some(flashing) = &mut self.flashing {
+                    flashing.update(event);
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::FlashDone(response) => {
+                self.flashing = None;
+
+                match response {
+                    Response::Flashed(n, mismatches) => {
+                        info!(origin="app", view="probe", "Flashed {} bytes", n);
+                        self.mismatches = Some(mismatches);
+                        Command::none()
+                    },
+
+                    r => self.response(r),
+                }
+            },
+
+            ProbeMessage::Verify => match &self.elf {
+                Some(elf) => {
+                    self.mismatches = None;
+                    self.request( ProbeCommand::Verify(elf.clone()) )
+                },
+
+                _ => {
+                    error!(origin="app", view="probe", "Cannot verify the target without an ELF");
+                    Command::none()
+                },
+            },
+
+            ProbeMessage::RttAttach | ProbeMessage::Rtt(_) | ProbeMessage::RttChannel(_) | ProbeMessage::RttInputChanged(_) |
+            ProbeMessage::RttSend | ProbeMessage::RttClear | ProbeMessage::LogLevel(_) | ProbeMessage::LogModuleChanged(_) => self.updateconsole(msg),
+
+            ProbeMessage::Semihosting(_) | ProbeMessage::Semihost(_) | ProbeMessage::SemihostTab => self.updatesemihost(msg),
+
+            ProbeMessage::Trace(_) | ProbeMessage::TracePackets(_) | ProbeMessage::TraceTab(_) | ProbeMessage::TraceClockChanged(_) |
+            ProbeMessage::TraceBaudChanged(_) | ProbeMessage::TraceFileChanged(_) | ProbeMessage::TraceOpen | ProbeMessage::TraceDecoded(..) => self.updatetrace(msg),
+
+            ProbeMessage::Stop      => self.request( ProbeCommand::Halt ),
+            ProbeMessage::Reset     => self.leavehalt( ProbeCommand::Reset ),
+            ProbeMessage::ResetHalt => self.leavehalt( ProbeCommand::ResetAndHalt ),
+            ProbeMessage::Run       => self.leavehalt( ProbeCommand::Run ),
+            ProbeMessage::Step      => self.leavehalt( ProbeCommand::Step ),
+
+            ProbeMessage::Dump => self.inspect(),
+
+            ProbeMessage::RegisterValueChanged(i, s) => {
+                if self.validaddr(&s) && (i < self.state.registers.len()) {
+                    self.state.registers[i].1 = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::WriteRegister(i) => {
+                let (address, value) = match (self.registers.get(i), self.state.registers.get(i)) {
+                    (Some(register), Some((_, input))) => (register.address, common::parse(input)),
+                    _ => return Command::none(),
+                };
+
+                match value {
+                    Some(value) => self.request( ProbeCommand::WriteRegister(address, value) ),
+                    _ => {
+                        warn!(origin="app", view="probe", "Invalid register value {}", self.state.registers[i].1);
+                        Command::none()
+                    },
+                }
+            },
+
+            // The probe rejects commands while the target is being flashed.
+            ProbeMessage::Tick if self.flashing.is_some() => Command::none(),
+
+            ProbeMessage::Tick => match self.cores.len() {
+                // Single core targets only need the status of the current core.
+                1 => self.request( ProbeCommand::Status ),
+                _ => Command::batch([ self.request( ProbeCommand::Status ), self.request( ProbeCommand::Cores ) ]),
+            },
+
+            ProbeMessage::CoreSelected(core) => self.request( ProbeCommand::SelectCore(core) ),
+
+            ProbeMessage::ProjectInterface(interface) => {
+                self.interface = Some( (*interface).clone() );
+
+                info!(origin="app", view="probe", "Project database interface aquired");
+
+                Command::perform(
+                    getdb(*interface).with_current_subscriber(),
+                    |m| { Message::Probe( m ) }
+                )
+            },
+
+            ProbeMessage::ChipInterface(interface) => {
+                self.chipdb = Some( *interface );
+
+                info!(origin="app", view="probe", "Chip database interface aquired");
+
+                Command::none()
+            },
+
+            ProbeMessage::ProjectDatabase(projects) => {
+                self.projectdb = projects;
+                self.update( ProbeMessage::UpdateProjectDatabase )
+            },
+
+            ProbeMessage::UpdateProjectDatabase => {
+                // Get read permission.
+                let projects = self.projectdb.blocking_read();
+
+                self.projects = projects.iter()
+                    .map(|p| p.name().clone())
+                    .collect();
+
+                // Refresh the selected project.
+                if let Some(name) = &self.selproject {
+                    self.project = projects.iter().find(|p| p.name() == name).cloned();
+                }
+
+                self.targets = self.project.iter()
+                    .flat_map(|p| p.targets.iter())
+                    .map(|t| t.name.clone())
+                    .collect();
+
+                debug!(origin="app", view="probe", "Updated project list: {} projects", projects.len());
+
+                drop(projects);
+
+                self.syncbreakpoints();
+                self.syncwatches();
+                self.syncsnapshots();
+
+                Command::none()
+            },
+
+            ProbeMessage::ProjectSelected(name) => {
+                self.project = self.projectdb.blocking_read()
+                    .iter()
+                    .find(|p| *p.name() == name)
+                    .cloned();
+
+                self.selproject = Some(name);
+
+                self.targets = self.project.iter()
+                    .flat_map(|p| p.targets.iter())
+                    .map(|t| t.name.clone())
+                    .collect();
+
+                self.seltarget = self.targets.first().cloned();
+
+                // The samples refer to the watches of the previous project.
+                self.sampling = None;
+
+                // The comparison refers to the snapshots of the previous project.
+                self.snapshotdiff = None;
+
+                self.syncbreakpoints();
+                self.syncwatches();
+                self.syncsnapshots();
+
+                Command::batch([ self.loadelf(), self.loadsvd() ])
+            },
+
+            ProbeMessage::TargetSelected(name) => {
+                self.seltarget = Some(name);
+
+                Command::batch([ self.loadelf(), self.loadsvd() ])
+            },
+
+            ProbeMessage::ProbeSelected(name) => {
+                self.selprobe = Some(name);
+                Command::none()
+            },
+
+            ProbeMessage::Connect => {
+                if self.probe.is_some() || self.connecting { return Command::none() }
+
+                // Get the selected probe.
+                let info = match self.selprobe.as_ref().and_then(|name| self.probenames.iter().position(|n| n == name)) {
+                    Some(i) => self.probes[i].clone(),
+                    _ => {
+                        warn!(origin="app", view="probe", "Cannot connect without a probe");
+                        return Command::none();
+                    },
+                };
+
+                // Get the chip of the selected target.
+                let chip = match self.target() {
+                    Some(target) => target.target.clone(),
+                    _ => {
+                        warn!(origin="app", view="probe", "Cannot connect without a target");
+                        return Command::none();
+                    },
+                };
+
+                info!(origin="app", view="probe", "Connecting to {} through probe {}", chip, hotplug::name(&info));
+
+                self.connecting = true;
+                self.lasterror = None;
+
+                Command::perform(
+                    connect(self.chipdb.clone(), info, chip).with_current_subscriber(),
+                    |m| { Message::Probe( m ) }
+                )
+            },
+
+            ProbeMessage::Connected(info, channel) => {
+                info!(origin="app", view="probe", "Connected to probe {}", hotplug::name(&info));
+
+                self.connecting = false;
+                self.connected = Some(info);
+                self.probe = Some(channel);
+
+                // Re-apply the enabled breakpoints of the project.
+                let locations: Vec<Location> = self.project.iter()
+                    .flat_map(|p| p.breakpoints.iter())
+                    .filter(|bp| bp.enabled)
+                    .map(|bp| bp.location.clone())
+                    .collect();
+
+                let breakpoints: Vec<u32> = locations.iter()
+                    .filter_map(|location| self.locate(location))
+                    .collect();
+
+                let mut cmds = vec![
+                    self.request( ProbeCommand::Cores ),
+                    self.request( ProbeCommand::Status ),
+                    self.request( ProbeCommand::Breakpoints ),
+                    self.request( ProbeCommand::Watchpoints ),
+                ];
+
+                cmds.extend( breakpoints.into_iter().map(|address| self.request( ProbeCommand::SetBreakpoint(address) )) );
+
+                Command::batch(cmds)
+            },
+
+            ProbeMessage::ConnectFailed(e) => {
+                error!(origin="app", view="probe", "Could not connect to the target: {}", e);
+
+                self.connecting = false;
+                self.lasterror = Some( probe::Error::AttachFailed(e).into() );
+
+                Command::none()
+            },
+
+            ProbeMessage::DumpFileChanged(_) | ProbeMessage::SaveCoreDump | ProbeMessage::CoreDumpSaved(_) | ProbeMessage::OpenCoreDump |
+            ProbeMessage::Offline(..) => self.updatecoredump(msg),
+
+            ProbeMessage::Disconnect => {
+                self.disconnect();
+                Command::none()
+            },
+
+            ProbeMessage::ProbeAdded(_) | ProbeMessage::ProbeRemoved(_) => self.updatehotplug(msg),
+
+            ProbeMessage::ProjectUpdateFailed => {
+                error!(origin="app", view="probe", "Could not save the current project");
+                self.lasterror = Some( error::Error::ProjectNotSaved );
+                Command::none()
+            },
+
+            ProbeMessage::ElfLoaded(elf) => {
+                info!(origin="app", view="probe", "Loaded ELF file {}", elf.path().display());
+                self.elf = Some(elf.clone());
+
+                // Unwind the halted core and name the fault with the debug information of the new ELF.
+                match (self.halted(), self.unwindable()) {
+                    (true, true) => Command::batch([
+                        self.request( ProbeCommand::Backtrace(elf.clone()) ),
+                        self.request( ProbeCommand::Fault(Some(elf)) ),
+                    ]),
+                    _ => Command::none(),
+                }
+            },
+
+            ProbeMessage::ElfFailed(e) => {
+                error!(origin="app", view="probe", "{}", e);
+                self.lasterror = Some( error::Error::Elf(e) );
+                Command::none()
+            },
+
+            ProbeMessage::SvdLoaded(_) | ProbeMessage::SvdFailed(_) | ProbeMessage::SelectPeripheral(_) | ProbeMessage::ToggleRegister(_) |
+            ProbeMessage::ReadPeripheral => self.updateperipherals(msg),
+
+            ProbeMessage::OpenFaultSource => self.updatefault(msg),
+
+            ProbeMessage::FieldChoice(..) | ProbeMessage::FieldInputChanged(..) | ProbeMessage::WriteField(..) => self.updatefields(msg),
+
+            ProbeMessage::BreakpointInputChanged(s) => {
+                self.state.textinput.breakpointval = s;
+                Command::none()
+            },
+
+            ProbeMessage::AddBreakpoint => {
+                let input = self.state.textinput.breakpointval.trim().to_string();
+
+                if input.len() == 0 { return Command::none() }
+
+                let location = match common::parse(&input) {
+                    Some(address) => Location::Address(address),
+                    _ => Location::Symbol(input),
+                };
+
+                let project = match &mut self.project {
+                    Some(project) => project,
+                    _ => {
+                        warn!(origin="app", view="probe", "Cannot add a breakpoint without a project");
+                        return Command::none();
+                    },
+                };
+
+                if project.breakpoints.iter().any(|bp| bp.location == location) {
+                    return Command::none();
+                }
+
+                project.breakpoints.push( BreakpointInfo::new(location.clone()) );
+
+                self.state.textinput.breakpointval = String::new();
+                self.syncbreakpoints();
+
+                let set = match self.locate(&location) {
+                    Some(address) => self.request( ProbeCommand::SetBreakpoint(address) ),
+                    _ => Command::none(),
+                };
+
+                Command::batch([ self.saveproject(), set ])
+            },
+
+            ProbeMessage::ToggleBreakpoint(i, enabled) => {
+                let location = match &mut self.project {
+                    Some(project) if i < project.breakpoints.len() => {
+                        project.breakpoints[i].enabled = enabled;
+                        project.breakpoints[i].location.clone()
+                    },
+                    _ => return Command::none(),
+                };
+
+                let cmd = match (self.locate(&location), enabled) {
+                    (Some(address), true)  => self.request( ProbeCommand::SetBreakpoint(address) ),
+                    (Some(address), false) => self.request( ProbeCommand::ClearBreakpoint(address) ),
+                    _ => Command::none(),
+                };
+
+                Command::batch([ self.saveproject(), cmd ])
+            },
+
+            ProbeMessage::RemoveBreakpoint(i) => {
+                let breakpoint = match &mut self.project {
+                    Some(project) if i < project.breakpoints.len() => project.breakpoints.remove(i),
+                    _ => return Command::none(),
+                };
+
+                self.syncbreakpoints();
+
+                let clear = match (self.locate(&breakpoint.location), breakpoint.enabled) {
+                    (Some(address), true) => self.request( ProbeCommand::ClearBreakpoint(address) ),
+                    _ => Command::none(),
+                };
+
+                Command::batch([ self.saveproject(), clear ])
+            },
+
+            ProbeMessage::LiveInputChanged(_) | ProbeMessage::LiveDatatype(_) | ProbeMessage::LivePeriodChanged(_) | ProbeMessage::AddLiveWatch |
+            ProbeMessage::RemoveLiveWatch(_) | ProbeMessage::LiveSampling(_) | ProbeMessage::LiveSample(_) => self.updatelive(msg),
+
+            ProbeMessage::PlotPause(_) | ProbeMessage::PlotZoom(_) | ProbeMessage::PlotClear => self.updateplot(msg),
+
+            ProbeMessage::SnapshotNameChanged(_) | ProbeMessage::SnapshotRange | ProbeMessage::SnapshotRam | ProbeMessage::RemoveSnapshot(_) |
+            ProbeMessage::SnapshotBefore(_) | ProbeMessage::SnapshotAfter(_) | ProbeMessage::DiffSnapshots => self.updatesnapshot(msg),
+
+            ProbeMessage::WatchpointInputChanged(s) => {
+                self.state.textinput.watchpointval = s;
+                Command::none()
+            },
+
+            ProbeMessage::WatchpointSizeChanged(s) => {
+                if self.validaddr(&s) {
+                    self.state.textinput.watchsizeval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::WatchKind(kind) => {
+                self.state.selwatchkind = Some(kind);
+                Command::none()
+            },
+
+            ProbeMessage::AddWatchpoint => {
+                let input = self.state.textinput.watchpointval.trim().to_string();
+
+                if input.len() == 0 { return Command::none() }
+
+                // Resolve the address and the default size of the location.
+                let (address, size) = match common::parse(&input) {
+                    Some(address) => (address, 4),
+
+                    _ => match self.elf.as_ref().map(|elf| elf.symbol(&input)) {
+                        Some(Some(symbol)) => (symbol.address, symbol.size.max(1).next_power_of_two()),
+                        Some(None) => {
+                            error!(origin="app", view="probe", "Cannot resolve watchpoint location {}", input);
+                            self.lasterror = Some( error::Error::NoSymbol(input) );
+                            return Command::none();
+                        },
+                        None => {
+                            error!(origin="app", view="probe", "Cannot resolve watchpoint location {} without an ELF", input);
+                            self.lasterror = Some( error::Error::SymbolWithoutElf(input) );
+                            return Command::none();
+                        },
+                    },
+                };
+
+                // An explicit size overrides the size of the symbol.
+                let size = match common::parse(&self.state.textinput.watchsizeval) {
+                    Some(size) => size,
+                    _ => size,
+                };
+
+                let kind = self.state.selwatchkind.unwrap_or(WatchKind::Write);
+
+                if common::parse(&input).is_none() {
+                    self.watchlabels.insert(address, input);
+                }
+
+                self.state.textinput.watchpointval = String::new();
+
+                self.request( ProbeCommand::SetWatchpoint(address, size, kind) )
+            },
+
+            ProbeMessage::RemoveWatchpoint(comparator) => self.request( ProbeCommand::ClearWatchpoint(comparator) ),
+
+            ProbeMessage::Response(response) => self.response(response),
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Builds the subscriptions of the view.
+    pub fn subscription(&self) -> Subscription<Message> {
+        let tick = match self.probe {
+            Some(_) => iced::time::every( Duration::from_millis(250) )
+                .map(|_| Message::Probe( ProbeMessage::Tick )),
+
+            _ => Subscription::none(),
+        };
+
+        let flash = match &self.flashing {
+            Some(flashing) => stream::subscription(flashing.id, flashing.events.clone(), |e| Message::Probe( ProbeMessage::Flash(e) )),
+            _ => Subscription::none(),
+        };
+
+        let rtt = match &self.console {
+            Some(console) => stream::subscription(console.id, console.events.clone(), |e| Message::Probe( ProbeMessage::Rtt(e) )),
+            _ => Subscription::none(),
+        };
+
+        let semihost = match &self.semihost {
+            Some(semihost) => stream::subscription(semihost.id, semihost.events.clone(), |e| Message::Probe( ProbeMessage::Semihost(e) )),
+            _ => Subscription::none(),
+        };
+
+        let trace = match self.trace.as_ref().and_then(|t| t.events.as_ref().map(|e| (t.id, e))) {
+            Some((id, events)) => stream::subscription(id, events.clone(), |p| Message::Probe( ProbeMessage::TracePackets(p) )),
+            _ => Subscription::none(),
+        };
+
+        let live = match &self.sampling {
+            Some(sampling) => stream::subscription(sampling.id, sampling.events.clone(), |s| Message::Probe( ProbeMessage::LiveSample(s) )),
+            _ => Subscription::none(),
+        };
+
+        Subscription::batch([ tick, flash, rtt, semihost, trace, live, hotplug::subscription( self.probes.clone() ) ])
+    }
+
+    /// Closes the open probe and clears the state of the session.
+    /// The probe command loop finishes once the pending commands are answered.
+    fn disconnect(&mut self) {
+        if let Some(connected) = self.connected.take() {
+            info!(origin="app", view="probe", "Disconnecting from probe {}", hotplug::name(&connected));
+        }
+
+        if let Some(path) = self.offline.take() {
+            info!(origin="app", view="probe", "Closing core dump {}", path);
+        }
+
+        if let Some(flashing) = self.flashing.take() {
+            flashing.handle.cancel();
+        }
+
+        self.console = None;
+        self.state.console.channels = Vec::new();
+        self.semihost = None;
+        self.tab = console::Tab::Rtt;
+
+        self.sampling = None;
+
+        // Capture files do not depend on the probe.
+        if self.trace.as_ref().map(|t| t.events.is_some()).unwrap_or(false) {
+            self.trace = None;
+        }
+
+        self.probe = None;
+        self.status = None;
+        self.cores = Vec::new();
+        self.selcore = None;
+        self.registers = Vec::new();
+        self.state.registers = Vec::new();
+        self.backtrace = None;
+        self.fault = None;
+        self.svdvalues.clear();
+        self.baseline = HashMap::new();
+        self.refresh = false;
+        self.bpunits = None;
+        self.watchpoints = None;
+        self.state.watchpoints = Vec::new();
+        self.watchhit = None;
+        self.mismatches = None;
+    }
+
+    /// Returns the selected target of the selected project.
+    fn target(&self) -> Option<&TargetInfo> {
+        let name = self.seltarget.as_ref()?;
+
+        self.project.as_ref()?.targets.iter().find(|t| t.name == *name)
+    }
+
+    /// Loads the ELF of the selected target.
+    fn loadelf(&mut self) -> Command<Message> {
+        self.elf = None;
+
+        match self.target().map(|t| t.binary()) {
+            Some(Some(path)) => Command::perform(
+                ElfFile::load(path).with_current_subscriber(),
+                |r| match r {
+                    Ok(elf) => Message::Probe( ProbeMessage::ElfLoaded( Arc::new(elf) ) ),
+                    Err(e) => Message::Probe( ProbeMessage::ElfFailed(e) ),
+                }
+            ),
+
+            _ => {
+                match self.noelf() {
+                    e @ error::Error::NoBinary(_) => {
+                        error!(origin="app", view="probe", "{}", e);
+                        self.lasterror = Some(e);
+                    },
+
+                    _ => warn!(origin="app", view="probe", "The selected target has no binary, symbols are not available"),
+                }
+
+                Command::none()
+            },
+        }
+    }
+
+    /// Returns why the selected target has no ELF.
+    fn noelf(&self) -> error::Error {
+        match self.target() {
+            Some(target) if !target.binary.is_empty() && target.binary().is_none() => error::Error::NoBinary( target.binary.clone() ),
+            _ => error::Error::NoElf,
+        }
+    }
+
+    /// Resolves a location to an address in the target.
+    fn resolve(&self, location: &Location) -> Result<u32, error::Error> {
+        match location {
+            Location::Address(address) => Ok(*address),
+
+            Location::Symbol(name) => match self.elf.as_ref().map(|elf| elf.symbol(name)) {
+                Some(Some(symbol)) => Ok(symbol.address),
+                Some(None) => Err( error::Error::NoSymbol( name.clone() ) ),
+                None => Err( error::Error::SymbolWithoutElf( name.clone() ) ),
+            },
+        }
+    }
+
+    /// Resolves a location to an address in the target.
+    /// Shows the error in the command display if it cannot be resolved.
+    fn locate(&mut self, location: &Location) -> Option<u32> {
+        match self.resolve(location) {
+            Ok(address) => Some(address),
+            Err(e) => {
+                error!(origin="app", view="probe", "{}", e);
+                self.lasterror = Some(e);
+                None
+            },
+        }
+    }
+
+    /// Keeps the breakpoint list widget states in sync with the project.
+    fn syncbreakpoints(&mut self) {
+        let n = self.project.as_ref().map(|p| p.breakpoints.len()).unwrap_or(0);
+
+        self.state.breakpoints.resize_with(n, Default::default);
+    }
+
+    /// Saves the current project in the project database.
+    fn saveproject(&self) -> Command<Message> {
+        match (&self.interface, &self.project) {
+            (Some(interface), Some(project)) => Command::perform(
+                updateproject(interface.clone(), project.clone()).with_current_subscriber(),
+                |m| { m }
+            ),
+
+            _ => {
+                error!(origin="app", view="probe", "Cannot save project without database interface");
+                Command::none()
+            },
+        }
+    }
+
+    /// Sends a command to the open probe.
+    fn request(&self, cmd: ProbeCommand) -> Command<Message> {
+        match &self.probe {
+            Some(channel) => Command::perform(
+                probe::request(channel.clone(), cmd).with_current_subscriber(),
+                |r| { Message::Probe( ProbeMessage::Response(r) ) }
+            ),
+
+            _ => {
+                warn!(origin="app", view="probe", "Cannot send command {:?} without an open probe", cmd);
+                Command::none()
+            },
+        }
+    }
+
+    /// Sends a command that makes the core leave the halted state.
+    /// Stores the current register values as the baseline for the next halt.
+    fn leavehalt(&mut self, cmd: ProbeCommand) -> Command<Message> {
+        self.baseline = self.registers.iter()
+            .map(|r| (r.address, r.value))
+            .collect();
+
+        self.refresh = self.registers.len() > 0;
+        self.watchhit = None;
+        self.backtrace = None;
+        self.fault = None;
+
+        // The peripherals change while the core runs.
+        self.svdvalues.clear();
+
+        self.request(cmd)
+    }
+
+    /// Processes a response of the open probe.
+    fn response(&mut self, response: Response) -> Command<Message> {
+        match response {
+            Response::Status(status) => {
+                let changed = self.status != Some(status);
+
+                if changed {
+                    debug!(origin="app", view="probe", "Core status changed to {:?}", status);
+                }
+
+                self.status = Some(status);
+
+                // Find the watchpoint that halted the core.
+                let hit = match status {
+                    CoreStatus::Halted(HaltReason::Watchpoint) if changed => self.request( ProbeCommand::WatchpointHit ),
+                    _ => Command::none(),
+                };
+
+                // Dump the registers again if the core halted.
+                if self.refresh && self.halted() {
+                    self.refresh = false;
+                    return Command::batch([ hit, self.inspect() ]);
+                }
+
+                return hit;
+            },
+
+            Response::Cores(selected, cores) => {
+                self.cores = cores;
+
+                if self.selcore != Some(selected) {
+                    debug!(origin="app", view="probe", "Core {} selected", selected);
+
+                    self.selcore = Some(selected);
+                    self.status = self.cores.iter().find(|c| c.index == selected).and_then(|c| c.status);
+
+                    // The register and debug unit state belongs to the previous core.
+                    self.registers = Vec::new();
+                    self.state.registers = Vec::new();
+                    self.baseline = HashMap::new();
+                    self.refresh = false;
+                    self.bpunits = None;
+                    self.watchpoints = None;
+                    self.watchhit = None;
+                    self.backtrace = None;
+                    self.fault = None;
+
+                    let dump = match self.halted() {
+                        true => self.inspect(),
+                        _ => Command::none(),
+                    };
+
+                    return Command::batch([
+                        self.request( ProbeCommand::Breakpoints ),
+                        self.request( ProbeCommand::Watchpoints ),
+                        dump,
+                    ]);
+                }
+            },
+
+            Response::Breakpoints(units, active) => {
+                debug!(origin="app", view="probe", "{} of {} breakpoint units in use", active.len(), units);
+                self.bpunits = Some( (units, active) );
+            },
+
+            Response::Watchpoints(units, active) => {
+                debug!(origin="app", view="probe", "{} of {} watchpoint comparators in use", active.len(), units);
+                self.state.watchpoints.resize_with(active.len(), Default::default);
+                self.watchpoints = Some( (units, active) );
+            },
+
+            Response::WatchpointHit(watchpoint, pc) => {
+                self.watchhit = Some( (watchpoint, pc) );
+            },
+
+            Response::Rtt(control) => {
+                info!(origin="app", view="probe", "RTT control block found at 0x{:08X}", control.address);
+
+                self.state.console.channels = control.up.iter().map(|_| Default::default()).collect();
+
+                if let Some(console) = &mut self.console {
+                    console.attached(control);
+                }
+            },
+
+            Response::RttWritten(channel, n) => {
+                debug!(origin="app", view="probe", "Sent {} bytes to RTT channel {}", n, channel);
+            },
+
+            Response::Semihosting(enabled) => {
+                debug!(origin="app", view="probe", "Semihosting enabled: {}", enabled);
+            },
+
+            Response::Live(running) => {
+                debug!(origin="app", view="probe", "Live watch sampling: {}", running);
+
+                if let Some(sampling) = &mut self.sampling {
+                    sampling.running = running;
+                }
+            },
+
+            Response::Trace(running) => {
+                debug!(origin="app", view="probe", "SWO trace running: {}", running);
+
+                if let Some(trace) = &mut self.trace {
+                    trace.running = running;
+                }
+            },
+
+            Response::Snapshot(regions) => {
+                if let (Some(name), Some(project)) = (self.snapshotname.take(), &mut self.project) {
+                    let snapshot = SnapshotInfo::new(name, regions);
+
+                    info!(origin="app", view="probe", "Captured snapshot '{}' of {} bytes", snapshot.name, snapshot.size());
+
+                    // A snapshot replaces the one with the same name.
+                    match project.snapshots.iter().position(|s| s.name == snapshot.name) {
+                        Some(i) => project.snapshots[i] = snapshot,
+                        _ => project.snapshots.push(snapshot),
+                    }
+
+                    self.state.snapshot.nameval = String::new();
+                    self.syncsnapshots();
 
-        ProbeView {
-            state: state::State::new(),
-            projects: Vec::new(),
-            selproject: None,
-            probes,
-            probenames,
-            selprobe: None,
-            regex: [hex, bin, oct, dec],
-        }
-    }
+                    return self.saveproject();
+                }
+            },
 
-    /// Updates the view.
-    pub fn update(&mut self, msg: ProbeMessage) -> Command<Message> {
-        match msg {
-            ProbeMessage::Datatype(d) => {
-                self.state.seldatatype = Some(d);
-                Command::none()
+            Response::CoreDump(mut dump) => {
+                if let Some(path) = self.dumppath.take() {
+                    // Keep the firmware next to the dump for the post-mortem session.
+                    dump.elf = self.elf.as_ref().map(|elf| elf.path().clone());
+
+                    return Command::perform(
+                        async move {
+                            dump.save( std::path::Path::new(&path) ).await.map(|_| path)
+                        },
+                        |r| Message::Probe( ProbeMessage::CoreDumpSaved(r) )
+                    );
+                }
             },
 
-            ProbeMessage::ReadAddressChanged(s) => {
-                if self.validaddr(&s) {
-                    self.state.textinput.readaddrval = s;
+            Response::Verified(mismatches) => {
+                match mismatches.len() {
+                    0 => info!(origin="app", view="probe", "Target memory matches the ELF"),
+                    n => warn!(origin="app", view="probe", "Target memory differs from the ELF in {} ranges", n),
                 }
 
-                Command::none()
+                self.mismatches = Some(mismatches);
             },
 
-            ProbeMessage::ReadRangeSAddressChanged(s) => {
-                if self.validaddr(&s) {
-                    self.state.textinput.saddrval = s;
-                }
+            Response::Registers(registers) => {
+                self.state.registers = registers.iter()
+                    .map(|r| (Default::default(), format!("0x{:08X}", r.value)))
+                    .collect();
 
-                Command::none()
+                self.registers = registers;
             },
 
-            ProbeMessage::ReadRangeEAddressChanged(s) => {
-                if self.validaddr(&s) {
-                    self.state.textinput.eaddrval = s;
+            Response::Backtrace(frames) => self.backtrace = Some(frames),
+
+            Response::Fault(fault) => self.fault = fault,
+
+            Response::Words(words) => self.svdvalues.extend(words),
+
+            Response::Register(register) => {
+                if let Some(i) = self.registers.iter().position(|r| r.address == register.address) {
+                    self.state.registers[i].1 = format!("0x{:08X}", register.value);
+                    self.registers[i] = register;
                 }
+            },
 
-                Command::none()
+            Response::Error(e) => {
+                error!(origin="app", view="probe", "Probe command failed: {}", e);
+
+                // The RTT session could not be attached.
+                if self.console.as_ref().map(|c| c.control.is_none()).unwrap_or(false) {
+                    self.console = None;
+                }
+
+                // The core cannot be sampled while it runs.
+                if self.sampling.as_ref().map(|s| !s.running).unwrap_or(false) {
+                    self.sampling = None;
+                }
+
+                // The SWO capture could not be started.
+                if self.trace.as_ref().map(|t| t.events.is_some() && !t.running).unwrap_or(false) {
+                    self.trace = None;
+                    self.tab = console::Tab::Rtt;
+                }
+
+                // The snapshot could not be captured.
+                self.snapshotname = None;
+
+                // The core could not be dumped.
+                self.dumppath = None;
+
+                self.lasterror = Some( e.into() );
             },
 
+            r => debug!(origin="app", view="probe", "Unhandled probe response {:?}", r),
+        }
+
+        Command::none()
+    }
+
+    /// Builds the description of the last watchpoint that fired.
+    fn watchhittext(&self) -> Option<String> {
+        let (watchpoint, pc) = self.watchhit?;
+
+        // Locate the PC in the ELF.
+        let location = match self.elf.as_ref().and_then(|elf| elf.symbolat(pc)) {
+            Some(symbol) => format!("0x{:08X} ({}+0x{:X})", pc, symbol.name, pc - symbol.address),
+            _ => format!("0x{:08X}", pc),
+        };
+
+        Some( match watchpoint {
+            Some(w) => format!("Watchpoint {} ({} of {}) fired at PC {}", w.comparator, w.kind, self.watchlabel(&w), location),
+            _ => format!("Halted on an unknown watchpoint at PC {}", location),
+        })
+    }
+
+    /// Builds the label of a watchpoint.
+    fn watchlabel(&self, watchpoint: &Watchpoint) -> String {
+        match self.watchlabels.get(&watchpoint.address) {
+            Some(name) => format!("{} @ 0x{:08X} [{} bytes]", name, watchpoint.address, watchpoint.size),
+            _ => format!("0x{:08X} [{} bytes]", watchpoint.address, watchpoint.size),
+        }
+    }
+
+    /// Reads the registers of the halted core, unwinds its call stack and analyzes its fault.
+    fn inspect(&mut self) -> Command<Message> {
+        let backtrace = match (self.elf.clone(), self.unwindable()) {
+            (Some(elf), true) => self.request( ProbeCommand::Backtrace(elf) ),
+            _ => Command::none(),
+        };
+
+        // Faults are analyzed without an ELF, only the symbols are missing.
+        let fault = match self.unwindable() {
+            true => self.request( ProbeCommand::Fault(self.elf.clone()) ),
             _ => Command::none(),
+        };
+
+        Command::batch([ self.request( ProbeCommand::Registers ), backtrace, fault, self.readperipheral() ])
+    }
+
+    /// Returns `true` if the call stack and the faults of the current core can be analyzed.
+    fn unwindable(&self) -> bool {
+        self.cores.iter()
+            .find(|c| Some(c.index) == self.selcore)
+            .map(|c| c.coretype != CoreType::Riscv)
+            .unwrap_or(false)
+    }
+
+    /// Returns `true` if the current core is known to be halted.
+    fn halted(&self) -> bool {
+        match self.status {
+            Some(CoreStatus::Halted(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the current core can be halted.
+    fn haltable(&self) -> bool {
+        match self.status {
+            Some(CoreStatus::Running) | Some(CoreStatus::Sleeping) | Some(CoreStatus::LockedUp) | Some(CoreStatus::Unknown) => true,
+            _ => false,
         }
     }
 
@@ -140,6 +1309,25 @@ impl ProbeView {
 
     /// Builds the GUI view for probe interaction.
     pub fn view(&mut self) -> Element<Message> {
+        // Get the valid commands for the current state.
+        // The widgets borrow the state, so this is done before building them.
+        let connected = self.probe.is_some();
+        let halted = self.halted();
+        let haltable = self.haltable();
+
+        // Labels of the active watchpoints and the text of the last watchpoint hit.
+        let wplabels: Vec<String> = match &self.watchpoints {
+            Some((_, active)) => active.iter()
+                .map(|w| format!("#{} {} of {}", w.comparator, w.kind, self.watchlabel(w)))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let watchhit = match halted {
+            true => self.watchhittext(),
+            _ => None,
+        };
+
         // Create top bar.
         let topbar = {
             // Create project selector.
@@ -159,6 +1347,22 @@ impl ProbeView {
                     .push(select)
             };
 
+            // Create target selector.
+            let target = {
+                let text = Text::new("Target").size(20);
+                let select = PickList::new(
+                    &mut self.state.targetlist,
+                    &self.targets,
+                    self.seltarget.clone(),
+                    |s| { Message::Probe( ProbeMessage::TargetSelected(s) ) }
+                );
+
+                Row::new()
+                    .spacing(7)
+                    .push(text)
+                    .push(select)
+            };
+
             // Create probe selector.
             let probe = {
                 let text = Text::new("Probe").size(20);
@@ -166,7 +1370,7 @@ impl ProbeView {
                     &mut self.state.probelist,
                     &self.probenames,
                     self.selprobe.clone(),
-                    |s| { Message::ProjectSelected( String::from(s) ) }
+                    |s| { Message::Probe( ProbeMessage::ProbeSelected(s) ) }
                 );
                 //.placeholder("Select project...");
 
@@ -176,13 +1380,34 @@ impl ProbeView {
                     .push(select)
             };
 
+            // Create the connect button.
+            let connect = {
+                let (label, msg) = match (self.probe.is_some(), self.connecting) {
+                    (true, _) => ("Disconnect", Some(ProbeMessage::Disconnect)),
+                    (_, true) => ("Connecting...", None),
+                    _ => ("Connect", Some(ProbeMessage::Connect)),
+                };
+
+                let button = Button::new(&mut self.state.button.connect, Text::new(label).size(14))
+                    .height(Length::Shrink);
+
+                match (msg, self.selprobe.is_some() && self.seltarget.is_some()) {
+                    (Some(ProbeMessage::Disconnect), _) => button.on_press(Message::Probe( ProbeMessage::Disconnect )),
+                    (Some(msg), true) => button.on_press(Message::Probe(msg)),
+                    _ => button,
+                }
+            };
+
             Row::new()
                 .padding(5)
                 .spacing(5)
                 .height(Length::Shrink)
                 .width(Length::Fill)
+                .align_items(Align::Center)
                 .push(project)
+                .push(target)
                 .push(probe)
+                .push(connect)
         };
 
         // Create command section.
@@ -191,36 +1416,113 @@ impl ProbeView {
             let coreselect = {
                 let text = Text::new("Core: ").size(14);
 
-                Row::new()
+                let indices: Vec<usize> = self.cores.iter().map(|c| c.index).collect();
+                let selcore = self.selcore;
+
+                let select = PickList::new(
+                    &mut self.state.corelist,
+                    indices,
+                    selcore,
+                    |c| { Message::Probe( ProbeMessage::CoreSelected(c) ) }
+                )
+                .padding(2)
+                .text_size(14)
+                .width(Length::Shrink);
+
+                let status = Text::new( statustext(&self.status) ).size(14);
+
+                let row = Row::new()
+                    .spacing(5)
+                    .align_items(Align::Center)
                     .push(text)
+                    .push(select)
+                    .push(status);
+
+                // Show the run state of all the cores in multicore targets.
+                match self.cores.len() {
+                    0 | 1 => Column::new().push(row),
+
+                    _ => self.cores.iter()
+                        .fold(Column::new().push(row), |col, core| {
+                            let marker = match Some(core.index) == selcore {
+                                true => "*",
+                                _ => " ",
+                            };
+
+                            let text = format!("{} Core {} ({}): {}", marker, core.index, core.architecture(), statustext(&core.status));
+
+                            col.push( Text::new(text).size(12) )
+                        }),
+                }
+            };
+
+            // Create the core dump row.
+            let coredump = {
+                let cansave = connected && self.offline.is_none() && halted && self.dumppath.is_none();
+                let canopen = !connected && !self.connecting;
+
+                self.state.coredump.view(cansave, canopen, self.offline.as_ref())
             };
 
             // Create left side button commands.
             let left = {
                 let position = Position::Right;
 
-                // Create the Load button.
+                // Create the Load button. It cancels the flash operation while flashing.
                 let load = {
-                    let key = String::from("load");
+                    let flashing = self.flashing.is_some();
 
-                    let button = Button::new(&mut self.state.button.load, Text::new("Load").size(14))
-                        .on_press(Message::Probe( ProbeMessage::Load ))
+                    let label = match flashing {
+                        true => "Cancel",
+                        _ => "Load",
+                    };
+
+                    let button = Button::new(&mut self.state.button.load, Text::new(label).size(14))
                         .height(Length::Shrink)
                         .width(Length::Fill);
 
-                    let tip = "Loads the current ELF or binary into the target";
+                    let button = match (flashing, connected && self.elf.is_some()) {
+                        (true, _) => button.on_press(Message::Probe( ProbeMessage::CancelLoad )),
+                        (_, true) => button.on_press(Message::Probe( ProbeMessage::Load )),
+                        _ => button,
+                    };
+
+                    let tip = match flashing {
+                        true => "Cancels loading the ELF into the target",
+                        _ => "Loads the current ELF into the target",
+                    };
 
                     Tooltip::new(button, tip, position)
                         .padding(5)
                         .gap(2)
                 };
 
+                // Create the Verify button.
+                let verifyinner = Button::new(&mut self.state.button.verify, Text::new("Verify").size(14))
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                let verifyinner = match connected && self.elf.is_some() && self.flashing.is_none() {
+                    true => verifyinner.on_press(Message::Probe( ProbeMessage::Verify )),
+                    _ => verifyinner,
+                };
+
+                let verifytip = "Compares the target memory with the current ELF";
+
+                let verify = Tooltip::new(verifyinner, verifytip, position)
+                    .padding(5)
+                    .gap(2);
+
                 // Create the Stop button.
                 let stopinner = Button::new(&mut self.state.button.stop, Text::new("Stop").size(14))
-                    .on_press(Message::Probe( ProbeMessage::Stop ))
                     .height(Length::Shrink)
                     .width(Length::Fill);
 
+                let stopinner = match haltable {
+                    true => stopinner.on_press(Message::Probe( ProbeMessage::Stop )),
+                    _ => stopinner,
+                };
+
                 let stoptip = "Stops the execution of the current target core";
 
                 let stop = Tooltip::new(stopinner, stoptip, position)
@@ -229,22 +1531,46 @@ impl ProbeView {
 
                 // Create the Reset button.
                 let resetinner = Button::new(&mut self.state.button.reset, Text::new("Reset").size(14))
-                    .on_press(Message::Probe( ProbeMessage::Reset ))
                     .height(Length::Shrink)
                     .width(Length::Fill);
 
+                let resetinner = match connected {
+                    true => resetinner.on_press(Message::Probe( ProbeMessage::Reset )),
+                    _ => resetinner,
+                };
+
                 let resettip = "Resets the current target core";
 
                 let reset = Tooltip::new(resetinner, resettip, position)
                     .padding(5)
                     .gap(2);
 
+                // Create the Reset and halt button.
+                let resethaltinner = Button::new(&mut self.state.button.resethalt, Text::new("Reset & halt").size(14))
+                    .height(Length::Shrink)
+                    .width(Length::Fill);
+
+                let resethaltinner = match connected {
+                    true => resethaltinner.on_press(Message::Probe( ProbeMessage::ResetHalt )),
+                    _ => resethaltinner,
+                };
+
+                let resethalttip = "Resets the current target core and halts it before the first instruction";
+
+                let resethalt = Tooltip::new(resethaltinner, resethalttip, position)
+                    .padding(5)
+                    .gap(2);
+
                 // Create the Run button.
                 let runinner = Button::new(&mut self.state.button.run, Text::new("Run").size(14))
-                    .on_press(Message::Probe( ProbeMessage::Run ))
                     .height(Length::Shrink)
                     .width(Length::Fill);
 
+                let runinner = match halted {
+                    true => runinner.on_press(Message::Probe( ProbeMessage::Run )),
+                    _ => runinner,
+                };
+
                 let runtip = "Continues execution of the current target core";
 
                 let run = Tooltip::new(runinner, runtip, position)
@@ -253,10 +1579,14 @@ impl ProbeView {
 
                 // Create the Step button.
                 let stepinner = Button::new(&mut self.state.button.step, Text::new("Step").size(14))
-                    .on_press(Message::Probe( ProbeMessage::Step ))
                     .height(Length::Shrink)
                     .width(Length::Fill);
 
+                let stepinner = match halted {
+                    true => stepinner.on_press(Message::Probe( ProbeMessage::Step )),
+                    _ => stepinner,
+                };
+
                 let steptip = "Steps the current target core one instruction";
 
                 let step = Tooltip::new(stepinner, steptip, position)
@@ -265,10 +1595,14 @@ impl ProbeView {
 
                 // Create the Dump register button.
                 let dumpinner = Button::new(&mut self.state.button.dump, Text::new("Dump registers").size(14))
-                    .on_press(Message::Probe( ProbeMessage::Dump ))
                     .height(Length::Shrink)
                     .width(Length::Fill);
 
+                let dumpinner = match halted {
+                    true => dumpinner.on_press(Message::Probe( ProbeMessage::Dump )),
+                    _ => dumpinner,
+                };
+
                 let dumptip = "Dumps the current target core's registers";
 
                 let dump = Tooltip::new(dumpinner, dumptip, position)
@@ -283,8 +1617,10 @@ impl ProbeView {
                     .max_width(125)
                     .align_items(Align::Center)
                     .push(load)
+                    .push(verify)
                     .push(stop)
                     .push(reset)
+                    .push(resethalt)
                     .push(run)
                     .push(step)
                     .push(dump)
@@ -432,12 +1768,216 @@ impl ProbeView {
             };
 
 
+            // Create the breakpoint list.
+            let breakpoints = {
+                let units = match &self.bpunits {
+                    Some((units, active)) => format!("Breakpoints ({} / {} units)", active.len(), units),
+                    _ => String::from("Breakpoints"),
+                };
+
+                let header = Text::new(units).size(14);
+
+                // The location input.
+                let location = TextInput::new(
+                    &mut self.state.textinput.breakpoint,
+                    "Address or symbol",
+                    &self.state.textinput.breakpointval,
+                    |s| { Message::Probe( ProbeMessage::BreakpointInputChanged(s) ) }
+                )
+                .padding(5)
+                .size(14)
+                .width(Length::Fill)
+                .on_submit(Message::Probe( ProbeMessage::AddBreakpoint ));
+
+                let add = Button::new(&mut self.state.button.addbp, Text::new("Add").size(14))
+                    .on_press(Message::Probe( ProbeMessage::AddBreakpoint ))
+                    .height(Length::Shrink);
+
+                let input = Row::new()
+                    .spacing(5)
+                    .push(location)
+                    .push(add);
+
+                let scrollable = Scrollable::new(&mut self.state.bpscroll)
+                    .spacing(2)
+                    .padding(5)
+                    .height(Length::Units(150))
+                    .width(Length::Fill)
+                    .scrollbar_margin(2)
+                    .scrollbar_width(5)
+                    .scroller_width(10);
+
+                let active = match &self.bpunits {
+                    Some((_, active)) => active.clone(),
+                    _ => Vec::new(),
+                };
+
+                let elf = self.elf.clone();
+
+                let list = match &self.project {
+                    Some(project) => project.breakpoints.iter()
+                        .zip(self.state.breakpoints.iter_mut())
+                        .enumerate()
+                        .fold(scrollable, |col, (i, (bp, remove))| {
+                            // Resolve the location for display.
+                            let address = match &bp.location {
+                                Location::Address(a) => Some(*a),
+                                Location::Symbol(name) => elf.as_ref().and_then(|elf| elf.symbol(name)).map(|s| s.address),
+                            };
+
+                            let label = match address {
+                                Some(a) if active.contains(&a) => format!("{} @ 0x{:08X} (set)", bp.location, a),
+                                Some(a) => format!("{} @ 0x{:08X}", bp.location, a),
+                                None => format!("{} (unresolved)", bp.location),
+                            };
+
+                            let toggle = Checkbox::new(bp.enabled, label, move |b| { Message::Probe( ProbeMessage::ToggleBreakpoint(i, b) ) })
+                                .size(14)
+                                .text_size(14)
+                                .width(Length::Fill);
+
+                            let remove = Button::new(remove, Text::new("Remove").size(12))
+                                .on_press(Message::Probe( ProbeMessage::RemoveBreakpoint(i) ));
+
+                            col.push(
+                                Row::new()
+                                    .spacing(5)
+                                    .align_items(Align::Center)
+                                    .push(toggle)
+                                    .push(remove)
+                            )
+                        }),
+
+                    _ => scrollable,
+                };
+
+                Column::new()
+                    .padding(5)
+                    .spacing(5)
+                    .max_width(475)
+                    .push(header)
+                    .push(input)
+                    .push(list)
+            };
+
+            // Create the watchpoint list.
+            let watchpoints = {
+                let comparators: Vec<usize> = match &self.watchpoints {
+                    Some((_, active)) => active.iter().map(|w| w.comparator).collect(),
+                    _ => Vec::new(),
+                };
+
+                let units = match &self.watchpoints {
+                    Some((units, active)) => format!("Watchpoints ({} / {} comparators)", active.len(), units),
+                    _ => String::from("Watchpoints"),
+                };
+
+                let header = Text::new(units).size(14);
+
+                // The location input.
+                let location = TextInput::new(
+                    &mut self.state.textinput.watchpoint,
+                    "Address or symbol",
+                    &self.state.textinput.watchpointval,
+                    |s| { Message::Probe( ProbeMessage::WatchpointInputChanged(s) ) }
+                )
+                .padding(5)
+                .size(14)
+                .width(Length::Fill)
+                .on_submit(Message::Probe( ProbeMessage::AddWatchpoint ));
+
+                // The size input.
+                let size = TextInput::new(
+                    &mut self.state.textinput.watchsize,
+                    "Size",
+                    &self.state.textinput.watchsizeval,
+                    |s| { Message::Probe( ProbeMessage::WatchpointSizeChanged(s) ) }
+                )
+                .padding(5)
+                .size(14)
+                .width(Length::Units(60))
+                .on_submit(Message::Probe( ProbeMessage::AddWatchpoint ));
+
+                // The picklist for the access kind.
+                let kind = PickList::new(
+                    &mut self.state.watchkind,
+                    &WATCHKINDS[..],
+                    self.state.selwatchkind.clone(),
+                    |k| { Message::Probe( ProbeMessage::WatchKind(k) ) }
+                )
+                .padding(4)
+                .width(Length::Shrink);
+
+                let add = Button::new(&mut self.state.button.addwp, Text::new("Add").size(14))
+                    .height(Length::Shrink);
+
+                let add = match connected {
+                    true => add.on_press(Message::Probe( ProbeMessage::AddWatchpoint )),
+                    _ => add,
+                };
+
+                let input = Row::new()
+                    .spacing(5)
+                    .push(location)
+                    .push(size)
+                    .push(kind)
+                    .push(add);
+
+                let scrollable = Scrollable::new(&mut self.state.wpscroll)
+                    .spacing(2)
+                    .padding(5)
+                    .height(Length::Units(100))
+                    .width(Length::Fill)
+                    .scrollbar_margin(2)
+                    .scrollbar_width(5)
+                    .scroller_width(10);
+
+                let list = wplabels.into_iter()
+                    .zip(comparators.into_iter())
+                    .zip(self.state.watchpoints.iter_mut())
+                    .fold(scrollable, |col, ((label, comparator), remove)| {
+                        let text = Text::new(label)
+                            .size(14)
+                            .width(Length::Fill);
+
+                        let remove = Button::new(remove, Text::new("Remove").size(12))
+                            .on_press(Message::Probe( ProbeMessage::RemoveWatchpoint(comparator) ));
+
+                        col.push(
+                            Row::new()
+                                .spacing(5)
+                                .align_items(Align::Center)
+                                .push(text)
+                                .push(remove)
+                        )
+                    });
+
+                Column::new()
+                    .padding(5)
+                    .spacing(5)
+                    .max_width(475)
+                    .push(header)
+                    .push(input)
+                    .push(list)
+            };
+
+            // Create the live watch list.
+            let livewatch = self.state.live.view(self.project.as_ref(), self.sampling.as_ref());
+
+            // Create the snapshot list.
+            let capture = connected && halted && self.snapshotname.is_none();
+            let snapshots = self.state.snapshot.view(self.project.as_ref(), capture);
+
+            // Create the peripheral tree.
+            let peripherals = self.state.svd.view(self.svd.as_deref(), self.selperipheral, &self.openregisters, &self.svdvalues, connected && halted);
+
             Column::new()
                 .padding(5)
                 .spacing(5)
                 .height(Length::Shrink)
                 .width(Length::Shrink)
                 .push(coreselect)
+                .push(coredump)
                 .push(
                     Row::new()
                         .padding(5)
@@ -447,6 +1987,11 @@ impl ProbeView {
                         .push(left)
                         .push(right)
                 )
+                .push(breakpoints)
+                .push(watchpoints)
+                .push(livewatch)
+                .push(snapshots)
+                .push(peripherals)
         };
 
         // Create display.
@@ -454,19 +1999,123 @@ impl ProbeView {
             // Text of the display.
             let text = Text::new("Command display").size(14);
 
-            Container::new(text)
+            let mut column = Column::new()
+                .push(text);
+
+            if let Some(e) = &self.lasterror {
+                column = column.push( Text::new( format!("Error: {}", e) ).size(14) );
+            }
+
+            if let Some(flashing) = &self.flashing {
+                column = column
+                    .push( Text::new( flashing.text() ).size(14) )
+                    .push( ProgressBar::new(0.0..=1.0, flashing.fraction()).height(Length::Units(10)) );
+            }
+
+            match &self.mismatches {
+                Some(mismatches) if mismatches.len() == 0 => {
+                    column = column.push( Text::new("Verification passed, the target matches the ELF").size(14) );
+                },
+
+                Some(mismatches) => {
+                    column = column.push( Text::new( format!("Verification failed, {} ranges differ from the ELF:", mismatches.len()) ).size(14).color(CHANGED) );
+
+                    for mismatch in mismatches.iter() {
+                        column = column.push( Text::new( format!("  {}", mismatch) ).size(14) );
+                    }
+                },
+
+                _ => (),
+            }
+
+            if let Some((before, after, changes)) = &self.snapshotdiff {
+                column = column.push( snapshot::diff(before, after, changes) );
+            }
+
+            if let Some(hit) = watchhit {
+                column = column.push( Text::new(hit).size(14).color(CHANGED) );
+            }
+
+            if let (true, Some(fault)) = (halted, &self.fault) {
+                column = column.push( self.state.fault.view(fault) );
+            }
+
+            if let (true, Some(frames)) = (halted, &self.backtrace) {
+                column = column.push( backtrace::view(frames) );
+            }
+
+            // Create the plot of the live watches.
+            if self.sampling.is_some() {
+                column = column.push( self.state.plot.view(&mut self.plot) );
+            }
+
+            // Create the register table.
+            if self.registers.len() > 0 {
+                let scrollable = Scrollable::new(&mut self.state.regscroll)
+                    .spacing(2)
+                    .padding(5)
+                    .height(Length::Fill)
+                    .width(Length::Fill)
+                    .scrollbar_margin(2)
+                    .scrollbar_width(5)
+                    .scroller_width(10);
+
+                let baseline = &self.baseline;
+
+                let table = self.registers.iter()
+                    .zip(self.state.registers.iter_mut())
+                    .enumerate()
+                    .fold(scrollable, |col, (i, (register, (input, value)))| {
+                        // Highlight the register if it changed since the last halt.
+                        let color = match baseline.get(&register.address) {
+                            Some(old) if *old != register.value => CHANGED,
+                            _ => Color::BLACK,
+                        };
+
+                        let name = Text::new( register.name.clone() )
+                            .size(14)
+                            .width(Length::Units(80))
+                            .color(color);
+
+                        let current = Text::new( format!("0x{:08X}", register.value) )
+                            .size(14)
+                            .width(Length::Units(100))
+                            .color(color);
+
+                        let edit = TextInput::new(
+                            input,
+                            "New value",
+                            value,
+                            move |s| { Message::Probe( ProbeMessage::RegisterValueChanged(i, s) ) }
+                        )
+                        .padding(2)
+                        .size(14)
+                        .width(Length::Units(120))
+                        .on_submit( Message::Probe( ProbeMessage::WriteRegister(i) ) );
+
+                        let row = Row::new()
+                            .spacing(5)
+                            .align_items(Align::Center)
+                            .push(name)
+                            .push(current)
+                            .push(edit);
+
+                        col.push(row)
+                    });
+
+                column = column.push(table);
+            }
+
+            Container::new(column)
                 .height(Length::Fill)
                 .width(Length::Fill)
         };
 
         // Create Console / Log / Events.
         let console = {
-            // Text of the display.
-            let text = Text::new("Console display").size(14);
+            let trace = self.state.trace.view(self.trace.as_ref(), self.tab);
 
-            Container::new(text)
-                .height(Length::FillPortion(15))
-                .width(Length::Fill)
+            self.state.console.view(self.probe.is_some(), self.tab, self.console.as_ref(), self.semihost.as_ref(), self.trace.as_ref(), trace)
         };
 
 
@@ -491,3 +2140,141 @@ impl ProbeView {
             .into()
     }
 }
+
+
+
+/// Builds the text that describes the status of a core.
+fn statustext(status: &Option<CoreStatus>) -> String {
+    match status {
+        None => String::from("Not connected"),
+
+        Some(status) => match status {
+            CoreStatus::Halted(reason) => format!("Halted ({:?})", reason),
+            CoreStatus::LockedUp => String::from("Locked up"),
+            CoreStatus::Running => String::from("Running"),
+            CoreStatus::Sleeping => String::from("Sleeping"),
+            CoreStatus::Unknown => String::from("Unknown"),
+        },
+    }
+}
+
+
+
+/// Async function to attach to a target and spawn the probe command loop.
+async fn connect(chipdb: Option<DBInterface<ChipCommand, ChipResponse>>, info: DebugProbeInfo, chip: String) -> ProbeMessage {
+    // Get the byte order of the chip.
+    let endianness = match chipdb {
+        Some(interface) => match getchip(interface, chip.clone()).await {
+            Some(chip) => Endianness::of(&chip),
+            _ => {
+                warn!(origin="app", view="probe", "Chip {} is not in the chip database, assuming little endian", chip);
+                Endianness::default()
+            },
+        },
+
+        _ => Endianness::default(),
+    };
+
+    // Attaching blocks on USB access.
+    let probeinfo = info.clone();
+    let attach = tokio::task::spawn_blocking(move || OpenProbe::create(probeinfo, chip, endianness));
+
+    match attach.await {
+        Ok(Ok((mut probe, channel))) => {
+            tokio::spawn( async move { probe.run().await }.with_current_subscriber() );
+
+            ProbeMessage::Connected(info, channel)
+        },
+
+        Ok(Err(e)) => ProbeMessage::ConnectFailed( e.to_string() ),
+
+        Err(e) => ProbeMessage::ConnectFailed( e.to_string() ),
+    }
+}
+
+/// Async function to get a chip from the chip database.
+async fn getchip(mut interface: DBInterface<ChipCommand, ChipResponse>, name: String) -> Option<Chip> {
+    // Create a command response pair.
+    let (cmd, res) = DBCommand::create( ChipCommand::GetChip(name) );
+
+    match interface.send(cmd).await {
+        Err(e) => {
+            error!(origin="app", view="probe", "Could not send a 'GetChip' command: {}", e);
+            return None;
+        },
+        _ => (),
+    }
+
+    match res.response().await {
+        Some(r) => match r {
+            ChipResponse::Chip(chip) => Some(chip),
+            _ => None,
+        },
+        _ => {
+            error!(origin="app", view="probe", "Channel closed before a response to 'GetChip' command was received");
+            None
+        },
+    }
+}
+
+/// Async function to get the project database.
+async fn getdb(mut interface: DBInterface<ProjectCommand, ProjectResponse>) -> ProbeMessage {
+    // Create a command response pair.
+    let (cmd, res) = DBCommand::create( ProjectCommand::GetSearchEngine );
+
+    match interface.send(cmd).await {
+        Err(e) => {
+            error!(origin="app", view="probe", "Could not send a 'GetSearchEngine' command: {}", e);
+            return ProbeMessage::ProjectUpdateFailed;
+        },
+        _ => (),
+    }
+
+    match res.response().await {
+        Some(r) => match r {
+            ProjectResponse::SearchEngine(projects, _) => ProbeMessage::ProjectDatabase(projects),
+            _ => {
+                error!(origin="app", view="probe", "Unknown response to 'GetSearchEngine' command");
+                ProbeMessage::ProjectUpdateFailed
+            },
+        },
+        _ => {
+            error!(origin="app", view="probe", "Channel closed before a response to 'GetSearchEngine' command was received");
+            ProbeMessage::ProjectUpdateFailed
+        },
+    }
+}
+
+/// Async function to update a `ProjectSerial`.
+async fn updateproject(mut interface: DBInterface<ProjectCommand, ProjectResponse>, project: ProjectSerial) -> Message {
+    // Create a command response pair.
+    let (cmd, res) = DBCommand::create( ProjectCommand::UpdateProject(project) );
+
+    match interface.send(cmd).await {
+        Err(e) => {
+            error!(origin="app", view="probe", "Could not send an 'UpdateProject' command: {}", e);
+            return Message::Probe( ProbeMessage::ProjectUpdateFailed );
+        },
+        _ => (),
+    }
+
+    match res.response().await {
+        Some(r) => match r {
+            ProjectResponse::Done => Message::UpdateProjectDatabase,
+
+            ProjectResponse::NotSaved => {
+                error!(origin="app", view="probe", "The project was updated but could not be saved");
+                Message::Probe( ProbeMessage::ProjectUpdateFailed )
+            },
+
+            _ => {
+                error!(origin="app", view="probe", "Unknown response to 'UpdateProject' command");
+                Message::Probe( ProbeMessage::ProjectUpdateFailed )
+            },
+        },
+        _ => {
+            error!(origin="app", view="probe", "Channel closed before a response to 'UpdateProject' command was received");
+            Message::Probe( ProbeMessage::ProjectUpdateFailed )
+        },
+    }
+}
@@ -0,0 +1,451 @@
+//! Peripheral tree of the probe GUI view.
+//! Browses the registers of the peripherals in the SVD of the target and decodes their fields.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::Command as ProbeCommand,
+
+    svd,
+};
+
+use iced::{
+    Command, Column, Element, Row,
+
+    Align, Length,
+
+    Scrollable, Text, PickList, TextInput,
+
+    button::{ self, Button },
+    pick_list, scrollable, text_input,
+    tooltip::{ Position, Tooltip },
+};
+
+use std::{
+    collections::{ HashMap, HashSet },
+    sync::Arc,
+};
+
+use tracing::{
+    error, info, warn,
+
+    instrument::WithSubscriber,
+};
+
+use super::{ common, error, ProbeView };
+
+
+
+/// Widget states of the peripheral tree.
+#[derive(Default)]
+pub(super) struct PeripheralState {
+    /// State of the read button.
+    read: button::State,
+
+    /// Tree scroll state.
+    scroll: scrollable::State,
+
+    /// Button states of the peripherals of the tree.
+    peripherals: Vec<button::State>,
+
+    /// Button states of the registers of the selected peripheral.
+    registers: Vec<button::State>,
+
+    /// Widget states of the fields of each register of the selected peripheral.
+    fields: Vec<Vec<FieldState>>,
+}
+
+impl PeripheralState {
+    /// Builds the peripheral tree of the device with the selected peripheral and its open registers expanded.
+    /// The peripheral can be read and its fields written if `writable`.
+    pub(super) fn view<'a>(&'a mut self, device: Option<&svd::Device>, selected: Option<usize>, open: &HashSet<usize>, values: &HashMap<u32, u32>, writable: bool) -> Column<'a, Message> {
+        let header = match device {
+            Some(device) => format!("Peripherals of {}", device.name),
+            _ => String::from("Peripherals (no SVD loaded)"),
+        };
+
+        let read = Button::new(&mut self.read, Text::new("Read").size(14))
+            .height(Length::Shrink);
+
+        let read = match writable && selected.is_some() {
+            true => read.on_press(Message::Probe( ProbeMessage::ReadPeripheral )),
+            _ => read,
+        };
+
+        let top = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(Text::new(header).size(14).width(Length::Fill))
+            .push(read);
+
+        let scrollable = Scrollable::new(&mut self.scroll)
+            .spacing(2)
+            .padding(5)
+            .height(Length::Units(300))
+            .width(Length::Fill)
+            .scrollbar_margin(2)
+            .scrollbar_width(5)
+            .scroller_width(10);
+
+        let mut registerstates = self.registers.iter_mut();
+        let mut fieldstates = self.fields.iter_mut();
+
+        let tree = match device {
+            Some(device) => device.peripherals.iter()
+                .zip(self.peripherals.iter_mut())
+                .enumerate()
+                .fold(scrollable, |mut col, (i, (peripheral, state))| {
+                    let marker = if selected == Some(i) { "-" } else { "+" };
+
+                    let button = Button::new(state, Text::new( format!("{} {} @ 0x{:08X}", marker, peripheral.name, peripheral.address) ).size(14))
+                        .on_press(Message::Probe( ProbeMessage::SelectPeripheral(i) ))
+                        .width(Length::Fill);
+
+                    col = match &peripheral.description {
+                        Some(description) => col.push( Tooltip::new(button, description, Position::Top).padding(5).gap(2) ),
+                        _ => col.push(button),
+                    };
+
+                    if selected != Some(i) {
+                        return col;
+                    }
+
+                    for (j, ((register, state), fields)) in peripheral.registers.iter().zip(registerstates.by_ref()).zip(fieldstates.by_ref()).enumerate() {
+                        let value = values.get(&register.word()).map(|w| register.value(*w));
+                        let digits = ((register.size + 3) / 4).max(1) as usize;
+
+                        let value = value.filter(|_| register.readable);
+
+                        let text = match (value, register.readable) {
+                            (_, false) => format!("    {} @ 0x{:08X} (not readable)", register.name, register.address),
+                            (Some(v), _) => format!("    {} @ 0x{:08X} = 0x{:0w$X}", register.name, register.address, v, w = digits),
+                            _ => format!("    {} @ 0x{:08X}", register.name, register.address),
+                        };
+
+                        let button = Button::new(state, Text::new(text).size(14))
+                            .on_press(Message::Probe( ProbeMessage::ToggleRegister(j) ))
+                            .width(Length::Fill);
+
+                        col = match &register.description {
+                            Some(description) => col.push( Tooltip::new(button, description, Position::Top).padding(5).gap(2) ),
+                            _ => col.push(button),
+                        };
+
+                        if !open.contains(&j) {
+                            continue;
+                        }
+
+                        // Decode the fields of the last value read.
+                        for (k, (field, fieldstate)) in register.fields.iter().zip(fields.iter_mut()).enumerate() {
+                            let current = value.filter(|_| field.readable).map(|v| field.extract(v));
+
+                            let text = match current {
+                                Some(f) => match field.name(f) {
+                                    Some(name) => format!("        {} {} = {} ({})", field.name, field.bits(), f, name),
+                                    _ => format!("        {} {} = {}", field.name, field.bits(), f),
+                                },
+                                _ => format!("        {} {}", field.name, field.bits()),
+                            };
+
+                            let text = Text::new(text).size(14).width(Length::Fill);
+
+                            let label: Element<Message> = match &field.description {
+                                Some(description) => Tooltip::new(text, description, Position::Top).padding(5).gap(2).into(),
+                                _ => text.into(),
+                            };
+
+                            let row = Row::new()
+                                .spacing(5)
+                                .align_items(Align::Center)
+                                .push(label);
+
+                            // Read-only fields cannot be edited.
+                            if !field.writable {
+                                col = col.push(row);
+                                continue;
+                            }
+
+                            let choices: Vec<String> = field.choices().map(|(name, _)| String::from(name)).collect();
+
+                            let row = match choices.len() {
+                                0 => {
+                                    let input = TextInput::new(
+                                        &mut fieldstate.input,
+                                        &format!("0 - {}", field.max()),
+                                        &fieldstate.value,
+                                        move |s| { Message::Probe( ProbeMessage::FieldInputChanged(j, k, s) ) }
+                                    )
+                                    .padding(2)
+                                    .size(14)
+                                    .width(Length::Units(100));
+
+                                    let write = Button::new(&mut fieldstate.write, Text::new("Write").size(12));
+
+                                    let write = match writable {
+                                        true => write.on_press(Message::Probe( ProbeMessage::WriteField(j, k) )),
+                                        _ => write,
+                                    };
+
+                                    row.push(input).push(write)
+                                },
+
+                                _ => {
+                                    let chosen = current.and_then(|f| field.choices().find(|(_, v)| *v == f))
+                                        .map(|(name, _)| String::from(name));
+
+                                    let picklist = PickList::new(
+                                        &mut fieldstate.choice,
+                                        choices,
+                                        chosen,
+                                        move |s| { Message::Probe( ProbeMessage::FieldChoice(j, k, s) ) }
+                                    )
+                                    .padding(2)
+                                    .text_size(14);
+
+                                    row.push(picklist)
+                                },
+                            };
+
+                            col = col.push(row);
+                        }
+                    }
+
+                    col
+                }),
+
+            _ => scrollable,
+        };
+
+        Column::new()
+            .padding(5)
+            .spacing(5)
+            .max_width(475)
+            .push(top)
+            .push(tree)
+    }
+}
+
+
+
+/// Widget states of a register field of the peripheral tree.
+#[derive(Default)]
+pub(super) struct FieldState {
+    /// Named value picklist state.
+    choice: pick_list::State<String>,
+
+    /// Value input state.
+    input: text_input::State,
+
+    /// Current value input.
+    value: String,
+
+    /// Write button state.
+    write: button::State,
+}
+
+
+
+impl ProbeView {
+    /// Updates the SVD of the target and the peripheral tree.
+    pub(super) fn updateperipherals(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::SvdLoaded(device) => {
+                info!(origin="app", view="probe", "Loaded SVD of device {} with {} peripherals", device.name, device.peripherals.len());
+
+                self.state.svd.peripherals.resize_with(device.peripherals.len(), Default::default);
+                self.svd = Some(device);
+
+                Command::none()
+            },
+
+            ProbeMessage::SvdFailed(e) => {
+                error!(origin="app", view="probe", "{}", e);
+                self.lasterror = Some( error::Error::Svd(e) );
+                Command::none()
+            },
+
+            ProbeMessage::SelectPeripheral(i) => {
+                self.openregisters.clear();
+
+                if self.selperipheral == Some(i) {
+                    self.selperipheral = None;
+                    return Command::none();
+                }
+
+                let fields: Vec<usize> = match self.svd.as_ref().and_then(|d| d.peripherals.get(i)) {
+                    Some(peripheral) => peripheral.registers.iter().map(|r| r.fields.len()).collect(),
+                    _ => return Command::none(),
+                };
+
+                self.selperipheral = Some(i);
+                self.state.svd.registers.resize_with(fields.len(), Default::default);
+
+                self.state.svd.fields = fields.into_iter()
+                    .map(|n| (0..n).map(|_| Default::default()).collect())
+                    .collect();
+
+                self.readperipheral()
+            },
+
+            ProbeMessage::ToggleRegister(i) => {
+                if !self.openregisters.remove(&i) {
+                    self.openregisters.insert(i);
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::ReadPeripheral => self.readperipheral(),
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Loads the SVD of the selected target, or the SVD of its chip in the chip database.
+    pub(super) fn loadsvd(&mut self) -> Command<Message> {
+        self.svd = None;
+        self.selperipheral = None;
+        self.openregisters.clear();
+        self.svdvalues.clear();
+
+        let (path, chip) = match self.target() {
+            Some(target) => {
+                if !target.svd.is_empty() && target.svd().is_none() {
+                    warn!(origin="app", view="probe", "SVD file {} of the selected target does not exist", target.svd);
+                }
+
+                (target.svd(), target.target.clone())
+            },
+
+            _ => return Command::none(),
+        };
+
+        Command::perform(
+            loadsvd(path, chip).with_current_subscriber(),
+            |r| match r {
+                Ok(device) => Message::Probe( ProbeMessage::SvdLoaded( Arc::new(device) ) ),
+                Err(e) => Message::Probe( ProbeMessage::SvdFailed(e) ),
+            }
+        )
+    }
+
+    /// Reads the registers of the selected peripheral of the halted core.
+    pub(super) fn readperipheral(&self) -> Command<Message> {
+        if self.probe.is_none() || !self.halted() {
+            return Command::none();
+        }
+
+        let words = match (&self.svd, self.selperipheral) {
+            (Some(device), Some(i)) => device.peripherals.get(i).map(|p| p.words()).unwrap_or_default(),
+            _ => return Command::none(),
+        };
+
+        match words.len() {
+            0 => Command::none(),
+            _ => self.request( ProbeCommand::ReadWords(words) ),
+        }
+    }
+
+    /// Updates the field inputs of the peripheral tree and writes the fields.
+    pub(super) fn updatefields(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::FieldChoice(j, k, name) => {
+                let value = self.svdfield(j, k)
+                    .and_then(|(_, field)| field.choices().find(|(n, _)| *n == name).map(|(_, v)| v));
+
+                match value {
+                    Some(value) => self.writefield(j, k, value),
+                    _ => Command::none(),
+                }
+            },
+
+            ProbeMessage::FieldInputChanged(j, k, s) => {
+                if s.is_empty() || common::parse(&s).is_some() {
+                    if let Some(state) = self.state.svd.fields.get_mut(j).and_then(|f| f.get_mut(k)) {
+                        state.value = s;
+                    }
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::WriteField(j, k) => {
+                let input = match self.state.svd.fields.get(j).and_then(|f| f.get(k)) {
+                    Some(state) => state.value.clone(),
+                    _ => return Command::none(),
+                };
+
+                match common::parse(&input) {
+                    Some(value) => self.writefield(j, k, value),
+                    _ => {
+                        warn!(origin="app", view="probe", "Enter the value of the field");
+                        Command::none()
+                    },
+                }
+            },
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Returns the register and field in the given rows of the selected peripheral.
+    fn svdfield(&self, register: usize, field: usize) -> Option<(&svd::Register, &svd::Field)> {
+        let register = self.svd.as_ref()?
+            .peripherals.get(self.selperipheral?)?
+            .registers.get(register)?;
+
+        Some( (register, register.fields.get(field)?) )
+    }
+
+    /// Writes a value into a field of the selected peripheral with a read-modify-write of its register.
+    /// Fields cleared or set by the write are written with the value without effect.
+    fn writefield(&self, register: usize, field: usize, value: u32) -> Command<Message> {
+        let (register, field) = match self.svdfield(register, field) {
+            Some(found) => found,
+            _ => return Command::none(),
+        };
+
+        if !field.writable {
+            warn!(origin="app", view="probe", "Field {}.{} is read-only", register.name, field.name);
+            return Command::none();
+        }
+
+        if self.probe.is_none() || !self.halted() {
+            warn!(origin="app", view="probe", "Fields can only be written while the core is halted");
+            return Command::none();
+        }
+
+        let (keep, bits) = match register.write(field, value) {
+            Some(write) => write,
+            _ => {
+                warn!(origin="app", view="probe", "Value {} does not fit in field {}.{}, the maximum is {}", value, register.name, field.name, field.max());
+                return Command::none();
+            },
+        };
+
+        // Reading the register could clear its flags.
+        match register.readable {
+            true => info!(origin="app", view="probe", "Writing {} into field {}.{}", value, register.name, field.name),
+            _ => info!(origin="app", view="probe", "Writing {} into field {}.{} without reading register {}, its other fields are written as zeros", value, register.name, field.name, register.name),
+        }
+
+        self.request( ProbeCommand::Modify(register.word(), keep, bits) )
+    }
+}
+
+
+
+/// Async function to load the SVD file of a target, or the SVD of its chip in the chip database.
+async fn loadsvd(path: Option<std::path::PathBuf>, chip: String) -> Result<svd::Device, svd::Error> {
+    let path = match path {
+        Some(path) => path,
+        _ => match crate::database::chip::svdfile(&chip).await {
+            Some(path) => path,
+            _ => return Err( svd::Error::Io( format!("Chip {} has no SVD file", chip) ) ),
+        },
+    };
+
+    svd::Device::load(path).await
+}
@@ -0,0 +1,118 @@
+//! Plot of the live watches of the probe GUI view.
+
+
+
+use crate::gui::{
+    msg::{ Message, ProbeMessage },
+
+    widgets::Plot,
+};
+
+use iced::{
+    Command, Column, Row,
+
+    Align, Length,
+
+    Canvas, Text,
+
+    button::{ self, Button },
+};
+
+use super::ProbeView;
+
+
+
+/// Widget states of the plot controls.
+#[derive(Default)]
+pub(super) struct PlotState {
+    /// State of the pause button.
+    pause: button::State,
+
+    /// State of the zoom in button.
+    zoomin: button::State,
+
+    /// State of the zoom out button.
+    zoomout: button::State,
+
+    /// State of the clear button.
+    clear: button::State,
+}
+
+impl PlotState {
+    /// Builds the plot with its controls and the readouts of the visible samples.
+    pub(super) fn view<'a>(&'a mut self, plot: &'a mut Plot) -> Column<'a, Message> {
+        let paused = plot.paused();
+        let window = plot.window();
+
+        let pause = Button::new(&mut self.pause, Text::new(if paused { "Resume" } else { "Pause" }).size(14))
+            .on_press(Message::Probe( ProbeMessage::PlotPause(!paused) ))
+            .height(Length::Shrink);
+
+        let zoomin = Button::new(&mut self.zoomin, Text::new("Zoom in").size(14))
+            .on_press(Message::Probe( ProbeMessage::PlotZoom(true) ))
+            .height(Length::Shrink);
+
+        let zoomout = Button::new(&mut self.zoomout, Text::new("Zoom out").size(14))
+            .on_press(Message::Probe( ProbeMessage::PlotZoom(false) ))
+            .height(Length::Shrink);
+
+        let clear = Button::new(&mut self.clear, Text::new("Clear").size(14))
+            .on_press(Message::Probe( ProbeMessage::PlotClear ))
+            .height(Length::Shrink);
+
+        let controls = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(pause)
+            .push(zoomin)
+            .push(zoomout)
+            .push(clear)
+            .push(Text::new( format!("Last {:.1} s", window) ).size(14));
+
+        // Readouts of the visible samples.
+        let readouts = plot.stats().into_iter()
+            .fold(Column::new().spacing(2), |col, (name, color, stats)| {
+                let text = match stats {
+                    Some(s) => format!("{}: min {:.3}  max {:.3}  avg {:.3}", name, s.min, s.max, s.avg),
+                    _ => format!("{}: no numeric samples", name),
+                };
+
+                col.push( Text::new(text).size(14).color(color) )
+            });
+
+        let canvas = Canvas::new(plot)
+            .height(Length::Units(250))
+            .width(Length::Fill);
+
+        Column::new()
+            .push(controls)
+            .push(canvas)
+            .push(readouts)
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the plot of the live watches.
+    pub(super) fn updateplot(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::PlotPause(pause) => {
+                self.plot.pause(pause);
+                Command::none()
+            },
+
+            ProbeMessage::PlotZoom(zoomin) => {
+                self.plot.zoom( if zoomin { 0.5 } else { 2.0 } );
+                Command::none()
+            },
+
+            ProbeMessage::PlotClear => {
+                self.plot.clear();
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+}
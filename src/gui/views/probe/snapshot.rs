@@ -0,0 +1,337 @@
+//! Memory snapshots of the probe GUI view.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::Command as ProbeCommand,
+
+    project::{ Change, ProjectSerial },
+};
+
+use iced::{
+    Command, Column, Row,
+
+    Align, Length,
+
+    Scrollable, Text, PickList, TextInput,
+
+    button::{ self, Button },
+    pick_list, scrollable, text_input,
+};
+
+use tracing::{ info, warn };
+
+use super::{ common, ProbeView, CHANGED };
+
+
+
+/// Maximum number of snapshot differences shown.
+const MAXCHANGES: usize = 256;
+
+
+
+/// Widget states of the snapshot list.
+#[derive(Default)]
+pub(super) struct SnapshotState {
+    /// Name input state.
+    name: text_input::State,
+
+    /// Current value of the name input.
+    pub(super) nameval: String,
+
+    /// State of the snapshot range button.
+    range: button::State,
+
+    /// State of the snapshot RAM button.
+    ram: button::State,
+
+    /// Earlier snapshot picklist state.
+    before: pick_list::State<String>,
+
+    /// Currently selected earlier snapshot.
+    selbefore: Option<String>,
+
+    /// Later snapshot picklist state.
+    after: pick_list::State<String>,
+
+    /// Currently selected later snapshot.
+    selafter: Option<String>,
+
+    /// State of the diff button.
+    diff: button::State,
+
+    /// List scroll state.
+    scroll: scrollable::State,
+
+    /// Remove button states of the list.
+    remove: Vec<button::State>,
+}
+
+impl SnapshotState {
+    /// Builds the snapshot list of the project with its capture and comparison controls.
+    /// The capture buttons are enabled if a snapshot can be captured.
+    pub(super) fn view<'a>(&'a mut self, project: Option<&ProjectSerial>, capture: bool) -> Column<'a, Message> {
+        let header = Text::new("Memory snapshots").size(14);
+
+        // The name input.
+        let name = TextInput::new(
+            &mut self.name,
+            "Snapshot name",
+            &self.nameval,
+            |s| { Message::Probe( ProbeMessage::SnapshotNameChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Fill);
+
+        let range = Button::new(&mut self.range, Text::new("Read range").size(14))
+            .height(Length::Shrink);
+
+        let range = match capture {
+            true => range.on_press(Message::Probe( ProbeMessage::SnapshotRange )),
+            _ => range,
+        };
+
+        let ram = Button::new(&mut self.ram, Text::new("All RAM").size(14))
+            .height(Length::Shrink);
+
+        let ram = match capture {
+            true => ram.on_press(Message::Probe( ProbeMessage::SnapshotRam )),
+            _ => ram,
+        };
+
+        let input = Row::new()
+            .spacing(5)
+            .push(name)
+            .push(range)
+            .push(ram);
+
+        // The snapshots to compare.
+        let names: Vec<String> = project.iter()
+            .flat_map(|p| p.snapshots.iter())
+            .map(|s| s.name.clone())
+            .collect();
+
+        let before = PickList::new(
+            &mut self.before,
+            names.clone(),
+            self.selbefore.clone(),
+            |s| { Message::Probe( ProbeMessage::SnapshotBefore(s) ) }
+        )
+        .padding(4)
+        .text_size(14)
+        .width(Length::Fill);
+
+        let after = PickList::new(
+            &mut self.after,
+            names,
+            self.selafter.clone(),
+            |s| { Message::Probe( ProbeMessage::SnapshotAfter(s) ) }
+        )
+        .padding(4)
+        .text_size(14)
+        .width(Length::Fill);
+
+        let diff = Button::new(&mut self.diff, Text::new("Diff").size(14))
+            .height(Length::Shrink);
+
+        let diff = match self.selbefore.is_some() && self.selafter.is_some() {
+            true => diff.on_press(Message::Probe( ProbeMessage::DiffSnapshots )),
+            _ => diff,
+        };
+
+        let compare = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(before)
+            .push(Text::new("->").size(14))
+            .push(after)
+            .push(diff);
+
+        let scrollable = Scrollable::new(&mut self.scroll)
+            .spacing(2)
+            .padding(5)
+            .height(Length::Units(150))
+            .width(Length::Fill)
+            .scrollbar_margin(2)
+            .scrollbar_width(5)
+            .scroller_width(10);
+
+        let list = match project {
+            Some(project) => project.snapshots.iter()
+                .zip(self.remove.iter_mut())
+                .enumerate()
+                .fold(scrollable, |col, (i, (snapshot, remove))| {
+                    let name = Text::new( snapshot.name.clone() )
+                        .size(14)
+                        .width(Length::FillPortion(1));
+
+                    let size = Text::new( format!("{} bytes in {} regions", snapshot.size(), snapshot.regions.len()) )
+                        .size(14)
+                        .width(Length::FillPortion(1));
+
+                    let remove = Button::new(remove, Text::new("Remove").size(12))
+                        .on_press(Message::Probe( ProbeMessage::RemoveSnapshot(i) ));
+
+                    col.push(
+                        Row::new()
+                            .spacing(5)
+                            .align_items(Align::Center)
+                            .push(name)
+                            .push(size)
+                            .push(remove)
+                    )
+                }),
+
+            _ => scrollable,
+        };
+
+        Column::new()
+            .padding(5)
+            .spacing(5)
+            .max_width(475)
+            .push(header)
+            .push(input)
+            .push(compare)
+            .push(list)
+    }
+}
+
+
+
+/// Builds the differences between two snapshots, up to `MAXCHANGES` of them.
+pub(super) fn diff<'a>(before: &str, after: &str, changes: &[Change]) -> Column<'a, Message> {
+    let mut column = Column::new();
+
+    match changes.len() {
+        0 => column = column.push( Text::new( format!("No changes between snapshots '{}' and '{}'", before, after) ).size(14) ),
+
+        n => {
+            column = column.push( Text::new( format!("{} ranges changed between snapshots '{}' and '{}':", n, before, after) ).size(14).color(CHANGED) );
+
+            for change in changes.iter().take(MAXCHANGES) {
+                column = column.push( Text::new( format!("  {}", change) ).size(14) );
+            }
+
+            if n > MAXCHANGES {
+                column = column.push( Text::new( format!("  ... and {} more", n - MAXCHANGES) ).size(14) );
+            }
+        },
+    }
+
+    column
+}
+
+
+
+impl ProbeView {
+    /// Updates the snapshots of the project and their comparison.
+    pub(super) fn updatesnapshot(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::SnapshotNameChanged(s) => {
+                self.state.snapshot.nameval = s;
+                Command::none()
+            },
+
+            ProbeMessage::SnapshotRange => {
+                let start = common::parse(&self.state.textinput.saddrval);
+                let end = common::parse(&self.state.textinput.eaddrval);
+
+                match (start, end) {
+                    (Some(s), Some(e)) if e > s => self.snapshot( Some( vec![ (s, e) ] ) ),
+                    _ => {
+                        warn!(origin="app", view="probe", "Enter a valid read range to take a snapshot of it");
+                        Command::none()
+                    },
+                }
+            },
+
+            ProbeMessage::SnapshotRam => self.snapshot(None),
+
+            ProbeMessage::RemoveSnapshot(i) => {
+                match &mut self.project {
+                    Some(project) if i < project.snapshots.len() => project.snapshots.remove(i),
+                    _ => return Command::none(),
+                };
+
+                self.syncsnapshots();
+                self.saveproject()
+            },
+
+            ProbeMessage::SnapshotBefore(name) => {
+                self.state.snapshot.selbefore = Some(name);
+                Command::none()
+            },
+
+            ProbeMessage::SnapshotAfter(name) => {
+                self.state.snapshot.selafter = Some(name);
+                Command::none()
+            },
+
+            ProbeMessage::DiffSnapshots => {
+                let (project, before, after) = match (&self.project, &self.state.snapshot.selbefore, &self.state.snapshot.selafter) {
+                    (Some(project), Some(before), Some(after)) => (project, before, after),
+                    _ => return Command::none(),
+                };
+
+                let find = |name: &String| project.snapshots.iter().find(|s| s.name == *name);
+
+                if let (Some(a), Some(b)) = (find(before), find(after)) {
+                    // Group the changes by variable if the ELF is loaded.
+                    let changes = a.diff(b, self.elf.as_deref());
+
+                    info!(origin="app", view="probe", "{} ranges changed between snapshots '{}' and '{}'", changes.len(), before, after);
+
+                    self.snapshotdiff = Some( (before.clone(), after.clone(), changes) );
+                }
+
+                Command::none()
+            },
+
+            _ => Command::none(),
+        }
+    }
+
+    /// Keeps the snapshot list widget states in sync with the project.
+    /// Forgets the selections of snapshots that no longer exist.
+    pub(super) fn syncsnapshots(&mut self) {
+        let names: Vec<String> = self.project.iter()
+            .flat_map(|p| p.snapshots.iter())
+            .map(|s| s.name.clone())
+            .collect();
+
+        self.state.snapshot.remove.resize_with(names.len(), Default::default);
+
+        let exists = |selected: &Option<String>| selected.as_ref().map(|s| names.contains(s)).unwrap_or(true);
+
+        if !exists(&self.state.snapshot.selbefore) {
+            self.state.snapshot.selbefore = None;
+        }
+
+        if !exists(&self.state.snapshot.selafter) {
+            self.state.snapshot.selafter = None;
+        }
+    }
+
+    /// Takes a snapshot of the given ranges, or of all the RAM, named after the snapshot input.
+    fn snapshot(&mut self, ranges: Option<Vec<(u32, u32)>>) -> Command<Message> {
+        let count = match &self.project {
+            Some(project) => project.snapshots.len(),
+            _ => {
+                warn!(origin="app", view="probe", "Cannot take a snapshot without a project");
+                return Command::none();
+            },
+        };
+
+        let name = match self.state.snapshot.nameval.trim() {
+            "" => format!("Snapshot {}", count + 1),
+            name => String::from(name),
+        };
+
+        self.snapshotname = Some(name);
+
+        self.request( ProbeCommand::Snapshot(ranges) )
+    }
+}
@@ -5,10 +5,15 @@
 use iced::{
     button,
     pick_list,
+    scrollable,
     text_input,
 };
 
-use super::Datatype;
+use crate::probe::WatchKind;
+
+use super::{
+    console::ConsoleState, coredump::CoreDumpState, fault::FaultState, live::LiveState, peripherals::PeripheralState, plot::PlotState, snapshot::SnapshotState, trace::TraceState, Datatype,
+};
 
 
 
@@ -19,6 +24,12 @@ pub(super) struct State {
     /// Probe picklist state.
     pub(super) probelist: pick_list::State<String>,
 
+    /// Target picklist state.
+    pub(super) targetlist: pick_list::State<String>,
+
+    /// Core picklist state.
+    pub(super) corelist: pick_list::State<usize>,
+
     /// A list of states for the buttons.
     pub(super) button: ButtonStates,
 
@@ -30,6 +41,54 @@ pub(super) struct State {
 
     /// A list of states for the text inputs.
     pub(super) textinput: TextInputStates,
+
+    /// Register table scroll state.
+    pub(super) regscroll: scrollable::State,
+
+    /// Value inputs of the register table.
+    pub(super) registers: Vec<(text_input::State, String)>,
+
+    /// Breakpoint list scroll state.
+    pub(super) bpscroll: scrollable::State,
+
+    /// Remove button states of the breakpoint list.
+    pub(super) breakpoints: Vec<button::State>,
+
+    /// Watchpoint kind picklist state.
+    pub(super) watchkind: pick_list::State<WatchKind>,
+
+    /// Currently selected watchpoint kind.
+    pub(super) selwatchkind: Option<WatchKind>,
+
+    /// Watchpoint list scroll state.
+    pub(super) wpscroll: scrollable::State,
+
+    /// Remove button states of the watchpoint list.
+    pub(super) watchpoints: Vec<button::State>,
+
+    /// Widget states of the live watch list.
+    pub(super) live: LiveState,
+
+    /// Widget states of the plot controls.
+    pub(super) plot: PlotState,
+
+    /// Widget states of the snapshot list.
+    pub(super) snapshot: SnapshotState,
+
+    /// Widget states of the core dump row.
+    pub(super) coredump: CoreDumpState,
+
+    /// Widget states of the peripheral tree.
+    pub(super) svd: PeripheralState,
+
+    /// Widget states of the fault analysis.
+    pub(super) fault: FaultState,
+
+    /// Widget states of the console.
+    pub(super) console: ConsoleState,
+
+    /// Widget states of the trace.
+    pub(super) trace: TraceState,
 }
 
 impl State {
@@ -37,10 +96,28 @@ impl State {
         State {
             projectlist: Default::default(),
             probelist: Default::default(),
+            targetlist: Default::default(),
+            corelist: Default::default(),
             button: Default::default(),
             rddatatype: Default::default(),
             seldatatype: None,
             textinput: Default::default(),
+            regscroll: Default::default(),
+            registers: Vec::new(),
+            bpscroll: Default::default(),
+            breakpoints: Vec::new(),
+            watchkind: Default::default(),
+            selwatchkind: Some(WatchKind::Write),
+            wpscroll: Default::default(),
+            watchpoints: Vec::new(),
+            live: LiveState::new(),
+            plot: Default::default(),
+            snapshot: Default::default(),
+            coredump: Default::default(),
+            svd: Default::default(),
+            fault: Default::default(),
+            console: ConsoleState::new(),
+            trace: Default::default(),
         }
     }
 }
@@ -66,21 +143,48 @@ pub(super) struct TextInputStates {
 
     /// Current value of the read range end address.
     pub(super) eaddrval: String,
+
+    /// Current breakpoint location.
+    pub(super) breakpoint: text_input::State,
+
+    /// Current value of the breakpoint location.
+    pub(super) breakpointval: String,
+
+    /// Current watchpoint location.
+    pub(super) watchpoint: text_input::State,
+
+    /// Current value of the watchpoint location.
+    pub(super) watchpointval: String,
+
+    /// Current watchpoint size.
+    pub(super) watchsize: text_input::State,
+
+    /// Current value of the watchpoint size.
+    pub(super) watchsizeval: String,
 }
 
 
 
 #[derive(Default)]
 pub(super) struct ButtonStates {
+    /// State of the connect button.
+    pub(super) connect: button::State,
+
     /// State of the load button.
     pub(super) load: button::State,
 
+    /// State of the verify button.
+    pub(super) verify: button::State,
+
     /// State of the stop button.
     pub(super) stop: button::State,
 
     /// State of the reset button.
     pub(super) reset: button::State,
 
+    /// State of the reset and halt button.
+    pub(super) resethalt: button::State,
+
     /// State of the run button.
     pub(super) run: button::State,
 
@@ -98,4 +202,10 @@ pub(super) struct ButtonStates {
 
     /// State of the read symbol button.
     pub(super) symbol: button::State,
+
+    /// State of the add breakpoint button.
+    pub(super) addbp: button::State,
+
+    /// State of the add watchpoint button.
+    pub(super) addwp: button::State,
 }
@@ -0,0 +1,62 @@
+//! Subscription that forwards the events of a channel into the GUI.
+
+
+
+use crate::gui::msg::Message;
+
+use iced_futures::futures::stream::{unfold, BoxStream};
+
+use iced_native::subscription::{
+    Recipe, Subscription,
+};
+
+use std::sync::Arc;
+
+use tokio::sync::{
+    mpsc::UnboundedReceiver, Mutex,
+};
+
+
+
+/// Builds a subscription that maps every event of the channel to a `Message`.
+/// The `id` identifies the channel, a new `id` restarts the subscription.
+pub(super) fn subscription<T: Send + 'static>(id: u64, channel: Arc<Mutex<UnboundedReceiver<T>>>, map: fn(T) -> Message) -> Subscription<Message> {
+    Subscription::from_recipe(ChannelRecipe { id, channel, map })
+}
+
+
+
+pub(super) struct ChannelRecipe<T> {
+    /// Identifier of the channel.
+    id: u64,
+
+    /// Receiving end of the channel.
+    channel: Arc<Mutex<UnboundedReceiver<T>>>,
+
+    /// Conversion of the events into messages.
+    map: fn(T) -> Message,
+}
+
+impl<H: std::hash::Hasher, I, T: Send + 'static> Recipe<H, I> for ChannelRecipe<T> {
+    type Output = Message;
+
+    fn hash(&self, state: &mut H) {
+        use std::hash::Hash;
+
+        std::any::TypeId::of::<Self>().hash(state);
+        self.id.hash(state);
+    }
+
+    fn stream(self: Box<Self>, _: BoxStream<'static, I>) -> BoxStream<'static, Self::Output> {
+        let ChannelRecipe { channel, map, .. } = *self;
+
+        Box::pin( unfold(
+            channel,
+            move |channel| async move {
+                let event = channel.lock().await.recv().await?;
+
+                Some((map(event), channel))
+            },
+        ))
+    }
+}
@@ -0,0 +1,435 @@
+//! SWO trace session of the probe GUI view.
+
+
+
+use crate::{
+    gui::msg::{ Message, ProbeMessage },
+
+    probe::{ self, Command as ProbeCommand, TraceConfig, TracePacket, TraceSender },
+};
+
+use iced::{
+    Command, Element, Row,
+
+    Align, Length,
+
+    Checkbox, Text, TextInput,
+
+    button::{ self, Button },
+    text_input,
+};
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+};
+
+use tokio::sync::{
+    mpsc::{ self, UnboundedReceiver }, Mutex,
+};
+
+use tracing::{ error, info, warn };
+
+use super::{
+    common, console, ProbeView,
+};
+
+
+
+/// Maximum number of events kept in the timeline.
+const MAXEVENTS: usize = 4096;
+
+
+
+pub(super) struct Trace {
+    /// Identifier of the trace session.
+    pub(super) id: u64,
+
+    /// Decoded packets of the SWO capture, `None` for a capture file.
+    pub(super) events: Option<Arc<Mutex<UnboundedReceiver<Vec<TracePacket>>>>>,
+
+    /// Source of the packets.
+    pub(super) source: String,
+
+    /// Indicates that the probe started the SWO capture.
+    pub(super) running: bool,
+
+    /// Text written to each ITM stimulus port.
+    ports: BTreeMap<u8, String>,
+
+    /// Hardware events with the cycle count of their timestamp.
+    timeline: Vec<(u64, TracePacket)>,
+
+    /// Cycles accumulated from the local timestamps.
+    time: u64,
+
+    /// Number of overflow packets.
+    overflows: usize,
+
+    /// Number of bytes that did not start a valid packet.
+    invalid: usize,
+}
+
+impl Trace {
+    /// Creates a trace session of the SWO capture and the channel of its packets.
+    pub(super) fn capture(id: u64) -> (Self, TraceSender) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut trace = Trace::file(id, String::from("SWO"));
+        trace.events = Some( Arc::new( Mutex::new(rx) ) );
+
+        (trace, tx)
+    }
+
+    /// Creates a trace session of a capture file.
+    pub(super) fn file(id: u64, source: String) -> Self {
+        Trace {
+            id,
+            events: None,
+            source,
+            running: false,
+            ports: BTreeMap::new(),
+            timeline: Vec::new(),
+            time: 0,
+            overflows: 0,
+            invalid: 0,
+        }
+    }
+
+    /// Updates the session with decoded packets.
+    pub(super) fn update(&mut self, packets: Vec<TracePacket>) {
+        for packet in packets {
+            match packet {
+                TracePacket::Instrumentation { port, payload } => console::append(self.ports.entry(port).or_default(), &payload),
+
+                TracePacket::LocalTimestamp { delta, .. } => self.time += delta as u64,
+
+                TracePacket::Sync | TracePacket::GlobalTimestamp { .. } | TracePacket::Extension(_) => (),
+
+                TracePacket::Invalid(_) => self.invalid += 1,
+
+                packet => {
+                    if packet == TracePacket::Overflow {
+                        self.overflows += 1;
+                    }
+
+                    self.timeline.push( (self.time, packet) );
+
+                    if self.timeline.len() > MAXEVENTS {
+                        let cut = self.timeline.len() - MAXEVENTS;
+                        self.timeline.drain(..cut);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Returns the ITM stimulus ports that received text.
+    pub(super) fn ports(&self) -> Vec<u8> {
+        self.ports.keys().cloned().collect()
+    }
+
+    /// Returns the text of an ITM stimulus port.
+    pub(super) fn port(&self, port: u8) -> &str {
+        self.ports.get(&port).map(|t| t.as_str()).unwrap_or("")
+    }
+
+    /// Returns the timeline as one line per event, with the cycles of its timestamp.
+    pub(super) fn timeline(&self) -> String {
+        self.timeline.iter()
+            .map(|(time, packet)| format!("{:>12}  {}", time, packet))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the notice of packets lost by the target or corrupted in the capture.
+    pub(super) fn notice(&self) -> Option<String> {
+        match (self.overflows, self.invalid) {
+            (0, 0) => None,
+            (o, 0) => Some( format!("The trace overflowed {} times, lower the amount of trace data or raise the baud rate", o) ),
+            (_, i) => Some( format!("{} bytes of the capture are not valid ITM packets, check the trace clock and the baud rate", i) ),
+        }
+    }
+
+    /// Clears the text of an ITM stimulus port or the timeline.
+    pub(super) fn clear(&mut self, tab: console::Tab) {
+        match tab {
+            console::Tab::Itm(port) => if let Some(text) = self.ports.get_mut(&port) {
+                text.clear();
+            },
+
+            console::Tab::Timeline => {
+                self.timeline.clear();
+                self.overflows = 0;
+                self.invalid = 0;
+            },
+
+            _ => (),
+        }
+    }
+}
+
+
+
+/// Widget states of the trace configuration and of the trace tabs of the console.
+#[derive(Default)]
+pub(super) struct TraceState {
+    /// Button states of the ITM port tabs.
+    itmports: Vec<button::State>,
+
+    /// State of the timeline tab button.
+    timeline: button::State,
+
+    /// Trace clock input state.
+    clock: text_input::State,
+
+    /// Current value of the trace clock.
+    clockval: String,
+
+    /// SWO baud rate input state.
+    baud: text_input::State,
+
+    /// Current value of the SWO baud rate.
+    baudval: String,
+
+    /// Trace capture file input state.
+    file: text_input::State,
+
+    /// Current value of the trace capture file.
+    fileval: String,
+
+    /// State of the open capture button.
+    open: button::State,
+}
+
+impl TraceState {
+    /// Builds the tabs of the trace in the console and the row of the trace configuration.
+    /// There is one tab for each ITM port with text and one for the timeline of the trace.
+    pub(super) fn view<'a>(&'a mut self, trace: Option<&Trace>, current: console::Tab) -> (Vec<Element<'a, Message>>, Row<'a, Message>) {
+        let ports = trace.map(|t| t.ports()).unwrap_or_default();
+
+        self.itmports.resize_with(ports.len(), Default::default);
+
+        let mut tabs: Vec<Element<'a, Message>> = self.itmports.iter_mut()
+            .zip(ports.into_iter())
+            .map(|(state, port)| {
+                let label = match current == console::Tab::Itm(port) {
+                    true => format!("[ITM {}]", port),
+                    _ => format!("ITM {}", port),
+                };
+
+                Button::new(state, Text::new(label).size(14))
+                    .on_press(Message::Probe( ProbeMessage::TraceTab(Some(port)) ))
+                    .into()
+            })
+            .collect();
+
+        if trace.is_some() {
+            let label = match current {
+                console::Tab::Timeline => "[Timeline]",
+                _ => "Timeline",
+            };
+
+            let tab = Button::new(&mut self.timeline, Text::new(label).size(14))
+                .on_press(Message::Probe( ProbeMessage::TraceTab(None) ));
+
+            tabs.push( tab.into() );
+        }
+
+        // SWO trace configuration and capture files.
+        let swo = Checkbox::new(
+            trace.map(|t| t.events.is_some()).unwrap_or(false),
+            "SWO trace",
+            |b| { Message::Probe( ProbeMessage::Trace(b) ) }
+        )
+        .size(14)
+        .text_size(14);
+
+        let clock = TextInput::new(
+            &mut self.clock,
+            "Trace clock (Hz)",
+            &self.clockval,
+            |s| { Message::Probe( ProbeMessage::TraceClockChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Units(140));
+
+        let baud = TextInput::new(
+            &mut self.baud,
+            "SWO baud rate",
+            &self.baudval,
+            |s| { Message::Probe( ProbeMessage::TraceBaudChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Units(140));
+
+        let file = TextInput::new(
+            &mut self.file,
+            "Trace capture file",
+            &self.fileval,
+            |s| { Message::Probe( ProbeMessage::TraceFileChanged(s) ) }
+        )
+        .padding(5)
+        .size(14)
+        .width(Length::Units(300))
+        .on_submit(Message::Probe( ProbeMessage::TraceOpen ));
+
+        let open = Button::new(&mut self.open, Text::new("Open capture").size(14))
+            .height(Length::Shrink)
+            .on_press(Message::Probe( ProbeMessage::TraceOpen ));
+
+        let status = match trace {
+            None => String::from("No trace"),
+            Some(trace) if trace.events.is_some() && !trace.running => String::from("Starting the SWO capture..."),
+            Some(trace) => format!("Trace from {}", trace.source),
+        };
+
+        let row = Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(swo)
+            .push(clock)
+            .push(baud)
+            .push(file)
+            .push(open)
+            .push(Text::new(status).size(14));
+
+        (tabs, row)
+    }
+}
+
+
+
+impl ProbeView {
+    /// Updates the trace session and its configuration.
+    pub(super) fn updatetrace(&mut self, msg: ProbeMessage) -> Command<Message> {
+        match msg {
+            ProbeMessage::Trace(true) => {
+                if self.probe.is_none() {
+                    warn!(origin="app", view="probe", "Cannot start the SWO trace without an open probe");
+                    return Command::none();
+                }
+
+                let (clock, baud) = match (common::parse(&self.state.trace.clockval), common::parse(&self.state.trace.baudval)) {
+                    (Some(clock), Some(baud)) => (clock, baud),
+                    _ => {
+                        warn!(origin="app", view="probe", "The SWO trace requires the trace clock and the baud rate");
+                        return Command::none();
+                    },
+                };
+
+                // Trace all the stimulus ports, the exceptions and the PC samples.
+                let config = TraceConfig { clock, baud, ports: 0xFFFF_FFFF, exceptions: true, pcsampling: true };
+
+                info!(origin="app", view="probe", "Starting the SWO trace at {} baud with a {} Hz trace clock", baud, clock);
+
+                self.consoles += 1;
+
+                let (trace, tx) = Trace::capture(self.consoles);
+
+                self.trace = Some(trace);
+                self.tab = console::Tab::Timeline;
+
+                self.request( ProbeCommand::TraceStart(config, tx) )
+            },
+
+            ProbeMessage::Trace(false) => {
+                let capture = self.trace.as_ref().map(|t| t.events.is_some()).unwrap_or(false);
+
+                self.trace = None;
+                self.tab = console::Tab::Rtt;
+
+                match capture {
+                    true => self.request( ProbeCommand::TraceStop ),
+                    _ => Command::none(),
+                }
+            },
+
+            ProbeMessage::TracePackets(packets) => {
+                if let Some(trace) = &mut self.trace {
+                    trace.update(packets);
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::TraceTab(port) => {
+                self.tab = match port {
+                    Some(port) => console::Tab::Itm(port),
+                    _ => console::Tab::Timeline,
+                };
+
+                Command::none()
+            },
+
+            ProbeMessage::TraceClockChanged(s) => {
+                if s.is_empty() || common::parse(&s).is_some() {
+                    self.state.trace.clockval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::TraceBaudChanged(s) => {
+                if s.is_empty() || common::parse(&s).is_some() {
+                    self.state.trace.baudval = s;
+                }
+
+                Command::none()
+            },
+
+            ProbeMessage::TraceFileChanged(s) => {
+                self.state.trace.fileval = s;
+                Command::none()
+            },
+
+            ProbeMessage::TraceOpen => {
+                let path = self.state.trace.fileval.trim().to_string();
+
+                if path.is_empty() {
+                    return Command::none();
+                }
+
+                Command::perform(
+                    async move {
+                        let packets = probe::trace::Decoder::file( std::path::Path::new(&path) ).await;
+                        (path, packets)
+                    },
+                    |(path, packets)| Message::Probe( ProbeMessage::TraceDecoded(path, packets) )
+                )
+            },
+
+            ProbeMessage::TraceDecoded(path, packets) => {
+                let packets = match packets {
+                    Ok(packets) => packets,
+                    Err(e) => {
+                        error!(origin="app", view="probe", "{}", e);
+                        return Command::none();
+                    },
+                };
+
+                info!(origin="app", view="probe", "Decoded {} trace packets from {}", packets.len(), path);
+
+                // A capture file replaces the live trace.
+                let stop = match self.trace.as_ref().map(|t| t.events.is_some()).unwrap_or(false) {
+                    true => self.request( ProbeCommand::TraceStop ),
+                    _ => Command::none(),
+                };
+
+                self.consoles += 1;
+
+                let mut trace = Trace::file(self.consoles, path);
+                trace.update(packets);
+
+                self.trace = Some(trace);
+                self.tab = console::Tab::Timeline;
+
+                stop
+            },
+
+            _ => Command::none(),
+        }
+    }
+}
@@ -2,8 +2,10 @@
 
 
 
+pub mod plot;
 pub mod topbar;
 
 
 
+pub use self::plot::Plot;
 pub use self::topbar::Topbar;
\ No newline at end of file
@@ -0,0 +1,324 @@
+//! A plot widget.
+//! Charts several series of samples over time, with pause and zoom.
+
+
+
+use iced::{
+    Color, Point, Rectangle,
+
+    HorizontalAlignment, VerticalAlignment,
+
+    canvas::{
+        self, Cache, Cursor, Event, Frame, Geometry, Path, Stroke, Text,
+
+        event::Status,
+    },
+
+    mouse,
+};
+
+use std::collections::VecDeque;
+
+
+
+/// Maximum number of points kept in each series.
+const MAXPOINTS: usize = 10_000;
+
+/// Shortest and longest visible time span, in seconds.
+const WINDOW: (f32, f32) = (0.5, 600.0);
+
+/// Width of the left margin with the value labels.
+const MARGIN: f32 = 70.0;
+
+/// Number of horizontal grid lines.
+const GRID: usize = 5;
+
+/// Colors of the series, repeated if there are more series.
+const PALETTE: [Color; 6] = [
+    Color { r: 0.12, g: 0.47, b: 0.71, a: 1.0 },
+    Color { r: 0.85, g: 0.37, b: 0.01, a: 1.0 },
+    Color { r: 0.17, g: 0.63, b: 0.17, a: 1.0 },
+    Color { r: 0.84, g: 0.15, b: 0.16, a: 1.0 },
+    Color { r: 0.58, g: 0.40, b: 0.74, a: 1.0 },
+    Color { r: 0.55, g: 0.34, b: 0.29, a: 1.0 },
+];
+
+/// Color of the axes and the grid.
+const GRIDCOLOR: Color = Color { r: 0.75, g: 0.75, b: 0.75, a: 1.0 };
+
+
+
+/// Statistics of the visible samples of a series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Smallest value.
+    pub min: f64,
+
+    /// Largest value.
+    pub max: f64,
+
+    /// Mean of the values.
+    pub avg: f64,
+}
+
+
+
+/// A named series of (time, value) samples.
+struct Series {
+    /// Name of the series.
+    name: String,
+
+    /// Samples with their time in seconds.
+    points: VecDeque<(f32, f64)>,
+}
+
+
+
+pub struct Plot {
+    /// Series of the plot.
+    series: Vec<Series>,
+
+    /// Visible time span in seconds.
+    window: f32,
+
+    /// Time of the right edge of the plot while paused.
+    paused: Option<f32>,
+
+    /// Time of the last sample.
+    latest: f32,
+
+    /// Cache of the drawn plot.
+    cache: Cache,
+}
+
+impl Plot {
+    /// Creates an empty plot that shows the last 10 seconds.
+    pub fn new() -> Self {
+        Plot {
+            series: Vec::new(),
+            window: 10.0,
+            paused: None,
+            latest: 0.0,
+            cache: Cache::new(),
+        }
+    }
+
+    /// Replaces the series of the plot with empty series of the given names.
+    pub fn reset(&mut self, names: Vec<String>) {
+        self.series = names.into_iter()
+            .map(|name| Series { name, points: VecDeque::new() })
+            .collect();
+
+        self.latest = 0.0;
+        self.paused = None;
+        self.cache.clear();
+    }
+
+    /// Removes the samples of all the series.
+    pub fn clear(&mut self) {
+        for series in self.series.iter_mut() {
+            series.points.clear();
+        }
+
+        self.latest = 0.0;
+        self.paused = self.paused.map(|_| 0.0);
+        self.cache.clear();
+    }
+
+    /// Adds a sample of each series at the given time in seconds.
+    /// Series without a numeric value are not extended.
+    pub fn sample(&mut self, time: f32, values: &[Option<f64>]) {
+        for (series, value) in self.series.iter_mut().zip(values.iter()) {
+            if let Some(value) = value {
+                series.points.push_back( (time, *value) );
+
+                if series.points.len() > MAXPOINTS {
+                    series.points.pop_front();
+                }
+            }
+        }
+
+        self.latest = time;
+
+        // A paused plot does not change.
+        if self.paused.is_none() {
+            self.cache.clear();
+        }
+    }
+
+    /// Pauses the plot at the last sample or resumes following the samples.
+    pub fn pause(&mut self, pause: bool) {
+        self.paused = match pause {
+            true => Some(self.latest),
+            _ => None,
+        };
+
+        self.cache.clear();
+    }
+
+    /// Indicates if the plot is paused.
+    pub fn paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Scales the visible time span by the given factor.
+    pub fn zoom(&mut self, factor: f32) {
+        self.window = (self.window * factor).max(WINDOW.0).min(WINDOW.1);
+        self.cache.clear();
+    }
+
+    /// Returns the visible time span in seconds.
+    pub fn window(&self) -> f32 {
+        self.window
+    }
+
+    /// Returns the name, color and statistics of the visible samples of each series.
+    pub fn stats(&self) -> Vec<(String, Color, Option<Stats>)> {
+        let (start, end) = self.range();
+
+        self.series.iter()
+            .enumerate()
+            .map(|(i, series)| {
+                let visible = series.points.iter()
+                    .filter(|(t, _)| (*t >= start) && (*t <= end))
+                    .map(|(_, v)| *v);
+
+                let (n, sum, min, max) = visible.fold((0usize, 0.0f64, f64::INFINITY, f64::NEG_INFINITY), |(n, sum, min, max), v| {
+                    (n + 1, sum + v, min.min(v), max.max(v))
+                });
+
+                let stats = match n {
+                    0 => None,
+                    n => Some( Stats { min, max, avg: sum / n as f64 } ),
+                };
+
+                (series.name.clone(), color(i), stats)
+            })
+            .collect()
+    }
+
+    /// Returns the visible time range.
+    fn range(&self) -> (f32, f32) {
+        let end = self.paused.unwrap_or(self.latest).max(self.window);
+
+        (end - self.window, end)
+    }
+
+    /// Returns the value range of the visible samples, with a margin.
+    fn values(&self, start: f32, end: f32) -> (f64, f64) {
+        let (min, max) = self.series.iter()
+            .flat_map(|s| s.points.iter())
+            .filter(|(t, _)| (*t >= start) && (*t <= end))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (_, v)| (min.min(*v), max.max(*v)));
+
+        match (min.is_finite(), max - min) {
+            (false, _) => (-1.0, 1.0),
+            (_, d) if d == 0.0 => (min - 1.0, max + 1.0),
+            (_, d) => (min - (d * 0.05), max + (d * 0.05)),
+        }
+    }
+
+    /// Draws the grid, the labels and the series.
+    fn render(&self, frame: &mut Frame) {
+        let size = frame.size();
+
+        let (start, end) = self.range();
+        let (low, high) = self.values(start, end);
+
+        let width = (size.width - MARGIN).max(1.0);
+        let height = size.height.max(1.0);
+
+        let x = |t: f32| MARGIN + (((t - start) / (end - start)) * width);
+        let y = |v: f64| height - ((((v - low) / (high - low)) as f32) * height);
+
+        // Grid and value labels.
+        for i in 0..=GRID {
+            let value = low + ((high - low) * (i as f64 / GRID as f64));
+            let h = y(value);
+
+            let line = Path::line(Point::new(MARGIN, h), Point::new(size.width, h));
+            frame.stroke(&line, Stroke::default().with_color(GRIDCOLOR).with_width(1.0));
+
+            frame.fill_text(Text {
+                content: format!("{:.3}", value),
+                position: Point::new(MARGIN - 5.0, h),
+                color: Color::BLACK,
+                size: 12.0,
+                horizontal_alignment: HorizontalAlignment::Right,
+                vertical_alignment: VerticalAlignment::Center,
+                ..Text::default()
+            });
+        }
+
+        // Time label of the right edge.
+        frame.fill_text(Text {
+            content: format!("{:.1} s", end),
+            position: Point::new(size.width - 5.0, height - 5.0),
+            color: Color::BLACK,
+            size: 12.0,
+            horizontal_alignment: HorizontalAlignment::Right,
+            vertical_alignment: VerticalAlignment::Bottom,
+            ..Text::default()
+        });
+
+        // Series.
+        for (i, series) in self.series.iter().enumerate() {
+            let mut visible = series.points.iter()
+                .filter(|(t, _)| (*t >= start) && (*t <= end));
+
+            let first = match visible.next() {
+                Some(point) => *point,
+                _ => continue,
+            };
+
+            let path = Path::new(|builder| {
+                builder.move_to( Point::new(x(first.0), y(first.1)) );
+
+                for (t, v) in visible {
+                    builder.line_to( Point::new(x(*t), y(*v)) );
+                }
+            });
+
+            frame.stroke(&path, Stroke::default().with_color(color(i)).with_width(1.5));
+        }
+    }
+}
+
+impl<Message> canvas::Program<Message> for Plot {
+    fn update(&mut self, event: Event, bounds: Rectangle, cursor: Cursor) -> (Status, Option<Message>) {
+        if !cursor.is_over(&bounds) {
+            return (Status::Ignored, None);
+        }
+
+        // The mouse wheel zooms the time axis.
+        match event {
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let lines = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 60.0,
+                };
+
+                self.zoom( 0.8f32.powf(lines) );
+
+                (Status::Captured, None)
+            },
+
+            _ => (Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _: Cursor) -> Vec<Geometry> {
+        let plot = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, frame.size(), Color::WHITE);
+
+            self.render(frame);
+        });
+
+        vec![plot]
+    }
+}
+
+/// Returns the color of the given series.
+pub fn color(i: usize) -> Color {
+    PALETTE[i % PALETTE.len()]
+}
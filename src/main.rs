@@ -2,9 +2,12 @@
 
 
 mod database;
+mod defmt;
+mod elf;
 mod log;
 mod probe;
 mod project;
+mod svd;
 mod gui;
 mod init;
 
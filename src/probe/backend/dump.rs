@@ -0,0 +1,184 @@
+//! Core dump backend.
+//! A read-only target over a saved core dump, used for post-mortem sessions
+//! without hardware. The core stays halted at the state of the dump.
+
+
+
+use crate::elf::Segment;
+
+use probe_rs::{ CoreStatus, CoreType, HaltReason };
+
+use std::time::Duration;
+
+use super::{
+    Backend, BackendError, CoreInterface, RegisterDescriptor,
+    super::{ fault, CoreDump, FlashHandle },
+};
+
+
+
+/// A target loaded from a core dump.
+pub struct DumpBackend {
+    /// Saved state of the core.
+    dump: CoreDump,
+}
+
+impl DumpBackend {
+    /// Creates a backend over a core dump.
+    pub fn new(dump: CoreDump) -> Self {
+        DumpBackend { dump }
+    }
+}
+
+impl Backend for DumpBackend {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        vec![ (0, self.dump.coretype) ]
+    }
+
+    fn ram(&self) -> Vec<(u32, u32)> {
+        // The fault status registers are saved along with the RAM.
+        self.dump.regions.iter()
+            .filter(|(address, _)| *address != fault::FAULTREGS.0)
+            .map(|(address, data)| (*address, data.len() as u32))
+            .collect()
+    }
+
+    fn core(&mut self, n: usize) -> Result<Box<dyn CoreInterface + '_>, BackendError> {
+        match n {
+            0 => Ok( Box::new( DumpCore { dump: &self.dump } ) ),
+            _ => Err( BackendError( format!("Core {} is not in the core dump", n) ) ),
+        }
+    }
+
+    fn flash(&mut self, _: &[Segment], _: &FlashHandle) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn setup_swo(&mut self, _: usize, _: u32, _: u32) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, BackendError> {
+        Err( readonly() )
+    }
+}
+
+
+
+/// The core of a core dump.
+struct DumpCore<'a> {
+    /// Saved state of the core.
+    dump: &'a CoreDump,
+}
+
+impl<'a> DumpCore<'a> {
+    /// Returns the saved bytes of a range.
+    fn bytes(&self, address: u32, len: usize) -> Result<&'a [u8], BackendError> {
+        self.dump.regions.iter()
+            .find(|(start, data)| (address >= *start) && ((address - *start) as usize + len <= data.len()))
+            .map(|(start, data)| &data[(address - *start) as usize..(address - *start) as usize + len])
+            .ok_or_else(|| BackendError( format!("0x{:08X} for {} bytes is not in the core dump", address, len) ))
+    }
+}
+
+impl<'a> CoreInterface for DumpCore<'a> {
+    fn read_word_8(&mut self, address: u32) -> Result<u8, BackendError> {
+        Ok( self.bytes(address, 1)?[0] )
+    }
+
+    fn read_word_32(&mut self, address: u32) -> Result<u32, BackendError> {
+        let mut word = [0u32];
+        self.read_32(address, &mut word)?;
+        Ok( word[0] )
+    }
+
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), BackendError> {
+        let bytes = self.bytes(address, data.len() * 4)?;
+
+        for (word, b) in data.iter_mut().zip(bytes.chunks_exact(4)) {
+            *word = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+
+        Ok(())
+    }
+
+    fn write_word_8(&mut self, _: u32, _: u8) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn write_word_32(&mut self, _: u32, _: u32) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn write_32(&mut self, _: u32, _: &[u32]) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn halt(&mut self, _: Duration) -> Result<u32, BackendError> {
+        self.read_core_reg(self.dump.pcaddress)
+    }
+
+    fn run(&mut self) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn reset(&mut self) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn reset_and_halt(&mut self, _: Duration) -> Result<u32, BackendError> {
+        Err( readonly() )
+    }
+
+    fn step(&mut self) -> Result<u32, BackendError> {
+        Err( readonly() )
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, BackendError> {
+        Ok( CoreStatus::Halted(HaltReason::Request) )
+    }
+
+    fn descriptors(&self) -> Vec<RegisterDescriptor> {
+        self.dump.registers.iter()
+            .map(|r| RegisterDescriptor { name: r.name.clone(), address: r.address, group: r.group })
+            .collect()
+    }
+
+    fn pcaddress(&self) -> u16 {
+        self.dump.pcaddress
+    }
+
+    fn read_core_reg(&mut self, address: u16) -> Result<u32, BackendError> {
+        match self.dump.registers.iter().find(|r| r.address == address) {
+            Some(register) => Ok( register.value ),
+            _ => Err( BackendError( format!("Register {} is not in the core dump", address) ) ),
+        }
+    }
+
+    fn write_core_reg(&mut self, _: u16, _: u32) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, BackendError> {
+        Ok(0)
+    }
+
+    fn set_hw_breakpoint(&mut self, _: u32) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn clear_hw_breakpoint(&mut self, _: u32) -> Result<(), BackendError> {
+        Err( readonly() )
+    }
+
+    fn clear_all_hw_breakpoints(&mut self) -> Result<(), BackendError> {
+        Ok(())
+    }
+}
+
+
+
+/// Error of the operations that change the target.
+fn readonly() -> BackendError {
+    BackendError( String::from("A core dump is read-only") )
+}
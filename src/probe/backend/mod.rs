@@ -0,0 +1,184 @@
+//! Backends of an `OpenProbe`.
+//! A backend gives access to the memory, run control and registers of the cores of a target.
+
+
+
+pub mod dump;
+pub mod probers;
+pub mod sim;
+
+
+
+use crate::elf::Segment;
+
+use probe_rs::{ CoreStatus, CoreType };
+
+use std::time::Duration;
+
+use super::{ FlashHandle, RegisterGroup };
+
+
+
+pub use self::dump::DumpBackend;
+pub use self::probers::ProbeRsBackend;
+pub use self::sim::{ ScriptedHalt, SimBackend };
+
+
+
+/// A target accessed by an `OpenProbe`.
+pub trait Backend: Send {
+    /// Lists the cores of the target with their architecture.
+    fn list_cores(&self) -> Vec<(usize, CoreType)>;
+
+    /// Lists the RAM regions of the target as (start, size) pairs.
+    fn ram(&self) -> Vec<(u32, u32)>;
+
+    /// Attaches to the given core.
+    fn core(&mut self, n: usize) -> Result<Box<dyn CoreInterface + '_>, BackendError>;
+
+    /// Programs the segments into the non volatile memory in a single operation.
+    fn flash(&mut self, segments: &[Segment], handle: &FlashHandle) -> Result<(), BackendError>;
+
+    /// Configures the probe to capture the SWO pin of the given core.
+    fn setup_swo(&mut self, core: usize, clock: u32, baud: u32) -> Result<(), BackendError>;
+
+    /// Reads the SWO bytes captured since the last read.
+    fn read_swo(&mut self) -> Result<Vec<u8>, BackendError>;
+}
+
+
+
+/// Stands in for the backend while a flash operation owns it.
+pub(super) struct Lent;
+
+impl Backend for Lent {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        Vec::new()
+    }
+
+    fn ram(&self) -> Vec<(u32, u32)> {
+        Vec::new()
+    }
+
+    fn core(&mut self, _: usize) -> Result<Box<dyn CoreInterface + '_>, BackendError> {
+        Err( lent() )
+    }
+
+    fn flash(&mut self, _: &[Segment], _: &FlashHandle) -> Result<(), BackendError> {
+        Err( lent() )
+    }
+
+    fn setup_swo(&mut self, _: usize, _: u32, _: u32) -> Result<(), BackendError> {
+        Err( lent() )
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, BackendError> {
+        Err( lent() )
+    }
+}
+
+/// Error of the accesses to a lent backend.
+fn lent() -> BackendError {
+    BackendError( String::from("The target is not available while it is being flashed") )
+}
+
+
+
+/// Debug access to a single core of a target.
+/// The bytes of the 32 bit words are in the byte lanes of their addresses, the lowest
+/// address in the lowest byte, as the debug access port transfers them. The endianness
+/// of the target only applies when the bytes are decoded into values.
+pub trait CoreInterface {
+    /// Reads a 8 bit word.
+    fn read_word_8(&mut self, address: u32) -> Result<u8, BackendError>;
+
+    /// Reads a 32 bit word.
+    fn read_word_32(&mut self, address: u32) -> Result<u32, BackendError>;
+
+    /// Reads a block of 32 bit words.
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), BackendError>;
+
+    /// Writes a 8 bit word.
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), BackendError>;
+
+    /// Writes a 32 bit word.
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), BackendError>;
+
+    /// Writes a block of 32 bit words.
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), BackendError>;
+
+    /// Halts the core and returns the PC.
+    fn halt(&mut self, timeout: Duration) -> Result<u32, BackendError>;
+
+    /// Resumes the core.
+    fn run(&mut self) -> Result<(), BackendError>;
+
+    /// Resets the core.
+    fn reset(&mut self) -> Result<(), BackendError>;
+
+    /// Resets the core and halts it before the first instruction. Returns the PC.
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<u32, BackendError>;
+
+    /// Steps the core one instruction and returns the PC.
+    fn step(&mut self) -> Result<u32, BackendError>;
+
+    /// Returns the run state of the core.
+    fn status(&mut self) -> Result<CoreStatus, BackendError>;
+
+    /// Lists the registers of the core, without aliases.
+    fn descriptors(&self) -> Vec<RegisterDescriptor>;
+
+    /// Returns the debug address of the program counter.
+    fn pcaddress(&self) -> u16;
+
+    /// Reads a core register.
+    fn read_core_reg(&mut self, address: u16) -> Result<u32, BackendError>;
+
+    /// Writes a core register.
+    fn write_core_reg(&mut self, address: u16, value: u32) -> Result<(), BackendError>;
+
+    /// Returns the number of hardware breakpoint units.
+    fn available_breakpoint_units(&mut self) -> Result<u32, BackendError>;
+
+    /// Sets a hardware breakpoint.
+    fn set_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError>;
+
+    /// Clears a hardware breakpoint.
+    fn clear_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError>;
+
+    /// Clears all the hardware breakpoints.
+    fn clear_all_hw_breakpoints(&mut self) -> Result<(), BackendError>;
+}
+
+
+
+/// Description of a core register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterDescriptor {
+    /// Name of the register.
+    pub name: String,
+
+    /// Debug address of the register.
+    pub address: u16,
+
+    /// Group of the register.
+    pub group: RegisterGroup,
+}
+
+
+
+/// Error reported by a backend.
+#[derive(Debug, Clone)]
+pub struct BackendError(pub String);
+
+impl core::fmt::Display for BackendError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<probe_rs::Error> for BackendError {
+    fn from(e: probe_rs::Error) -> Self {
+        BackendError( e.to_string() )
+    }
+}
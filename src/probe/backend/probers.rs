@@ -0,0 +1,217 @@
+//! `probe-rs` backend.
+
+
+
+use crate::elf::Segment;
+
+use probe_rs::{
+    Core, CoreStatus, CoreType, Session,
+
+    architecture::arm::SwoConfig,
+
+    config::MemoryRegion,
+
+    CoreRegisterAddress, MemoryInterface, RegisterDescription,
+
+    flashing::{ DownloadOptions, FlashLoader, FlashProgress },
+};
+
+use std::time::Duration;
+
+use super::{
+    Backend, BackendError, CoreInterface, RegisterDescriptor,
+    super::{ FlashHandle, RegisterGroup },
+};
+
+
+
+/// A target attached through a debug probe.
+pub struct ProbeRsBackend {
+    /// Session with the target.
+    session: Session,
+}
+
+impl ProbeRsBackend {
+    /// Creates a backend from an attached session.
+    pub fn new(session: Session) -> Self {
+        ProbeRsBackend { session }
+    }
+}
+
+impl Backend for ProbeRsBackend {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        self.session.list_cores()
+    }
+
+    fn ram(&self) -> Vec<(u32, u32)> {
+        self.session.target().memory_map.iter()
+            .filter_map(|region| match region {
+                MemoryRegion::Ram(ram) => Some( (ram.range.start, ram.range.end - ram.range.start) ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn core(&mut self, n: usize) -> Result<Box<dyn CoreInterface + '_>, BackendError> {
+        Ok( Box::new( self.session.core(n)? ) )
+    }
+
+    fn flash(&mut self, segments: &[Segment], handle: &FlashHandle) -> Result<(), BackendError> {
+        let target = self.session.target();
+        let mut loader = FlashLoader::new(target.memory_map.clone(), target.source().clone());
+
+        // Erase and program all the segments in one pass.
+        for segment in segments {
+            if let Err(e) = loader.add_data(segment.address, &segment.data) {
+                return Err( BackendError( format!("Segment at 0x{:08X}: {}", segment.address, e) ) );
+            }
+        }
+
+        // The commit cannot be aborted once started, the callback only reports its progress.
+        let reporter = handle.clone();
+        let progress = FlashProgress::new(move |event| reporter.progress(event));
+
+        let mut options = DownloadOptions::default();
+        options.progress = Some(&progress);
+
+        match loader.commit(&mut self.session, options) {
+            Err(e) => Err( BackendError( e.to_string() ) ),
+            _ => Ok(()),
+        }
+    }
+
+    fn setup_swo(&mut self, core: usize, clock: u32, baud: u32) -> Result<(), BackendError> {
+        let config = SwoConfig::new(clock)
+            .set_baud(baud)
+            .set_continuous_formatter(false);
+
+        Ok( self.session.setup_swv(core, &config)? )
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, BackendError> {
+        Ok( self.session.read_swo()? )
+    }
+}
+
+
+
+impl<'probe> CoreInterface for Core<'probe> {
+    fn read_word_8(&mut self, address: u32) -> Result<u8, BackendError> {
+        Ok( MemoryInterface::read_word_8(self, address)? )
+    }
+
+    fn read_word_32(&mut self, address: u32) -> Result<u32, BackendError> {
+        Ok( MemoryInterface::read_word_32(self, address)? )
+    }
+
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), BackendError> {
+        Ok( MemoryInterface::read_32(self, address, data)? )
+    }
+
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), BackendError> {
+        Ok( MemoryInterface::write_word_8(self, address, data)? )
+    }
+
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), BackendError> {
+        Ok( MemoryInterface::write_word_32(self, address, data)? )
+    }
+
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), BackendError> {
+        Ok( MemoryInterface::write_32(self, address, data)? )
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<u32, BackendError> {
+        Ok( Core::halt(self, timeout)?.pc )
+    }
+
+    fn run(&mut self) -> Result<(), BackendError> {
+        Ok( Core::run(self)? )
+    }
+
+    fn reset(&mut self) -> Result<(), BackendError> {
+        Ok( Core::reset(self)? )
+    }
+
+    fn reset_and_halt(&mut self, timeout: Duration) -> Result<u32, BackendError> {
+        Ok( Core::reset_and_halt(self, timeout)?.pc )
+    }
+
+    fn step(&mut self) -> Result<u32, BackendError> {
+        Ok( Core::step(self)?.pc )
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, BackendError> {
+        Ok( Core::status(self)? )
+    }
+
+    fn descriptors(&self) -> Vec<RegisterDescriptor> {
+        // Get the register file of the core's architecture.
+        let file = Core::registers(self);
+
+        // Collect the register descriptions with their groups.
+        let mut descriptions: Vec<(&RegisterDescription, RegisterGroup)> = file.platform_registers()
+            .map(|r| (r, RegisterGroup::General))
+            .collect();
+
+        descriptions.push( (file.stack_pointer(), RegisterGroup::Special) );
+        descriptions.push( (file.return_address(), RegisterGroup::Special) );
+        descriptions.push( (file.program_counter(), RegisterGroup::Special) );
+
+        for special in [file.psr(), file.msp(), file.psp(), file.extra()].iter() {
+            if let Some(r) = *special {
+                descriptions.push( (r, RegisterGroup::Special) );
+            }
+        }
+
+        if let Some(fpscr) = file.fpscr() {
+            descriptions.push( (fpscr, RegisterGroup::Float) );
+        }
+
+        if let Some(fpu) = file.fpu_registers() {
+            descriptions.extend( fpu.map(|r| (r, RegisterGroup::Float)) );
+        }
+
+        // Skip aliases of already listed registers.
+        let mut out: Vec<RegisterDescriptor> = Vec::with_capacity(descriptions.len());
+
+        for (description, group) in descriptions {
+            let address = CoreRegisterAddress::from(description).0;
+
+            if out.iter().any(|r| r.address == address) {
+                continue;
+            }
+
+            out.push( RegisterDescriptor { name: String::from(description.name()), address, group } );
+        }
+
+        out
+    }
+
+    fn pcaddress(&self) -> u16 {
+        CoreRegisterAddress::from( Core::registers(self).program_counter() ).0
+    }
+
+    fn read_core_reg(&mut self, address: u16) -> Result<u32, BackendError> {
+        Ok( Core::read_core_reg(self, CoreRegisterAddress(address))? )
+    }
+
+    fn write_core_reg(&mut self, address: u16, value: u32) -> Result<(), BackendError> {
+        Ok( Core::write_core_reg(self, CoreRegisterAddress(address), value)? )
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, BackendError> {
+        Ok( Core::available_breakpoint_units(self)? )
+    }
+
+    fn set_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError> {
+        Ok( Core::set_hw_breakpoint(self, address)? )
+    }
+
+    fn clear_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError> {
+        Ok( Core::clear_hw_breakpoint(self, address)? )
+    }
+
+    fn clear_all_hw_breakpoints(&mut self) -> Result<(), BackendError> {
+        Ok( Core::clear_all_hw_breakpoints(self)? )
+    }
+}
@@ -0,0 +1,453 @@
+//! Simulated target.
+//! An in-process core with configurable memory regions and scripted halts,
+//! used to run the probe command loop without a physical probe.
+
+
+
+use crate::elf::Segment;
+
+use probe_rs::{ CoreStatus, CoreType, HaltReason };
+
+use std::{
+    collections::VecDeque,
+    time::Duration,
+};
+
+use super::{
+    Backend, BackendError, CoreInterface, RegisterDescriptor,
+    super::{ FlashEvent, FlashHandle, RegisterGroup },
+};
+
+
+
+/// Debug address of the stack pointer.
+const SP: u16 = 13;
+
+/// Debug address of the link register.
+const LR: u16 = 14;
+
+/// Debug address of the program counter.
+const PC: u16 = 15;
+
+/// Debug address of the program status register.
+const XPSR: u16 = 16;
+
+/// Number of simulated core registers.
+const NREGISTERS: usize = 17;
+
+
+
+/// A halt of a simulated core, scheduled while the core runs.
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptedHalt {
+    /// Number of status polls of the running core before the halt.
+    pub polls: usize,
+
+    /// Reason of the halt.
+    pub reason: HaltReason,
+
+    /// PC of the core at the halt.
+    pub pc: u32,
+}
+
+
+
+/// A simulated target.
+pub struct SimBackend {
+    /// Memory regions shared by all the cores.
+    regions: Vec<Region>,
+
+    /// State of the cores.
+    cores: Vec<SimState>,
+
+    /// SWO bytes not yet read, `None` if the SWO capture was not configured.
+    swo: Option<Vec<u8>>,
+
+    /// SWO bytes emitted by the target once the capture is configured.
+    swoscript: Vec<u8>,
+}
+
+impl SimBackend {
+    /// Creates a target with the given number of halted cores and no memory.
+    pub fn new(coretype: CoreType, cores: usize) -> Self {
+        SimBackend {
+            regions: Vec::new(),
+            cores: (0..cores).map(|_| SimState::new(coretype)).collect(),
+            swo: None,
+            swoscript: Vec::new(),
+        }
+    }
+
+    /// Adds a RAM region.
+    pub fn ram(mut self, start: u32, size: u32) -> Self {
+        self.regions.push( Region { start, data: vec![0; size as usize], flash: false } );
+        self
+    }
+
+    /// Adds an erased flash region. Flash can only be written through `Backend::flash`.
+    pub fn rom(mut self, start: u32, size: u32) -> Self {
+        self.regions.push( Region { start, data: vec![0xFF; size as usize], flash: true } );
+        self
+    }
+
+    /// Sets the number of hardware breakpoint units of all the cores.
+    pub fn breakpoints(mut self, units: u32) -> Self {
+        for core in self.cores.iter_mut() {
+            core.units = units;
+        }
+
+        self
+    }
+
+    /// Sets the PC of the given core after a reset.
+    pub fn resetpc(mut self, core: usize, pc: u32) -> Self {
+        if let Some(state) = self.cores.get_mut(core) {
+            state.resetpc = pc;
+        }
+
+        self
+    }
+
+    /// Sets the SWO bytes emitted by the target once the capture is configured.
+    pub fn swo(mut self, data: &[u8]) -> Self {
+        self.swoscript = data.to_vec();
+        self
+    }
+
+    /// Schedules a halt of the given core.
+    pub fn script(mut self, core: usize, halt: ScriptedHalt) -> Self {
+        if let Some(state) = self.cores.get_mut(core) {
+            state.script.push_back(halt);
+        }
+
+        self
+    }
+}
+
+impl Backend for SimBackend {
+    fn list_cores(&self) -> Vec<(usize, CoreType)> {
+        self.cores.iter()
+            .enumerate()
+            .map(|(n, core)| (n, core.coretype))
+            .collect()
+    }
+
+    fn ram(&self) -> Vec<(u32, u32)> {
+        self.regions.iter()
+            .filter(|r| !r.flash)
+            .map(|r| (r.start, r.data.len() as u32))
+            .collect()
+    }
+
+    fn core(&mut self, n: usize) -> Result<Box<dyn CoreInterface + '_>, BackendError> {
+        let SimBackend { regions, cores, .. } = self;
+
+        match cores.get_mut(n) {
+            Some(state) => Ok( Box::new( SimCore { regions, state } ) ),
+            _ => Err( BackendError( format!("Core {} does not exist", n) ) ),
+        }
+    }
+
+    fn flash(&mut self, segments: &[Segment], handle: &FlashHandle) -> Result<(), BackendError> {
+        // Check all the segments before programming any.
+        if let Some(s) = segments.iter().find(|s| !self.regions.iter().any(|r| r.flash && r.contains(s.address, s.data.len()))) {
+            return Err( BackendError( format!("No flash memory at 0x{:08X} for {} bytes", s.address, s.data.len()) ) );
+        }
+
+        let total: usize = segments.iter().map(|s| s.data.len()).sum();
+
+        handle.send( FlashEvent::Erasing );
+        handle.send( FlashEvent::Erased(total as u32) );
+        handle.send( FlashEvent::Programming );
+
+        for segment in segments {
+            if let Some(region) = self.regions.iter_mut().find(|r| r.flash && r.contains(segment.address, segment.data.len())) {
+                let offset = (segment.address - region.start) as usize;
+                region.data[offset..offset + segment.data.len()].copy_from_slice(&segment.data);
+            }
+
+            handle.send( FlashEvent::Programmed(segment.data.len() as u32) );
+        }
+
+        Ok(())
+    }
+
+    fn setup_swo(&mut self, core: usize, _: u32, baud: u32) -> Result<(), BackendError> {
+        match (self.cores.get(core), baud) {
+            (None, _) => Err( BackendError( format!("Core {} does not exist", core) ) ),
+            (_, 0) => Err( BackendError( String::from("The SWO baud rate cannot be zero") ) ),
+            _ => {
+                self.swo = Some( self.swoscript.clone() );
+                Ok(())
+            },
+        }
+    }
+
+    fn read_swo(&mut self) -> Result<Vec<u8>, BackendError> {
+        match &mut self.swo {
+            Some(data) => Ok( data.drain(..).collect() ),
+            _ => Err( BackendError( String::from("The SWO capture is not configured") ) ),
+        }
+    }
+}
+
+
+
+/// A memory region of the simulated target.
+struct Region {
+    /// Start address.
+    start: u32,
+
+    /// Contents of the region.
+    data: Vec<u8>,
+
+    /// Indicates if the region is flash memory.
+    flash: bool,
+}
+
+impl Region {
+    /// Returns `true` if the range is inside the region.
+    fn contains(&self, address: u32, len: usize) -> bool {
+        (address >= self.start) && ((address - self.start) as usize + len <= self.data.len())
+    }
+}
+
+
+
+/// State of a simulated core.
+struct SimState {
+    /// Architecture of the core.
+    coretype: CoreType,
+
+    /// Values of the core registers.
+    registers: [u32; NREGISTERS],
+
+    /// Run state of the core.
+    status: CoreStatus,
+
+    /// Scheduled halts.
+    script: VecDeque<ScriptedHalt>,
+
+    /// Active hardware breakpoints.
+    breakpoints: Vec<u32>,
+
+    /// Number of hardware breakpoint units.
+    units: u32,
+
+    /// PC after a reset.
+    resetpc: u32,
+}
+
+impl SimState {
+    fn new(coretype: CoreType) -> Self {
+        SimState {
+            coretype,
+            registers: [0; NREGISTERS],
+            status: CoreStatus::Halted(HaltReason::Request),
+            script: VecDeque::new(),
+            breakpoints: Vec::new(),
+            units: 4,
+            resetpc: 0,
+        }
+    }
+}
+
+
+
+/// A core of the simulated target.
+struct SimCore<'a> {
+    /// Memory of the target.
+    regions: &'a mut Vec<Region>,
+
+    /// State of the core.
+    state: &'a mut SimState,
+}
+
+impl<'a> SimCore<'a> {
+    /// Returns the region and offset of a RAM range.
+    fn region(&mut self, address: u32, len: usize, write: bool) -> Result<(&mut Region, usize), BackendError> {
+        match self.regions.iter_mut().find(|r| r.contains(address, len)) {
+            Some(r) if write && r.flash => Err( BackendError( format!("Cannot write flash memory at 0x{:08X}", address) ) ),
+            Some(r) => {
+                let offset = (address - r.start) as usize;
+                Ok( (r, offset) )
+            },
+            _ => Err( BackendError( format!("No memory at 0x{:08X} for {} bytes", address, len) ) ),
+        }
+    }
+
+    /// Checks that the core is halted.
+    fn halted(&self) -> Result<(), BackendError> {
+        match self.state.status {
+            CoreStatus::Halted(_) => Ok(()),
+            _ => Err( BackendError( String::from("Core is not halted") ) ),
+        }
+    }
+
+    /// Resets the registers of the core.
+    fn clear(&mut self) {
+        self.state.registers = [0; NREGISTERS];
+        self.state.registers[PC as usize] = self.state.resetpc;
+        self.state.registers[XPSR as usize] = 1 << 24;
+    }
+}
+
+impl<'a> CoreInterface for SimCore<'a> {
+    fn read_word_8(&mut self, address: u32) -> Result<u8, BackendError> {
+        let (region, offset) = self.region(address, 1, false)?;
+        Ok( region.data[offset] )
+    }
+
+    fn read_word_32(&mut self, address: u32) -> Result<u32, BackendError> {
+        let mut word = [0u32];
+        self.read_32(address, &mut word)?;
+        Ok( word[0] )
+    }
+
+    fn read_32(&mut self, address: u32, data: &mut [u32]) -> Result<(), BackendError> {
+        let (region, offset) = self.region(address, data.len() * 4, false)?;
+
+        for (i, word) in data.iter_mut().enumerate() {
+            let o = offset + (i * 4);
+            *word = u32::from_le_bytes([region.data[o], region.data[o+1], region.data[o+2], region.data[o+3]]);
+        }
+
+        Ok(())
+    }
+
+    fn write_word_8(&mut self, address: u32, data: u8) -> Result<(), BackendError> {
+        let (region, offset) = self.region(address, 1, true)?;
+        region.data[offset] = data;
+        Ok(())
+    }
+
+    fn write_word_32(&mut self, address: u32, data: u32) -> Result<(), BackendError> {
+        self.write_32(address, &[data])
+    }
+
+    fn write_32(&mut self, address: u32, data: &[u32]) -> Result<(), BackendError> {
+        let (region, offset) = self.region(address, data.len() * 4, true)?;
+
+        for (i, word) in data.iter().enumerate() {
+            let o = offset + (i * 4);
+            region.data[o..o+4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn halt(&mut self, _: Duration) -> Result<u32, BackendError> {
+        if let CoreStatus::Running = self.state.status {
+            self.state.status = CoreStatus::Halted(HaltReason::Request);
+        }
+
+        Ok( self.state.registers[PC as usize] )
+    }
+
+    fn run(&mut self) -> Result<(), BackendError> {
+        self.state.status = CoreStatus::Running;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), BackendError> {
+        self.clear();
+        self.state.status = CoreStatus::Running;
+        Ok(())
+    }
+
+    fn reset_and_halt(&mut self, _: Duration) -> Result<u32, BackendError> {
+        self.clear();
+        self.state.status = CoreStatus::Halted(HaltReason::Request);
+        Ok( self.state.registers[PC as usize] )
+    }
+
+    fn step(&mut self) -> Result<u32, BackendError> {
+        self.halted()?;
+
+        // Every instruction is a 16 bit Thumb instruction.
+        self.state.registers[PC as usize] = self.state.registers[PC as usize].wrapping_add(2);
+        self.state.status = CoreStatus::Halted(HaltReason::Step);
+
+        Ok( self.state.registers[PC as usize] )
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, BackendError> {
+        // Run the script of the core.
+        if let CoreStatus::Running = self.state.status {
+            if let Some(next) = self.state.script.front_mut() {
+                match next.polls {
+                    0 => {
+                        self.state.registers[PC as usize] = next.pc;
+                        self.state.status = CoreStatus::Halted(next.reason);
+                        self.state.script.pop_front();
+                    },
+
+                    _ => next.polls -= 1,
+                }
+            }
+        }
+
+        Ok( self.state.status )
+    }
+
+    fn descriptors(&self) -> Vec<RegisterDescriptor> {
+        (0..NREGISTERS as u16)
+            .map(|address| {
+                let (name, group) = match address {
+                    SP   => (String::from("SP"), RegisterGroup::Special),
+                    LR   => (String::from("LR"), RegisterGroup::Special),
+                    PC   => (String::from("PC"), RegisterGroup::Special),
+                    XPSR => (String::from("XPSR"), RegisterGroup::Special),
+                    n    => (format!("R{}", n), RegisterGroup::General),
+                };
+
+                RegisterDescriptor { name, address, group }
+            })
+            .collect()
+    }
+
+    fn pcaddress(&self) -> u16 {
+        PC
+    }
+
+    fn read_core_reg(&mut self, address: u16) -> Result<u32, BackendError> {
+        match self.state.registers.get(address as usize) {
+            Some(value) => Ok(*value),
+            _ => Err( BackendError( format!("Register {} does not exist", address) ) ),
+        }
+    }
+
+    fn write_core_reg(&mut self, address: u16, value: u32) -> Result<(), BackendError> {
+        match self.state.registers.get_mut(address as usize) {
+            Some(register) => {
+                *register = value;
+                Ok(())
+            },
+            _ => Err( BackendError( format!("Register {} does not exist", address) ) ),
+        }
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, BackendError> {
+        Ok( self.state.units )
+    }
+
+    fn set_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError> {
+        if self.state.breakpoints.len() as u32 >= self.state.units {
+            return Err( BackendError( String::from("No free breakpoint units") ) );
+        }
+
+        self.state.breakpoints.push(address);
+
+        Ok(())
+    }
+
+    fn clear_hw_breakpoint(&mut self, address: u32) -> Result<(), BackendError> {
+        self.state.breakpoints.retain(|a| *a != address);
+        Ok(())
+    }
+
+    fn clear_all_hw_breakpoints(&mut self) -> Result<(), BackendError> {
+        self.state.breakpoints.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+//! Probe commands and responses.
+
+
+
+use probe_rs::CoreStatus;
+
+use crate::elf::ElfFile;
+
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{ CoreDump, CoreInfo, Datatype, Error, Fault, FlashHandle, Frame, LiveSender, Mismatch, Register, RttControl, RttSender, SemihostSender, TraceConfig, TraceSender, Value, WatchKind, Watchpoint };
+
+
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Reads an `i8` at the given address.
+    ReadI8(u32),
+
+    /// Reads an `u8` at the given address.
+    ReadU8(u32),
+
+    /// Reads an `i16` at the given address.
+    ReadI16(u32),
+
+    /// Reads an `u16` at the given address.
+    ReadU16(u32),
+
+    /// Reads an `i32` at the given address.
+    ReadI32(u32),
+
+    /// Reads an `u32` at the given address.
+    ReadU32(u32),
+
+    /// Reads an `i64` at the given address.
+    ReadI64(u32),
+
+    /// Reads an `u64` at the given address.
+    ReadU64(u32),
+
+    /// Reads a value of the given datatype at the given address.
+    Read(Datatype, u32),
+
+    /// Reads the 32 bit words at the given aligned addresses with word accesses.
+    /// Used for peripheral registers, which may not support byte accesses.
+    ReadWords(Vec<u32>),
+
+    /// Halts the current core.
+    Halt,
+
+    /// Resumes execution of the current core.
+    Run,
+
+    /// Resets the current core.
+    Reset,
+
+    /// Resets the current core and halts it before the first instruction.
+    ResetAndHalt,
+
+    /// Steps the current core one instruction.
+    Step,
+
+    /// Requests the status of the current core.
+    Status,
+
+    /// Lists the cores of the target with their run state.
+    Cores,
+
+    /// Selects the core that receives the following commands.
+    SelectCore(usize),
+
+    /// Sets a hardware breakpoint at the given address.
+    SetBreakpoint(u32),
+
+    /// Clears the hardware breakpoint at the given address.
+    ClearBreakpoint(u32),
+
+    /// Clears all hardware breakpoints of the current core.
+    ClearAllBreakpoints,
+
+    /// Lists the hardware breakpoints of the current core.
+    Breakpoints,
+
+    /// Sets a watchpoint of the given kind on the given address and size in bytes.
+    SetWatchpoint(u32, u32, WatchKind),
+
+    /// Clears the watchpoint in the given comparator.
+    ClearWatchpoint(usize),
+
+    /// Lists the watchpoints of the current core.
+    Watchpoints,
+
+    /// Requests the watchpoint that halted the current core.
+    WatchpointHit,
+
+    /// Reads all registers of the current core.
+    Registers,
+
+    /// Writes the register with the given debug address.
+    WriteRegister(u16, u32),
+
+    /// Writes an `i8` at the given address.
+    WriteI8(u32, i8),
+
+    /// Writes an `u8` at the given address.
+    WriteU8(u32, u8),
+
+    /// Writes an `i16` at the given address.
+    WriteI16(u32, i16),
+
+    /// Writes an `u16` at the given address.
+    WriteU16(u32, u16),
+
+    /// Writes an `i32` at the given address.
+    WriteI32(u32, i32),
+
+    /// Writes an `u32` at the given address.
+    WriteU32(u32, u32),
+
+    /// Writes an `i64` at the given address.
+    WriteI64(u32, i64),
+
+    /// Writes an `u64` at the given address.
+    WriteU64(u32, u64),
+
+    /// Writes an `f32` at the given address.
+    WriteF32(u32, f32),
+
+    /// Writes an `f64` at the given address.
+    WriteF64(u32, f64),
+
+    /// Writes the given bytes starting at the given address.
+    WriteRange(u32, Vec<u8>),
+
+    /// Writes the 32 bit word at the given aligned address with word accesses, keeping the bits of the mask from a read of it.
+    /// Contains the address, the mask of the kept bits and the new bits. Without kept bits the word is written
+    /// without reading it, for registers with read side effects. Returns the word read back if it was read.
+    Modify(u32, u32, u32),
+
+    /// Programs the loadable segments of the ELF into the target flash on a blocking thread.
+    /// The other commands are rejected until the operation finishes.
+    Flash(Arc<ElfFile>, FlashHandle),
+
+    /// Compares the loadable segments of the ELF with the target memory.
+    Verify(Arc<ElfFile>),
+
+    /// Reads the given `(start, end)` ranges of the halted core, or all the RAM regions of the target.
+    Snapshot(Option<Vec<(u32, u32)>>),
+
+    /// Saves the registers of the halted core and all the RAM regions of the target.
+    CoreDump,
+
+    /// Unwinds the call stack of the halted core with the debug information of the ELF.
+    Backtrace(Arc<ElfFile>),
+
+    /// Analyzes the fault handled by the halted core, naming the faulting instruction with the ELF.
+    Fault(Option<Arc<ElfFile>>),
+
+    /// Attaches to the RTT control block at the given address, or scans the RAM for it.
+    /// The data of the up channels is sent through the given channel.
+    RttAttach(Option<u32>, RttSender),
+
+    /// Writes the given bytes into a RTT down channel.
+    RttWrite(usize, Vec<u8>),
+
+    /// Enables semihosting with the files sandboxed to the given directory, or disables it.
+    Semihosting(Option<(PathBuf, SemihostSender)>),
+
+    /// Configures the trace units of the current core and captures the SWO pin.
+    /// The decoded packets are sent through the given channel.
+    TraceStart(TraceConfig, TraceSender),
+
+    /// Stops the SWO capture.
+    TraceStop,
+
+    /// Samples the variables at the given addresses with the given period while the current core runs.
+    /// Fails if the core cannot read memory without halting. The samples are sent through the given channel.
+    LiveStart(Vec<(u32, Datatype)>, Duration, LiveSender),
+
+    /// Stops the sampling of the variables.
+    LiveStop,
+}
+
+
+
+#[derive(Debug, Clone)]
+pub enum Response {
+    /// Returns an `i8`.
+    I8(i8),
+
+    /// Returns an `u8`.
+    U8(u8),
+
+    /// Returns an `i16`.
+    I16(i16),
+
+    /// Returns an `u16`.
+    U16(u16),
+
+    /// Returns an `i32`.
+    I32(i32),
+
+    /// Returns an `u32`.
+    U32(u32),
+
+    /// Returns an `i64`.
+    I64(i64),
+
+    /// Returns an `u64`.
+    U64(u64),
+
+    /// Returns the status of the current core after a command.
+    Status(CoreStatus),
+
+    /// Returns the selected core and all the cores of the target.
+    Cores(usize, Vec<CoreInfo>),
+
+    /// Returns the number of breakpoint units and the active breakpoints of the current core.
+    Breakpoints(u32, Vec<u32>),
+
+    /// Returns the number of watchpoint comparators and the active watchpoints of the current core.
+    Watchpoints(u32, Vec<Watchpoint>),
+
+    /// Returns the watchpoint that halted the core, if found, and the PC at the halt.
+    WatchpointHit(Option<Watchpoint>, u32),
+
+    /// Returns all the registers of the current core.
+    Registers(Vec<Register>),
+
+    /// Returns a single register after a write.
+    Register(Register),
+
+    /// Returns the address and value of each word read.
+    Words(Vec<(u32, u32)>),
+
+    /// Returns a typed value.
+    Value(Value),
+
+    /// A write was performed and read back.
+    /// Contains the start address, the number of bytes written and the
+    /// addresses whose read back value did not match.
+    Written(u32, usize, Vec<u32>),
+
+    /// The image was programmed and verified.
+    /// Contains the number of bytes flashed and the ranges that did not match.
+    Flashed(usize, Vec<Mismatch>),
+
+    /// Returns the ranges of target memory that do not match the ELF.
+    Verified(Vec<Mismatch>),
+
+    /// Returns the start address and contents of each region of a snapshot.
+    Snapshot(Vec<(u32, Vec<u8>)>),
+
+    /// Returns the saved state of the current core.
+    CoreDump(CoreDump),
+
+    /// Returns the frames of the call stack, starting at the halted frame.
+    Backtrace(Vec<Frame>),
+
+    /// Returns the analysis of the fault, if the core is in a fault handler.
+    Fault(Option<Fault>),
+
+    /// Returns the attached RTT control block.
+    Rtt(RttControl),
+
+    /// Returns the RTT down channel and the number of bytes written into it.
+    RttWritten(usize, usize),
+
+    /// Returns if semihosting is enabled.
+    Semihosting(bool),
+
+    /// Returns if the SWO capture is running.
+    Trace(bool),
+
+    /// Returns if the variables are sampled.
+    Live(bool),
+
+    /// The command failed.
+    Error(Error),
+}
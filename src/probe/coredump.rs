@@ -0,0 +1,475 @@
+//! Core dumps.
+//! The state of a halted core is saved as an ELF core file, with a `PT_LOAD`
+//! segment for each RAM region and notes with the registers, the core and the
+//! path of the firmware ELF. The registers of ARM cores are also stored in a
+//! standard `NT_PRSTATUS` note, readable by GDB.
+
+
+
+use object::{
+    elf::{
+        FileHeader32,
+        EF_ARM_EABI_VER5, ELFCLASS32, ELFDATA2LSB, EM_ARM, EM_RISCV, ET_CORE, EV_CURRENT,
+        NT_PRSTATUS, PF_R, PF_W, PT_LOAD, PT_NOTE,
+    },
+    read::elf::{ FileHeader, ProgramHeader },
+};
+
+use probe_rs::CoreType;
+
+use std::path::{ Path, PathBuf };
+
+use super::{ Endianness, Register, RegisterGroup };
+
+
+
+/// Owner of the notes of this debugger.
+const OWNER: &[u8] = b"SI4P";
+
+/// Owner of the standard core notes.
+const CORE: &[u8] = b"CORE";
+
+/// Note with the registers of the core.
+const NT_REGISTERS: u32 = 1;
+
+/// Note with the architecture and byte order of the core.
+const NT_CORE: u32 = 2;
+
+/// Note with the path of the firmware ELF.
+const NT_ELFPATH: u32 = 3;
+
+/// Size of the ARM `elf_prstatus` structure.
+const PRSTATUS: usize = 148;
+
+/// Offset of the registers in the ARM `elf_prstatus` structure.
+const PRREG: usize = 72;
+
+/// Number of registers in the ARM `elf_prstatus` structure: R0-R15, CPSR and ORIG_R0.
+const PRNREG: usize = 18;
+
+/// Names of the ARM registers of a `NT_PRSTATUS` note, by debug address.
+const PRNAMES: [&str; 17] = ["R0", "R1", "R2", "R3", "R4", "R5", "R6", "R7", "R8", "R9", "R10", "R11", "R12", "SP", "LR", "PC", "XPSR"];
+
+/// Architectures stored in a dump, by index.
+const CORETYPES: [CoreType; 5] = [CoreType::Armv6m, CoreType::Armv7m, CoreType::Armv7em, CoreType::Armv8m, CoreType::Riscv];
+
+/// Size of the ELF header.
+const EHSIZE: usize = 52;
+
+/// Size of a program header.
+const PHENTSIZE: usize = 32;
+
+
+
+/// The saved state of a halted core.
+#[derive(Debug, Clone)]
+pub struct CoreDump {
+    /// Architecture of the core.
+    pub coretype: CoreType,
+
+    /// Byte order of the target memory.
+    pub endianness: Endianness,
+
+    /// Debug address of the program counter.
+    pub pcaddress: u16,
+
+    /// Registers of the core.
+    pub registers: Vec<Register>,
+
+    /// RAM regions as (address, contents) pairs.
+    pub regions: Vec<(u32, Vec<u8>)>,
+
+    /// Path of the firmware ELF.
+    pub elf: Option<PathBuf>,
+}
+
+impl CoreDump {
+    /// Reads the core dump stored in a file.
+    pub async fn load(path: &Path) -> Result<Self, String> {
+        match tokio::fs::read(path).await {
+            Ok(data) => Self::parse(&data).map_err(|e| format!("Could not parse core dump {}: {}", path.display(), e)),
+            Err(e) => Err( format!("Could not read core dump {}: {}", path.display(), e) ),
+        }
+    }
+
+    /// Writes the core dump into a file.
+    pub async fn save(&self, path: &Path) -> Result<(), String> {
+        match tokio::fs::write(path, self.serialize()).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err( format!("Could not write core dump {}: {}", path.display(), e) ),
+        }
+    }
+
+    /// Builds the ELF core file of the dump.
+    pub fn serialize(&self) -> Vec<u8> {
+        let arm = self.coretype != CoreType::Riscv;
+
+        // Notes of the dump.
+        let mut notes = Vec::new();
+
+        if arm {
+            note(&mut notes, CORE, NT_PRSTATUS, &self.prstatus());
+        }
+
+        let core = [
+            CORETYPES.iter().position(|c| *c == self.coretype).unwrap_or(0) as u8,
+            (self.endianness == Endianness::Big) as u8,
+        ];
+
+        note(&mut notes, OWNER, NT_CORE, &core);
+
+        let mut registers = self.pcaddress.to_le_bytes().to_vec();
+
+        for register in self.registers.iter() {
+            let name = &register.name.as_bytes()[..register.name.len().min(255)];
+
+            let group = match register.group {
+                RegisterGroup::General => 0,
+                RegisterGroup::Special => 1,
+                RegisterGroup::Float   => 2,
+            };
+
+            registers.extend_from_slice( &register.address.to_le_bytes() );
+            registers.push(group);
+            registers.push(name.len() as u8);
+            registers.extend_from_slice( &register.value.to_le_bytes() );
+            registers.extend_from_slice(name);
+        }
+
+        note(&mut notes, OWNER, NT_REGISTERS, &registers);
+
+        if let Some(path) = &self.elf {
+            note(&mut notes, OWNER, NT_ELFPATH, path.to_string_lossy().as_bytes());
+        }
+
+        // File header.
+        let phnum = 1 + self.regions.len();
+
+        let (machine, flags) = match arm {
+            true => (EM_ARM, EF_ARM_EABI_VER5),
+            _ => (EM_RISCV, 0),
+        };
+
+        let mut file = vec![0x7F, b'E', b'L', b'F', ELFCLASS32, ELFDATA2LSB, EV_CURRENT, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        file.extend_from_slice(&ET_CORE.to_le_bytes());
+        file.extend_from_slice(&machine.to_le_bytes());
+        file.extend_from_slice(&(EV_CURRENT as u32).to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&(EHSIZE as u32).to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&flags.to_le_bytes());
+        file.extend_from_slice(&(EHSIZE as u16).to_le_bytes());
+        file.extend_from_slice(&(PHENTSIZE as u16).to_le_bytes());
+        file.extend_from_slice(&(phnum as u16).to_le_bytes());
+        file.extend_from_slice(&40u16.to_le_bytes());
+        file.extend_from_slice(&0u16.to_le_bytes());
+        file.extend_from_slice(&0u16.to_le_bytes());
+
+        // Program headers, with the contents after them in the same order.
+        let mut offset = (EHSIZE + (phnum * PHENTSIZE)) as u32;
+
+        phdr(&mut file, PT_NOTE, offset, 0, notes.len() as u32, PF_R, 4);
+        offset += notes.len() as u32;
+
+        for (address, data) in self.regions.iter() {
+            phdr(&mut file, PT_LOAD, offset, *address, data.len() as u32, PF_R | PF_W, 1);
+            offset += data.len() as u32;
+        }
+
+        file.extend_from_slice(&notes);
+
+        for (_, data) in self.regions.iter() {
+            file.extend_from_slice(data);
+        }
+
+        file
+    }
+
+    /// Parses an ELF core file.
+    /// Core files without the notes of this debugger are read through their `NT_PRSTATUS` note.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let header = FileHeader32::<object::Endianness>::parse(data).map_err(|e| e.to_string())?;
+        let endian = header.endian().map_err(|e| e.to_string())?;
+
+        if header.e_type(endian) != ET_CORE {
+            return Err( String::from("The file is not an ELF core file") );
+        }
+
+        if header.e_ident().data != ELFDATA2LSB {
+            return Err( String::from("Big endian core files are not supported") );
+        }
+
+        let mut dump = CoreDump {
+            coretype: match header.e_machine(endian) {
+                EM_RISCV => CoreType::Riscv,
+                _ => CoreType::Armv7m,
+            },
+            endianness: Endianness::Little,
+            pcaddress: 15,
+            registers: Vec::new(),
+            regions: Vec::new(),
+            elf: None,
+        };
+
+        let mut prstatus = None;
+
+        for segment in header.program_headers(endian, data).map_err(|e| e.to_string())? {
+            let bytes = match segment.data(endian, data) {
+                Err(_) => return Err( String::from("Segment data out of bounds") ),
+                Ok(b) => b,
+            };
+
+            match segment.p_type(endian) {
+                PT_LOAD => dump.regions.push( (segment.p_vaddr(endian), bytes.to_vec()) ),
+
+                PT_NOTE => for (name, ntype, desc) in notes(bytes)? {
+                    match (name, ntype) {
+                        (OWNER, NT_CORE) if desc.len() >= 2 => {
+                            dump.coretype = match CORETYPES.get(desc[0] as usize) {
+                                Some(coretype) => *coretype,
+                                _ => return Err( format!("Unknown core type {}", desc[0]) ),
+                            };
+
+                            dump.endianness = match desc[1] {
+                                0 => Endianness::Little,
+                                _ => Endianness::Big,
+                            };
+                        },
+
+                        (OWNER, NT_REGISTERS) => {
+                            let (pcaddress, registers) = registers(desc)?;
+
+                            dump.pcaddress = pcaddress;
+                            dump.registers = registers;
+                        },
+
+                        (OWNER, NT_ELFPATH) => dump.elf = Some( PathBuf::from( String::from_utf8_lossy(desc).into_owned() ) ),
+
+                        (CORE, NT_PRSTATUS) if desc.len() >= PRSTATUS => prstatus = Some(desc),
+
+                        _ => (),
+                    }
+                },
+
+                _ => (),
+            }
+        }
+
+        // Fall back to the standard ARM registers.
+        if let (true, Some(desc)) = (dump.registers.is_empty(), prstatus) {
+            dump.registers = PRNAMES.iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let o = PRREG + (i * 4);
+
+                    Register {
+                        name: String::from(*name),
+                        address: i as u16,
+                        value: u32::from_le_bytes([desc[o], desc[o+1], desc[o+2], desc[o+3]]),
+                        group: if i < 13 { RegisterGroup::General } else { RegisterGroup::Special },
+                    }
+                })
+                .collect();
+        }
+
+        if dump.registers.is_empty() {
+            return Err( String::from("The core file has no registers") );
+        }
+
+        Ok( dump )
+    }
+
+    /// Builds the ARM `elf_prstatus` structure with R0-R15 and XPSR.
+    fn prstatus(&self) -> Vec<u8> {
+        let mut desc = vec![0u8; PRSTATUS];
+
+        for register in self.registers.iter().filter(|r| (r.address as usize) < PRNREG - 1) {
+            let o = PRREG + (register.address as usize * 4);
+            desc[o..o+4].copy_from_slice( &register.value.to_le_bytes() );
+        }
+
+        desc
+    }
+}
+
+
+
+/// Appends a note.
+fn note(out: &mut Vec<u8>, name: &[u8], ntype: u32, desc: &[u8]) {
+    out.extend_from_slice( &(name.len() as u32 + 1).to_le_bytes() );
+    out.extend_from_slice( &(desc.len() as u32).to_le_bytes() );
+    out.extend_from_slice( &ntype.to_le_bytes() );
+
+    out.extend_from_slice(name);
+    out.push(0);
+    out.resize(align(out.len()), 0);
+
+    out.extend_from_slice(desc);
+    out.resize(align(out.len()), 0);
+}
+
+/// Appends a program header.
+fn phdr(out: &mut Vec<u8>, ptype: u32, offset: u32, address: u32, size: u32, flags: u32, alignment: u32) {
+    for field in [ptype, offset, address, address, size, size, flags, alignment].iter() {
+        out.extend_from_slice( &field.to_le_bytes() );
+    }
+}
+
+/// Splits a note segment into its notes, as (owner, type, description) tuples.
+fn notes(mut data: &[u8]) -> Result<Vec<(&[u8], u32, &[u8])>, String> {
+    let mut out = Vec::new();
+
+    while data.len() >= 12 {
+        let word = |o: usize| u32::from_le_bytes([data[o], data[o+1], data[o+2], data[o+3]]) as usize;
+
+        let (namesz, descsz, ntype) = (word(0), word(4), word(8) as u32);
+
+        let start = 12 + align(namesz);
+        let end = start + descsz;
+
+        if end > data.len() {
+            return Err( String::from("Truncated note") );
+        }
+
+        // The owner ends with a nul terminator.
+        let name = &data[12..12 + namesz];
+        let name = match name.iter().position(|b| *b == 0) {
+            Some(n) => &name[..n],
+            _ => name,
+        };
+
+        out.push( (name, ntype, &data[start..end]) );
+
+        data = &data[align(end).min(data.len())..];
+    }
+
+    Ok( out )
+}
+
+/// Parses the register note.
+fn registers(desc: &[u8]) -> Result<(u16, Vec<Register>), String> {
+    if desc.len() < 2 {
+        return Err( String::from("Truncated register note") );
+    }
+
+    let pcaddress = u16::from_le_bytes([desc[0], desc[1]]);
+
+    let mut registers = Vec::new();
+    let mut data = &desc[2..];
+
+    while data.len() > 0 {
+        if data.len() < 8 || data.len() < 8 + data[3] as usize {
+            return Err( String::from("Truncated register note") );
+        }
+
+        let group = match data[2] {
+            0 => RegisterGroup::General,
+            1 => RegisterGroup::Special,
+            _ => RegisterGroup::Float,
+        };
+
+        let end = 8 + data[3] as usize;
+
+        registers.push( Register {
+            name: String::from_utf8_lossy(&data[8..end]).into_owned(),
+            address: u16::from_le_bytes([data[0], data[1]]),
+            value: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            group,
+        });
+
+        data = &data[end..];
+    }
+
+    Ok( (pcaddress, registers) )
+}
+
+/// Rounds up to a multiple of 4 bytes.
+fn align(n: usize) -> usize {
+    (n + 3) & !3
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump() -> CoreDump {
+        let register = |name: &str, address, value, group| Register { name: String::from(name), address, value, group };
+
+        CoreDump {
+            coretype: CoreType::Armv7em,
+            endianness: Endianness::Little,
+            pcaddress: 15,
+            registers: vec![
+                register("R0", 0, 0x1111_1111, RegisterGroup::General),
+                register("PC", 15, 0x0800_0123, RegisterGroup::Special),
+                register("XPSR", 16, 0x2100_0003, RegisterGroup::Special),
+                register("S0", 0x40, 0x3F80_0000, RegisterGroup::Float),
+            ],
+            regions: vec![ (0x2000_0000, vec![1, 2, 3, 4, 5]), (0x2001_0000, vec![0xAA; 8]) ],
+            elf: Some( PathBuf::from("/work/firmware.elf") ),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let dump = dump();
+        let parsed = CoreDump::parse(&dump.serialize()).unwrap();
+
+        assert_eq!(parsed.coretype, dump.coretype);
+        assert_eq!(parsed.endianness, dump.endianness);
+        assert_eq!(parsed.pcaddress, dump.pcaddress);
+        assert_eq!(parsed.registers, dump.registers);
+        assert_eq!(parsed.regions, dump.regions);
+        assert_eq!(parsed.elf, dump.elf);
+    }
+
+    #[test]
+    fn prstatus() {
+        let file = dump().serialize();
+
+        // The first note is the standard process status.
+        let header = FileHeader32::<object::Endianness>::parse(&*file).unwrap();
+        let endian = header.endian().unwrap();
+
+        let segment = &header.program_headers(endian, &*file).unwrap()[0];
+        assert_eq!(segment.p_type(endian), PT_NOTE);
+
+        let notes = notes(segment.data(endian, &*file).unwrap()).unwrap();
+        assert_eq!((notes[0].0, notes[0].1, notes[0].2.len()), (CORE, NT_PRSTATUS, PRSTATUS));
+
+        let reg = |i: usize| u32::from_le_bytes([notes[0].2[PRREG + i*4], notes[0].2[PRREG + i*4 + 1], notes[0].2[PRREG + i*4 + 2], notes[0].2[PRREG + i*4 + 3]]);
+        assert_eq!((reg(0), reg(15), reg(16)), (0x1111_1111, 0x0800_0123, 0x2100_0003));
+
+        // Without the notes of this debugger the registers come from the process status.
+        let foreign: Vec<u8> = {
+            let mut dump = dump();
+            dump.registers.retain(|r| r.address < 17);
+
+            let mut file = dump.serialize();
+
+            while let Some(owner) = file.windows(4).position(|w| w == OWNER) {
+                file[owner] = b'X';
+            }
+
+            file
+        };
+
+        let parsed = CoreDump::parse(&foreign).unwrap();
+        assert_eq!(parsed.registers.len(), PRNAMES.len());
+        assert_eq!((parsed.registers[15].name.as_str(), parsed.registers[15].value), ("PC", 0x0800_0123));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(CoreDump::parse(&[0x7F, b'E', b'L', b'F']).is_err());
+
+        // An executable is not a core file.
+        let mut file = dump().serialize();
+        file[16] = 2;
+
+        assert!(CoreDump::parse(&file).is_err());
+    }
+}
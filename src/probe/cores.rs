@@ -0,0 +1,35 @@
+//! Core abstractions.
+
+
+
+use probe_rs::{ CoreStatus, CoreType };
+
+
+
+/// A core of the target and its run state.
+#[derive(Debug, Clone, Copy)]
+pub struct CoreInfo {
+    /// Index of the core in the session.
+    pub index: usize,
+
+    /// Architecture of the core.
+    pub coretype: CoreType,
+
+    /// Run state of the core, if it could be read.
+    pub status: Option<CoreStatus>,
+}
+
+impl CoreInfo {
+    /// Returns the name of the architecture of the core.
+    pub fn architecture(&self) -> &'static str {
+        match self.coretype {
+            CoreType::Armv6m  => "ARMv6-M",
+            CoreType::Armv7m  => "ARMv7-M",
+            CoreType::Armv7em => "ARMv7E-M",
+            CoreType::Armv8m  => "ARMv8-M",
+            CoreType::Riscv   => "RISC-V",
+            #[allow(unreachable_patterns)]
+            _ => "Unknown",
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Typed decoding and encoding of target memory.
+//! All conversions between raw target bytes and typed values go through this module.
+
+
+
+use architecture::Chip;
+
+use serde::{ Deserialize, Serialize };
+
+use super::Error;
+
+
+
+/// Byte order of the target memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// Returns the byte order of the given chip.
+    pub fn of(chip: &Chip) -> Self {
+        match chip.endianness() {
+            architecture::Endianness::Big => Endianness::Big,
+            _ => Endianness::Little,
+        }
+    }
+
+    /// Decodes an `u16` from the first 2 bytes of the data.
+    pub fn u16(&self, data: &[u8]) -> Result<u16, Error> {
+        let bytes = take::<2>(data)?;
+
+        Ok( match *self {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big    => u16::from_be_bytes(bytes),
+        })
+    }
+
+    /// Decodes an `u32` from the first 4 bytes of the data.
+    pub fn u32(&self, data: &[u8]) -> Result<u32, Error> {
+        let bytes = take::<4>(data)?;
+
+        Ok( match *self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big    => u32::from_be_bytes(bytes),
+        })
+    }
+
+    /// Decodes an `u64` from the first 8 bytes of the data.
+    pub fn u64(&self, data: &[u8]) -> Result<u64, Error> {
+        let bytes = take::<8>(data)?;
+
+        Ok( match *self {
+            Endianness::Little => u64::from_le_bytes(bytes),
+            Endianness::Big    => u64::from_be_bytes(bytes),
+        })
+    }
+
+    /// Encodes an `u16` into target bytes.
+    pub fn encode16(&self, value: u16) -> [u8; 2] {
+        match *self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big    => value.to_be_bytes(),
+        }
+    }
+
+    /// Encodes an `u32` into target bytes.
+    pub fn encode32(&self, value: u32) -> [u8; 4] {
+        match *self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big    => value.to_be_bytes(),
+        }
+    }
+
+    /// Encodes an `u64` into target bytes.
+    pub fn encode64(&self, value: u64) -> [u8; 8] {
+        match *self {
+            Endianness::Little => value.to_le_bytes(),
+            Endianness::Big    => value.to_be_bytes(),
+        }
+    }
+}
+
+impl Default for Endianness {
+    fn default() -> Self {
+        Endianness::Little
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Datatype {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+
+    Float16,
+    Float32,
+
+    BFloat16,
+
+    Char,
+}
+
+impl Datatype {
+    /// Size in bytes of the datatype in target memory.
+    pub const fn size(&self) -> usize {
+        match *self {
+            Datatype::Int8  | Datatype::UInt8  | Datatype::Char => 1,
+            Datatype::Int16 | Datatype::UInt16 | Datatype::Float16 | Datatype::BFloat16 => 2,
+            Datatype::Int32 | Datatype::UInt32 | Datatype::Float32 => 4,
+            Datatype::Int64 | Datatype::UInt64 => 8,
+        }
+    }
+}
+
+
+
+/// A typed value decoded from target memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+
+    /// A half precision float, widened to `f32`.
+    F16(f32),
+
+    F32(f32),
+
+    /// A brain float, widened to `f32`.
+    BF16(f32),
+
+    Char(char),
+}
+
+impl Value {
+    /// Returns the datatype of the value.
+    pub fn datatype(&self) -> Datatype {
+        match *self {
+            Value::I8(_)   => Datatype::Int8,
+            Value::U8(_)   => Datatype::UInt8,
+            Value::I16(_)  => Datatype::Int16,
+            Value::U16(_)  => Datatype::UInt16,
+            Value::I32(_)  => Datatype::Int32,
+            Value::U32(_)  => Datatype::UInt32,
+            Value::I64(_)  => Datatype::Int64,
+            Value::U64(_)  => Datatype::UInt64,
+            Value::F16(_)  => Datatype::Float16,
+            Value::F32(_)  => Datatype::Float32,
+            Value::BF16(_) => Datatype::BFloat16,
+            Value::Char(_) => Datatype::Char,
+        }
+    }
+
+    /// Returns the value as a number, `None` for characters.
+    pub fn number(&self) -> Option<f64> {
+        Some( match *self {
+            Value::I8(v)   => v as f64,
+            Value::U8(v)   => v as f64,
+            Value::I16(v)  => v as f64,
+            Value::U16(v)  => v as f64,
+            Value::I32(v)  => v as f64,
+            Value::U32(v)  => v as f64,
+            Value::I64(v)  => v as f64,
+            Value::U64(v)  => v as f64,
+            Value::F16(v)  => v as f64,
+            Value::F32(v)  => v as f64,
+            Value::BF16(v) => v as f64,
+            Value::Char(_) => return None,
+        })
+    }
+}
+
+impl core::fmt::Display for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            Value::I8(v)   => write!(f, "{}", v),
+            Value::U8(v)   => write!(f, "{} (0x{:02X})", v, v),
+            Value::I16(v)  => write!(f, "{}", v),
+            Value::U16(v)  => write!(f, "{} (0x{:04X})", v, v),
+            Value::I32(v)  => write!(f, "{}", v),
+            Value::U32(v)  => write!(f, "{} (0x{:08X})", v, v),
+            Value::I64(v)  => write!(f, "{}", v),
+            Value::U64(v)  => write!(f, "{} (0x{:016X})", v, v),
+            Value::F16(v)  => write!(f, "{}", v),
+            Value::F32(v)  => write!(f, "{}", v),
+            Value::BF16(v) => write!(f, "{}", v),
+            Value::Char(c) => write!(f, "{:?}", c),
+        }
+    }
+}
+
+
+
+/// Decodes a value of the given datatype from the start of the data.
+pub fn decode(datatype: Datatype, data: &[u8], endianness: Endianness) -> Result<Value, Error> {
+    Ok( match datatype {
+        Datatype::Int8     => Value::I8( take::<1>(data)?[0] as i8 ),
+        Datatype::UInt8    => Value::U8( take::<1>(data)?[0] ),
+        Datatype::Int16    => Value::I16( endianness.u16(data)? as i16 ),
+        Datatype::UInt16   => Value::U16( endianness.u16(data)? ),
+        Datatype::Int32    => Value::I32( endianness.u32(data)? as i32 ),
+        Datatype::UInt32   => Value::U32( endianness.u32(data)? ),
+        Datatype::Int64    => Value::I64( endianness.u64(data)? as i64 ),
+        Datatype::UInt64   => Value::U64( endianness.u64(data)? ),
+        Datatype::Float16  => Value::F16( f16tof32( endianness.u16(data)? ) ),
+        Datatype::Float32  => Value::F32( f32::from_bits( endianness.u32(data)? ) ),
+        Datatype::BFloat16 => Value::BF16( f32::from_bits( (endianness.u16(data)? as u32) << 16 ) ),
+        Datatype::Char     => Value::Char( char::from( take::<1>(data)?[0] ) ),
+    })
+}
+
+/// Converts the bits of an IEEE 754 half precision float into an `f32`.
+pub fn f16tof32(bits: u16) -> f32 {
+    let sign = ((bits as u32) & 0x8000) << 16;
+    let exp  = ((bits as u32) >> 10) & 0x1F;
+    let man  = (bits as u32) & 0x3FF;
+
+    let out = match (exp, man) {
+        // Signed zero.
+        (0, 0) => sign,
+
+        // Subnormal, normalize the mantissa.
+        (0, _) => {
+            let shift = man.leading_zeros() - 21;
+            let man = (man << shift) & 0x3FF;
+            let exp = 127 - 15 + 1 - shift;
+
+            sign | (exp << 23) | (man << 13)
+        },
+
+        // Infinity and NaN.
+        (0x1F, _) => sign | 0x7F80_0000 | (man << 13),
+
+        // Normal number.
+        _ => sign | ((exp + 127 - 15) << 23) | (man << 13),
+    };
+
+    f32::from_bits(out)
+}
+
+/// Takes the first `N` bytes of the data.
+fn take<const N: usize>(data: &[u8]) -> Result<[u8; N], Error> {
+    if data.len() < N {
+        return Err( Error::NotEnoughData(N, data.len()) );
+    }
+
+    let mut out = [0u8; N];
+    out.copy_from_slice(&data[..N]);
+
+    Ok( out )
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x88];
+
+        assert_eq!(decode(Datatype::UInt16, &data, Endianness::Little).unwrap(), Value::U16(0x0201));
+        assert_eq!(decode(Datatype::UInt16, &data, Endianness::Big).unwrap(),    Value::U16(0x0102));
+        assert_eq!(decode(Datatype::UInt32, &data, Endianness::Little).unwrap(), Value::U32(0x04030201));
+        assert_eq!(decode(Datatype::UInt32, &data, Endianness::Big).unwrap(),    Value::U32(0x01020304));
+        assert_eq!(decode(Datatype::UInt64, &data, Endianness::Little).unwrap(), Value::U64(0x8807060504030201));
+        assert_eq!(decode(Datatype::UInt64, &data, Endianness::Big).unwrap(),    Value::U64(0x0102030405060788));
+
+        assert_eq!(decode(Datatype::Int8,  &[0xFF], Endianness::Little).unwrap(), Value::I8(-1));
+        assert_eq!(decode(Datatype::Int16, &[0xFE, 0xFF], Endianness::Little).unwrap(), Value::I16(-2));
+        assert_eq!(decode(Datatype::Int64, &data, Endianness::Little).unwrap(), Value::I64(0x8807060504030201u64 as i64));
+    }
+
+    #[test]
+    fn floats() {
+        let f32bytes = 1.5f32.to_bits().to_be_bytes();
+        assert_eq!(decode(Datatype::Float32, &f32bytes, Endianness::Big).unwrap(), Value::F32(1.5));
+
+        // Half precision.
+        assert_eq!(decode(Datatype::Float16, &[0x00, 0x3C], Endianness::Little).unwrap(), Value::F16(1.0));
+        assert_eq!(decode(Datatype::Float16, &[0xC0, 0x00], Endianness::Big).unwrap(), Value::F16(-2.0));
+        assert_eq!(decode(Datatype::Float16, &[0xFF, 0x7B], Endianness::Little).unwrap(), Value::F16(65504.0));
+        assert_eq!(decode(Datatype::Float16, &[0x01, 0x00], Endianness::Little).unwrap(), Value::F16(2.0f32.powi(-24)));
+        assert_eq!(f16tof32(0x7C00), f32::INFINITY);
+        assert!(f16tof32(0x7E00).is_nan());
+
+        // Brain float.
+        assert_eq!(decode(Datatype::BFloat16, &[0x80, 0x3F], Endianness::Little).unwrap(), Value::BF16(1.0));
+        assert_eq!(decode(Datatype::BFloat16, &[0x40, 0x49], Endianness::Big).unwrap(), Value::BF16(3.140625));
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(decode(Datatype::Char, b"A", Endianness::Big).unwrap(), Value::Char('A'));
+    }
+
+    #[test]
+    fn short_data() {
+        match decode(Datatype::UInt32, &[0, 1], Endianness::Little) {
+            Err(Error::NotEnoughData(4, 2)) => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for e in [Endianness::Little, Endianness::Big].iter() {
+            assert_eq!(e.u16(&e.encode16(0xBEEF)).unwrap(), 0xBEEF);
+            assert_eq!(e.u32(&e.encode32(0xDEADBEEF)).unwrap(), 0xDEADBEEF);
+            assert_eq!(e.u64(&e.encode64(0x0123456789ABCDEF)).unwrap(), 0x0123456789ABCDEF);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Cortex-M fault analysis.
+//! Decodes the fault status registers of the System Control Block and the
+//! exception frame stacked on fault entry into a readable explanation.
+
+
+
+/// Configurable Fault Status Register.
+pub const CFSR: u32 = 0xE000_ED28;
+
+/// HardFault Status Register.
+pub const HFSR: u32 = 0xE000_ED2C;
+
+/// MemManage Fault Address Register.
+pub const MMFAR: u32 = 0xE000_ED34;
+
+/// BusFault Address Register.
+pub const BFAR: u32 = 0xE000_ED38;
+
+/// Auxiliary Fault Status Register.
+pub const AFSR: u32 = 0xE000_ED3C;
+
+/// Range of the fault status registers, saved in core dumps.
+pub const FAULTREGS: (u32, u32) = (CFSR, AFSR + 4);
+
+
+
+/// Fault status registers of the System Control Block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultRegisters {
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub mmfar: u32,
+    pub bfar: u32,
+    pub afsr: u32,
+}
+
+
+
+/// Registers stacked by the hardware on exception entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackedFrame {
+    /// Address of the frame in the stack.
+    pub address: u32,
+
+    /// R0 to R3.
+    pub r: [u32; 4],
+
+    pub r12: u32,
+    pub lr: u32,
+    pub pc: u32,
+    pub xpsr: u32,
+}
+
+impl StackedFrame {
+    /// Builds the frame from the 8 words stacked at the given address.
+    pub fn new(address: u32, words: [u32; 8]) -> Self {
+        StackedFrame {
+            address,
+            r: [words[0], words[1], words[2], words[3]],
+            r12: words[4],
+            lr: words[5],
+            pc: words[6],
+            xpsr: words[7],
+        }
+    }
+}
+
+impl core::fmt::Display for StackedFrame {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "R0 0x{:08X}  R1 0x{:08X}  R2 0x{:08X}  R3 0x{:08X}  R12 0x{:08X}  LR 0x{:08X}  PC 0x{:08X}  xPSR 0x{:08X}",
+            self.r[0], self.r[1], self.r[2], self.r[3], self.r12, self.lr, self.pc, self.xpsr)
+    }
+}
+
+
+
+/// Direction of a memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl Access {
+    /// Decodes the direction of the load or store Thumb instruction starting with the given halfword.
+    /// The direction of 32 bit instructions is fully encoded in their first halfword.
+    pub fn of(hw1: u16) -> Option<Access> {
+        let load = |bit: u16| match (hw1 >> bit) & 1 {
+            0 => Some(Access::Write),
+            _ => Some(Access::Read),
+        };
+
+        match hw1 >> 11 {
+            // 32 bit instructions.
+            0b11101 | 0b11110 | 0b11111 => match hw1 & 0xFE00 {
+                // Load and store single, multiple, dual and exclusive, and coprocessor transfers.
+                0xF800 | 0xE800 | 0xEC00 => load(4),
+                _ => None,
+            },
+
+            // Load literal.
+            0b01001 => Some(Access::Read),
+
+            // Load and store with register offset.
+            0b01010 | 0b01011 => match (hw1 >> 9) & 7 {
+                0..=2 => Some(Access::Write),
+                _ => Some(Access::Read),
+            },
+
+            // Load and store with immediate offset, relative to SP, and multiple.
+            0b01100..=0b10011 | 0b11000 | 0b11001 => load(11),
+
+            // Push and pop.
+            _ => match hw1 & 0xFE00 {
+                0xB400 => Some(Access::Write),
+                0xBC00 => Some(Access::Read),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl core::fmt::Display for Access {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Access::Read => write!(f, "reading"),
+            Access::Write => write!(f, "writing"),
+        }
+    }
+}
+
+
+
+/// State of a core halted in a fault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    /// Number of the active exception.
+    pub exception: u16,
+
+    /// Fault status registers, `None` if the core has none or they could not be read.
+    pub registers: Option<FaultRegisters>,
+
+    /// The core implements the ARMv6-M architecture, which has no fault status registers.
+    pub armv6m: bool,
+
+    /// Registers stacked on fault entry.
+    pub frame: Option<StackedFrame>,
+
+    /// Address of the faulting instruction.
+    pub pc: u32,
+
+    /// Direction of the faulting instruction, if it accesses memory.
+    pub access: Option<Access>,
+
+    /// Function of the faulting instruction and offset of the PC in it.
+    pub function: Option<String>,
+
+    /// Source file and line of the faulting instruction.
+    pub source: Option<(String, Option<u32>)>,
+}
+
+impl Fault {
+    /// Returns `true` if the exception is a fault.
+    pub fn isfault(exception: u16) -> bool {
+        (3..=7).contains(&exception)
+    }
+
+    /// Returns the name of the active exception.
+    pub fn name(&self) -> String {
+        match self.exception {
+            3 => String::from("HardFault"),
+            4 => String::from("MemManage fault"),
+            5 => String::from("BusFault"),
+            6 => String::from("UsageFault"),
+            7 => String::from("SecureFault"),
+            n => format!("Exception {}", n),
+        }
+    }
+
+    /// Returns the faulting instruction as its function and offset, or its address.
+    fn site(&self) -> String {
+        match &self.function {
+            Some(function) => format!("{} (0x{:08X})", function, self.pc),
+            _ => format!("0x{:08X}", self.pc),
+        }
+    }
+
+    /// Returns the source location of the faulting instruction.
+    pub fn location(&self) -> Option<String> {
+        self.source.as_ref().map(|(file, line)| match line {
+            Some(line) => format!("{}:{}", file, line),
+            _ => file.clone(),
+        })
+    }
+
+    /// Explains the causes of the fault, one per line.
+    pub fn explain(&self) -> Vec<String> {
+        let site = self.site();
+
+        let registers = match self.registers {
+            Some(registers) => registers,
+
+            _ if self.armv6m => return vec![
+                format!("{} at PC {}. ARMv6-M cores have no fault status registers, check the stacked frame for the cause", self.name(), site),
+            ],
+
+            _ => return vec![ format!("{} at PC {}. The fault status registers could not be read", self.name(), site) ],
+        };
+
+        let access = self.access.map(|a| a.to_string()).unwrap_or_else(|| String::from("accessing"));
+
+        let address = |valid: bool, address: u32| match valid {
+            true => format!("0x{:08X}", address),
+            _ => String::from("an unknown address"),
+        };
+
+        let cfsr = registers.cfsr;
+        let bit = |n: u32| (cfsr & (1 << n)) != 0;
+
+        let mut causes = Vec::new();
+
+        // MemManage faults.
+        if bit(0) { causes.push( format!("MPU violation executing the instruction at PC {}", site) ); }
+        if bit(1) { causes.push( format!("MPU violation {} {} at PC {}", access, address(bit(7), registers.mmfar), site) ); }
+        if bit(3) { causes.push( String::from("MPU violation unstacking the exception frame on exception return") ); }
+        if bit(4) { causes.push( String::from("MPU violation stacking the exception frame, the stack may have overflowed") ); }
+        if bit(5) { causes.push( String::from("MPU violation preserving the floating point state") ); }
+
+        // Bus faults.
+        if bit(8)  { causes.push( format!("Bus fault fetching the instruction at PC {}", site) ); }
+        if bit(9)  { causes.push( format!("Precise bus fault {} {} at PC {}", access, address(bit(15), registers.bfar), site) ); }
+        if bit(10) { causes.push( format!("Imprecise bus fault, the faulting access was issued before PC {}", site) ); }
+        if bit(11) { causes.push( String::from("Bus fault unstacking the exception frame on exception return") ); }
+        if bit(12) { causes.push( String::from("Bus fault stacking the exception frame, the stack may have overflowed") ); }
+        if bit(13) { causes.push( String::from("Bus fault preserving the floating point state") ); }
+
+        // Usage faults.
+        if bit(16) { causes.push( format!("Undefined instruction at PC {}", site) ); }
+        if bit(17) { causes.push( format!("Invalid execution state at PC {}, a branch to an address without the Thumb bit set", site) ); }
+        if bit(18) { causes.push( String::from("Invalid EXC_RETURN value on exception return") ); }
+        if bit(19) { causes.push( format!("Coprocessor instruction at PC {} with the coprocessor disabled", site) ); }
+        if bit(20) { causes.push( String::from("Stack overflow detected by the stack limit registers") ); }
+        if bit(24) { causes.push( format!("Unaligned access at PC {}", site) ); }
+        if bit(25) { causes.push( format!("Division by zero at PC {}", site) ); }
+
+        // Escalations to HardFault.
+        if (registers.hfsr & (1 << 1)) != 0 { causes.push( String::from("Bus fault reading the vector table on exception entry") ); }
+        if (registers.hfsr & (1 << 30)) != 0 { causes.push( String::from("Escalated to HardFault, the configurable fault was disabled or could not be taken") ); }
+        if (registers.hfsr & (1 << 31)) != 0 { causes.push( String::from("Debug event while the debugger was disabled") ); }
+
+        if registers.afsr != 0 {
+            causes.push( format!("Implementation defined auxiliary fault status 0x{:08X}", registers.afsr) );
+        }
+
+        if causes.is_empty() {
+            causes.push( format!("{} at PC {} without fault status bits set", self.name(), site) );
+        }
+
+        causes
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fault(cfsr: u32, hfsr: u32, access: Option<Access>) -> Fault {
+        Fault {
+            exception: 3,
+            registers: Some( FaultRegisters { cfsr, hfsr, mmfar: 0x2000_0000, bfar: 0x4000_1000, afsr: 0 } ),
+            armv6m: false,
+            frame: None,
+            pc: 0x0800_011C,
+            access,
+            function: Some( String::from("foo::bar+0x1C") ),
+            source: Some( (String::from("src/foo.rs"), Some(42)) ),
+        }
+    }
+
+    #[test]
+    fn access() {
+        // LDR r0, [r1] and STR r0, [r1].
+        assert_eq!(Access::of(0x6808), Some(Access::Read));
+        assert_eq!(Access::of(0x6008), Some(Access::Write));
+
+        // STRB r0, [r1, r2] and LDRSH r0, [r1, r2].
+        assert_eq!(Access::of(0x5488), Some(Access::Write));
+        assert_eq!(Access::of(0x5E88), Some(Access::Read));
+
+        // PUSH {r7, lr} and POP {r7, pc}.
+        assert_eq!(Access::of(0xB580), Some(Access::Write));
+        assert_eq!(Access::of(0xBD80), Some(Access::Read));
+
+        // LDR.W r0, [r1, #4] and STR.W r0, [r1, #4].
+        assert_eq!(Access::of(0xF8D1), Some(Access::Read));
+        assert_eq!(Access::of(0xF8C1), Some(Access::Write));
+
+        // BX lr and BL.
+        assert_eq!(Access::of(0x4770), None);
+        assert_eq!(Access::of(0xF000), None);
+    }
+
+    #[test]
+    fn explain() {
+        // Forced precise bus fault with a valid address.
+        let f = fault((1 << 9) | (1 << 15), 1 << 30, Some(Access::Read));
+
+        assert_eq!(f.explain(), vec![
+            String::from("Precise bus fault reading 0x40001000 at PC foo::bar+0x1C (0x0800011C)"),
+            String::from("Escalated to HardFault, the configurable fault was disabled or could not be taken"),
+        ]);
+
+        assert_eq!(f.location().as_deref(), Some("src/foo.rs:42"));
+
+        // MPU violation without a valid address or a known direction.
+        assert_eq!(fault(1 << 1, 0, None).explain(), vec![ String::from("MPU violation accessing an unknown address at PC foo::bar+0x1C (0x0800011C)") ]);
+
+        assert_eq!(fault(1 << 25, 0, None).explain(), vec![ String::from("Division by zero at PC foo::bar+0x1C (0x0800011C)") ]);
+        assert_eq!(fault(0, 0, None).explain(), vec![ String::from("HardFault at PC foo::bar+0x1C (0x0800011C) without fault status bits set") ]);
+
+        // ARMv6-M has no fault status registers.
+        let f = Fault { registers: None, armv6m: true, ..fault(0, 0, None) };
+        assert_eq!(f.explain().len(), 1);
+        assert!(f.explain()[0].starts_with("HardFault at PC foo::bar+0x1C"));
+    }
+}
@@ -0,0 +1,162 @@
+//! Flash programming of ELF images.
+//! Progress is streamed through a `FlashHandle` while the command runs.
+
+
+
+use crate::elf::Segment;
+
+use probe_rs::flashing::ProgressEvent;
+
+use std::sync::{
+    Arc,
+    atomic::{ AtomicBool, Ordering },
+};
+
+use tokio::sync::mpsc::{
+    self, UnboundedReceiver, UnboundedSender,
+};
+
+
+
+/// Size of the blocks the image is verified in.
+/// Cancellation is checked between blocks.
+pub(super) const FLASHCHUNK: u32 = 0x10000;
+
+
+
+/// Progress of a flash operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashEvent {
+    /// The operation started with the given number of bytes to program.
+    Started(usize),
+
+    /// The erase of the flash started.
+    Erasing,
+
+    /// A sector of the given size was erased.
+    Erased(u32),
+
+    /// The programming of the flash started.
+    Programming,
+
+    /// A page of the given size was programmed.
+    Programmed(u32),
+
+    /// The verification of the image started.
+    Verifying,
+
+    /// The given number of bytes were verified.
+    Verified(u32),
+}
+
+
+
+/// Handle to report the progress of and cancel a flash operation.
+#[derive(Debug, Clone)]
+pub struct FlashHandle {
+    /// Progress channel.
+    events: UnboundedSender<FlashEvent>,
+
+    /// Cancellation flag.
+    cancel: Arc<AtomicBool>,
+}
+
+impl FlashHandle {
+    /// Creates a new handle and the receiving end of its progress channel.
+    pub fn new() -> (Self, UnboundedReceiver<FlashEvent>) {
+        let (events, rx) = mpsc::unbounded_channel();
+
+        (FlashHandle { events, cancel: Arc::new( AtomicBool::new(false) ) }, rx)
+    }
+
+    /// Requests the cancellation of the operation.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the operation was cancelled.
+    pub(super) fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Reports progress. A closed channel is not an error, the GUI may have moved on.
+    pub(super) fn send(&self, event: FlashEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Reports the progress events of `probe-rs`.
+    pub(super) fn progress(&self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::StartedErasing => self.send( FlashEvent::Erasing ),
+            ProgressEvent::SectorErased { size, .. } => self.send( FlashEvent::Erased(size as u32) ),
+            ProgressEvent::StartedProgramming => self.send( FlashEvent::Programming ),
+            ProgressEvent::PageProgrammed { size, .. } => self.send( FlashEvent::Programmed(size as u32) ),
+            _ => (),
+        }
+    }
+}
+
+
+
+/// Splits the segments in blocks that do not cross `FLASHCHUNK` boundaries.
+pub(super) fn chunks(segments: &[Segment]) -> Vec<(u32, &[u8])> {
+    let mut out = Vec::new();
+
+    for segment in segments {
+        let mut address = segment.address;
+        let mut data = &segment.data[..];
+
+        while data.len() > 0 {
+            let space = (FLASHCHUNK - (address % FLASHCHUNK)) as usize;
+            let (block, rest) = data.split_at( space.min(data.len()) );
+
+            out.push( (address, block) );
+
+            address = address.wrapping_add(block.len() as u32);
+            data = rest;
+        }
+    }
+
+    out
+}
+
+
+
+/// A range of target memory that does not match the ELF image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Start address of the range.
+    pub address: u32,
+
+    /// Size of the range in bytes.
+    pub size: u32,
+
+    /// Name of the section that owns the range.
+    pub section: Option<String>,
+}
+
+impl core::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.section {
+            Some(name) => write!(f, "0x{:08X} - 0x{:08X} ({} bytes) in {}", self.address, self.address.wrapping_add(self.size), self.size, name),
+            _ => write!(f, "0x{:08X} - 0x{:08X} ({} bytes)", self.address, self.address.wrapping_add(self.size), self.size),
+        }
+    }
+}
+
+
+
+/// Appends the ranges where the read back data differs from the expected data.
+/// Ranges contiguous with the last one are merged into it.
+pub(super) fn compare(address: u32, expected: &[u8], actual: &[u8], out: &mut Vec<(u32, u32)>) {
+    for (i, (e, a)) in expected.iter().zip(actual.iter()).enumerate() {
+        if e == a { continue }
+
+        let current = address + i as u32;
+
+        match out.last_mut() {
+            Some((start, size)) if start.wrapping_add(*size) == current => *size += 1,
+            _ => out.push( (current, 1) ),
+        }
+    }
+}
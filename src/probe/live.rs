@@ -0,0 +1,73 @@
+//! Live sampling of target memory.
+//! Reads a list of typed variables at a fixed rate while the core runs, through
+//! the background memory access of the debug port.
+
+
+
+use std::time::{ Duration, Instant };
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{ Datatype, Error, Value };
+
+
+
+/// Channel to send the samples of the live watch.
+pub type LiveSender = UnboundedSender<LiveSample>;
+
+
+
+/// A sample of all the watched variables.
+#[derive(Debug, Clone)]
+pub struct LiveSample {
+    /// Time since the start of the sampling.
+    pub time: Duration,
+
+    /// Value of each variable, in the order of the watch list.
+    pub values: Vec<Result<Value, Error>>,
+}
+
+
+
+/// Live watch of the currently selected core.
+pub(super) struct LiveWatch {
+    /// Watched variables as (address, datatype) pairs.
+    pub(super) vars: Vec<(u32, Datatype)>,
+
+    /// Sampling period.
+    period: Duration,
+
+    /// Start of the sampling.
+    start: Instant,
+
+    /// Time of the next sample.
+    next: Instant,
+
+    /// Channel of the samples.
+    pub(super) events: LiveSender,
+}
+
+impl LiveWatch {
+    /// Creates a live watch that samples the variables with the given period.
+    pub(super) fn new(vars: Vec<(u32, Datatype)>, period: Duration, events: LiveSender) -> Self {
+        let start = Instant::now();
+
+        LiveWatch { vars, period, start, next: start, events }
+    }
+
+    /// Returns the time since the start of the sampling if a sample is due.
+    /// Missed samples are skipped instead of sampled in a burst.
+    pub(super) fn due(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+
+        if now < self.next {
+            return None;
+        }
+
+        while self.next <= now {
+            self.next += self.period;
+        }
+
+        Some( now - self.start )
+    }
+}
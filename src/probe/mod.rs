@@ -2,173 +2,1690 @@
 
 
 
+pub mod backend;
+pub mod cmds;
+pub mod coredump;
+pub mod cores;
+pub mod decode;
+pub mod fault;
+pub mod flash;
+pub mod live;
+pub mod register;
+pub mod rtt;
+pub mod semihosting;
+pub mod trace;
+pub mod unwind;
+pub mod watchpoint;
+
+#[cfg(test)]
+mod tests;
+
+
+
 use probe_rs::{
-    Core, DebugProbeInfo, Probe, Session,
+    CoreType, DebugProbeInfo,
 
     CoreStatus, Error as ProbeError, HaltReason,
 
-    MemoryInterface,
+    config::TargetSelector,
+};
+
+use crate::elf::ElfFile;
+
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{
+    sync::{
+        mpsc, oneshot,
+    },
+
+    task::{ JoinError, JoinHandle },
+};
+
+use tracing::{
+    debug, error, info, warn,
+};
+
+
+
+pub use self::backend::{ Backend, BackendError, CoreInterface };
+pub use self::cmds::{ Command, Response };
+pub use self::coredump::CoreDump;
+pub use self::cores::CoreInfo;
+pub use self::decode::{ Datatype, Endianness, Value };
+pub use self::fault::{ Access, Fault, FaultRegisters, StackedFrame };
+pub use self::flash::{ FlashEvent, FlashHandle, Mismatch };
+pub use self::live::{ LiveSample, LiveSender };
+pub use self::register::{ Register, RegisterGroup };
+pub use self::rtt::{ RttChannel, RttControl, RttEvent, RttSender };
+pub use self::semihosting::{ SemihostEvent, SemihostSender, Semihosting };
+pub use self::trace::{ ExceptionAction, TraceConfig, TracePacket, TraceSender };
+pub use self::unwind::{ Frame, FrameKind };
+pub use self::watchpoint::{ WatchKind, Watchpoint };
+
+
+
+/// Channel to send commands to an `OpenProbe`.
+pub type CommandSender = mpsc::UnboundedSender<(Command, oneshot::Sender<Response>)>;
+
+/// Flash operation running on a blocking thread. Returns the backend it owns and the result.
+type FlashTask = JoinHandle<(Box<dyn Backend>, Result<Response, Error>)>;
+
+/// Maximum time to wait for the core to halt.
+const HALTTIMEOUT: Duration = Duration::from_millis(500);
+
+/// Period of the polling of the RTT up channels, the semihosting requests, the SWO capture and the live watch.
+/// It is also the shortest sampling period of the live watch.
+const POLLPERIOD: Duration = Duration::from_millis(10);
+
+
+/// Asynchronous manager of a probe.
+pub struct OpenProbe {
+    /// Target backend.
+    inner: Box<dyn Backend>,
+
+    /// A receiver of commands and response channels.
+    cmds: mpsc::UnboundedReceiver<(Command, oneshot::Sender<Response>)>,
+
+    /// Currently selected core.
+    core: usize,
+
+    /// Byte order of the target.
+    endianness: Endianness,
+
+    /// Hardware breakpoints set in the target as (core, address) pairs.
+    breakpoints: Vec<(usize, u32)>,
+
+    /// Watchpoints set in the target as (core, watchpoint) pairs.
+    watchpoints: Vec<(usize, Watchpoint)>,
+
+    /// Attached RTT control block and the channel of its events.
+    rtt: Option<(RttControl, RttSender)>,
+
+    /// Host of the semihosting requests of the current core.
+    semihosting: Option<Semihosting>,
+
+    /// Decoder of the SWO capture and the channel of its packets.
+    trace: Option<(trace::Decoder, TraceSender)>,
+
+    /// Variables sampled while the core runs.
+    live: Option<live::LiveWatch>,
+
+    /// Current flash operation and the channel of its response.
+    flashing: Option<(FlashTask, oneshot::Sender<Response>)>,
+}
+
+impl OpenProbe {
+    /// Creates a new open probe from the given `DebugProbeInfo`.
+    pub fn create(info: DebugProbeInfo, target: impl Into<TargetSelector>, endianness: Endianness) -> Result<(Self, CommandSender), ProbeError> {
+        // Open the probe.
+        let probe = info.open()?;
+
+        // Create a session.
+        let session = probe.attach(target)?;
+
+        Ok( Self::new( Box::new( backend::ProbeRsBackend::new(session) ), endianness ) )
+    }
+
+    /// Creates a read-only open probe over a saved core dump.
+    pub fn offline(dump: CoreDump) -> (Self, CommandSender) {
+        let endianness = dump.endianness;
+
+        Self::new( Box::new( backend::DumpBackend::new(dump) ), endianness )
+    }
+
+    /// Creates a new open probe over the given backend.
+    pub fn new(inner: Box<dyn Backend>, endianness: Endianness) -> (Self, CommandSender) {
+        // Create the channels.
+        let (tx, cmds) = mpsc::unbounded_channel();
+
+        // Create the open probe.
+        let openprobe = OpenProbe { inner, cmds, core: 0, endianness, breakpoints: Vec::new(), watchpoints: Vec::new(), rtt: None, semihosting: None, trace: None, live: None, flashing: None };
+
+        (openprobe, tx)
+    }
+
+    /// Runs in a loop until the command channel is closed.
+    pub async fn run(&mut self) {
+        info!(origin="probe", "Probe command loop started");
+
+        let mut poll = tokio::time::interval(POLLPERIOD);
+
+        loop {
+            tokio::select! {
+                cmd = self.cmds.recv() => match cmd {
+                    // All senders were dropped, shut down the probe.
+                    None => break,
+
+                    // The target belongs to the flash operation until it finishes.
+                    Some((_, channel)) if self.flashing.is_some() => Self::respond(channel, Response::Error( Error::Flashing )),
+
+                    Some((Command::Flash(elf, handle), channel)) => self.flash(elf, handle, channel),
+
+                    Some((cmd, channel)) => {
+                        let response = self.execute(cmd);
+                        Self::respond(channel, response);
+                    },
+                },
+
+                // Take back the target from the finished flash operation.
+                result = Self::flashed(&mut self.flashing), if self.flashing.is_some() => self.flashdone(result),
+
+                // Poll the RTT up channels, the semihosting requests, the SWO capture and the live watch in between commands.
+                _ = poll.tick(), if self.flashing.is_none() && (self.rtt.is_some() || self.semihosting.is_some() || self.trace.is_some() || self.live.is_some()) => {
+                    self.pollrtt();
+                    self.pollsemihosting();
+                    self.polltrace();
+                    self.polllive();
+                },
+            }
+        }
+
+        info!(origin="probe", "Command channel closed, probe command loop finished");
+    }
+
+    /// Executes a single command and builds its response.
+    fn execute(&mut self, cmd: Command) -> Response {
+        let response = match cmd {
+            Command::ReadI8(address)  => self.readi8(address).map(Response::I8),
+            Command::ReadU8(address)  => self.readu8(address).map(Response::U8),
+            Command::ReadI16(address) => self.readi16(address).map(Response::I16),
+            Command::ReadU16(address) => self.readu16(address).map(Response::U16),
+            Command::ReadI32(address) => self.readi32(address).map(Response::I32),
+            Command::ReadU32(address) => self.readu32(address).map(Response::U32),
+            Command::ReadI64(address) => self.readi64(address).map(Response::I64),
+            Command::ReadU64(address) => self.readu64(address).map(Response::U64),
+
+            Command::Read(datatype, address) => self.read(datatype, address).map(Response::Value),
+            Command::ReadWords(addresses) => self.readwords(addresses).map(Response::Words),
+
+            Command::Halt         => self.halt().map(Response::Status),
+            Command::Run          => self.resume().map(Response::Status),
+            Command::Reset        => self.reset().map(Response::Status),
+            Command::ResetAndHalt => self.resethalt().map(Response::Status),
+            Command::Step         => self.step().map(Response::Status),
+            Command::Status       => self.status().map(Response::Status),
+
+            Command::Cores            => Ok( self.cores() ),
+            Command::SelectCore(core) => self.selectcore(core),
+
+            Command::SetBreakpoint(address)   => self.setbreakpoint(address),
+            Command::ClearBreakpoint(address) => self.clearbreakpoint(address),
+            Command::ClearAllBreakpoints      => self.clearbreakpoints(),
+            Command::Breakpoints              => self.breakpoints(),
+
+            Command::SetWatchpoint(address, size, kind) => self.setwatchpoint(address, size, kind),
+            Command::ClearWatchpoint(comparator)        => self.clearwatchpoint(comparator),
+            Command::Watchpoints                        => self.watchpoints(),
+            Command::WatchpointHit                      => self.watchpointhit(),
+
+            Command::Registers => self.registers().map(Response::Registers),
+            Command::WriteRegister(address, value) => self.wrregister(address, value).map(Response::Register),
+
+            Command::WriteI8(address, value)  => self.write(address, &[value as u8]),
+            Command::WriteU8(address, value)  => self.write(address, &[value]),
+            Command::WriteI16(address, value) => self.write(address, &self.endianness.encode16(value as u16)),
+            Command::WriteU16(address, value) => self.write(address, &self.endianness.encode16(value)),
+            Command::WriteI32(address, value) => self.write(address, &self.endianness.encode32(value as u32)),
+            Command::WriteU32(address, value) => self.write(address, &self.endianness.encode32(value)),
+            Command::WriteI64(address, value) => self.write(address, &self.endianness.encode64(value as u64)),
+            Command::WriteU64(address, value) => self.write(address, &self.endianness.encode64(value)),
+            Command::WriteF32(address, value) => self.write(address, &self.endianness.encode32(value.to_bits())),
+            Command::WriteF64(address, value) => self.write(address, &self.endianness.encode64(value.to_bits())),
+
+            Command::WriteRange(address, data) => self.write(address, &data),
+
+            Command::Modify(address, keep, value) => self.modify(address, keep, value).map(|word| Response::Words( word.map(|w| (address & !3, w)).into_iter().collect() )),
+
+            // Flash operations are started by the command loop.
+            Command::Flash(..) => unreachable!(),
+            Command::Verify(elf) => Self::verify(&mut *self.inner, self.core, &elf, None).map(Response::Verified),
+
+            Command::Snapshot(ranges) => self.snapshot(ranges),
+            Command::CoreDump => self.coredump().map(Response::CoreDump),
+            Command::Backtrace(elf) => self.backtrace(&elf).map(Response::Backtrace),
+            Command::Fault(elf) => self.fault(elf.as_deref()).map(Response::Fault),
+
+            Command::RttAttach(address, events) => self.rttattach(address, events),
+            Command::RttWrite(channel, data)    => self.rttwrite(channel, &data),
+
+            Command::Semihosting(host) => {
+                info!(origin="probe", "Semihosting {}", if host.is_some() { "enabled" } else { "disabled" });
+
+                self.semihosting = host.map(|(root, events)| Semihosting::new(root, events));
+                Ok( Response::Semihosting(self.semihosting.is_some()) )
+            },
+
+            Command::TraceStart(config, events) => self.tracestart(config, events),
+            Command::TraceStop => self.tracestop(),
+
+            Command::LiveStart(vars, period, events) => self.livestart(vars, period, events),
+
+            Command::LiveStop => {
+                if self.live.take().is_some() {
+                    info!(origin="probe", "Live watch stopped");
+                }
+
+                Ok( Response::Live(false) )
+            },
+        };
+
+        match response {
+            Err(e) => Response::Error(e),
+            Ok(r) => r,
+        }
+    }
+
+    /// Sends the response of a command.
+    fn respond(channel: oneshot::Sender<Response>, response: Response) {
+        match channel.send(response) {
+            Err(_) => warn!(origin="probe", "Response channel closed before the response was sent"),
+            _ => (),
+        }
+    }
+
+    /// Starts programming the ELF on a blocking thread, which owns the backend until it finishes.
+    /// The progress is reported through the handle, the response is sent once the operation finishes.
+    fn flash(&mut self, elf: Arc<ElfFile>, handle: FlashHandle, channel: oneshot::Sender<Response>) {
+        let mut inner = std::mem::replace(&mut self.inner, Box::new( backend::Lent ));
+        let core = self.core;
+
+        let task = tokio::task::spawn_blocking(move || {
+            let result = Self::program(&mut *inner, core, &elf, &handle);
+            (inner, result)
+        });
+
+        self.flashing = Some( (task, channel) );
+    }
+
+    /// Waits for the current flash operation to finish.
+    async fn flashed(flashing: &mut Option<(FlashTask, oneshot::Sender<Response>)>) -> Result<(Box<dyn Backend>, Result<Response, Error>), JoinError> {
+        match flashing {
+            Some((task, _)) => task.await,
+            _ => std::future::pending().await,
+        }
+    }
+
+    /// Takes back the backend of the finished flash operation and sends its response.
+    fn flashdone(&mut self, result: Result<(Box<dyn Backend>, Result<Response, Error>), JoinError>) {
+        let channel = match self.flashing.take() {
+            Some((_, channel)) => channel,
+            _ => return,
+        };
+
+        let response = match result {
+            Ok((inner, response)) => {
+                self.inner = inner;
+                response
+            },
+
+            // The backend was lost with the thread.
+            Err(e) => {
+                error!(origin="probe", "The flash operation did not finish, the target is no longer available: {}", e);
+                Err( Error::FlashFailed( e.to_string() ) )
+            },
+        };
+
+        Self::respond(channel, match response {
+            Err(e) => Response::Error(e),
+            Ok(r) => r,
+        });
+    }
+
+    /// Programs the loadable segments of the ELF into the target and verifies them.
+    fn program(inner: &mut dyn Backend, core: usize, elf: &ElfFile, handle: &FlashHandle) -> Result<Response, Error> {
+        let total = elf.loadsize();
+
+        if total == 0 {
+            error!(origin="probe", "ELF file {} has no loadable segments", elf.path().display());
+            return Err( Error::NothingToFlash );
+        }
+
+        info!(origin="probe", "Flashing {} bytes from {}", total, elf.path().display());
+
+        handle.send( FlashEvent::Started(total) );
+
+        if handle.cancelled() {
+            warn!(origin="probe", "Flashing cancelled before programming");
+            return Err( Error::FlashCancelled );
+        }
+
+        // Program the whole image in one operation.
+        if let Err(e) = inner.flash(elf.segments(), handle) {
+            error!(origin="probe", "Failed to flash {}: {}", elf.path().display(), e);
+            return Err( Error::FlashFailed(e.0) );
+        }
+
+        // Read back the image.
+        let mismatches = Self::verify(inner, core, elf, Some(handle))?;
+
+        match mismatches.len() {
+            0 => info!(origin="probe", "Flashed and verified {} bytes", total),
+            n => error!(origin="probe", "Flashed {} bytes, verification found {} mismatching ranges", total, n),
+        }
+
+        Ok( Response::Flashed(total, mismatches) )
+    }
+
+    /// Reads back the loadable segments of the ELF and compares them with the file contents.
+    fn verify(inner: &mut dyn Backend, index: usize, elf: &ElfFile, handle: Option<&FlashHandle>) -> Result<Vec<Mismatch>, Error> {
+        if let Some(handle) = handle {
+            handle.send( FlashEvent::Verifying );
+        }
+
+        let mut ranges = Vec::new();
+
+        for (address, data) in flash::chunks(elf.segments()) {
+            if handle.map(|h| h.cancelled()).unwrap_or(false) {
+                warn!(origin="probe", "Verification cancelled before block at 0x{:08X}", address);
+                return Err( Error::FlashCancelled );
+            }
+
+            let mut core = Self::attach(inner, index)?;
+
+            let end = Self::endaddr(address, data.len())?;
+            let readback = Self::rdrange(&mut *core, address, end)?;
+
+            flash::compare(address, data, &readback, &mut ranges);
+
+            if let Some(handle) = handle {
+                handle.send( FlashEvent::Verified(data.len() as u32) );
+            }
+        }
+
+        let mismatches: Vec<Mismatch> = ranges.into_iter()
+            .map(|(address, size)| Mismatch { address, size, section: elf.section(address).map(String::from) })
+            .collect();
+
+        for mismatch in mismatches.iter() {
+            warn!(origin="probe", "Verification mismatch at {}", mismatch);
+        }
+
+        Ok( mismatches )
+    }
+
+    /// Reads the given `(start, end)` ranges, or all the RAM regions of the target, from the halted core.
+    fn snapshot(&mut self, ranges: Option<Vec<(u32, u32)>>) -> Result<Response, Error> {
+        let ranges = match ranges {
+            Some(ranges) => ranges,
+            _ => self.inner.ram().into_iter()
+                .map(|(start, size)| (start, start.saturating_add(size)))
+                .collect(),
+        };
+
+        if ranges.len() == 0 {
+            error!(origin="probe", "The target has no RAM regions to snapshot");
+            return Err( Error::NoRam );
+        }
+
+        let mut core = self.getcore()?;
+
+        // A running core would change the memory during the snapshot.
+        Self::corehalted(&mut *core)?;
+
+        let mut regions = Vec::with_capacity(ranges.len());
+
+        for (start, end) in ranges {
+            regions.push( (start, Self::rdrange(&mut *core, start, end)?) );
+        }
+
+        info!(origin="probe", "Captured a snapshot of {} bytes in {} regions", regions.iter().map(|(_, d)| d.len()).sum::<usize>(), regions.len());
+
+        Ok( Response::Snapshot(regions) )
+    }
+
+    /// Saves the registers of the halted core, the RAM regions of the target and its fault status registers.
+    /// The regions that cannot be read are left out of the dump.
+    fn coredump(&mut self) -> Result<CoreDump, Error> {
+        let coretype = match self.inner.list_cores().into_iter().find(|(n, _)| *n == self.core) {
+            Some((_, coretype)) => coretype,
+            _ => return Err( Error::CoreNotFound(self.core) ),
+        };
+
+        let registers = self.registers()?;
+
+        let ram = self.inner.ram();
+        let endianness = self.endianness;
+        let index = self.core;
+
+        let mut core = self.getcore()?;
+        let pcaddress = core.pcaddress();
+
+        let mut regions = Vec::with_capacity(ram.len());
+
+        for (start, size) in ram {
+            match Self::rdrange(&mut *core, start, start.saturating_add(size)) {
+                Ok(data) => regions.push( (start, data) ),
+                Err(e) => warn!(origin="probe", "The core dump skips the RAM region at 0x{:08X}: {}", start, e),
+            }
+        }
+
+        // The fault status registers let the post-mortem session explain a fault.
+        if coretype != CoreType::Armv6m {
+            let (start, end) = fault::FAULTREGS;
+
+            match Self::rdrange(&mut *core, start, end) {
+                Ok(data) => regions.push( (start, data) ),
+                Err(e) => warn!(origin="probe", "The core dump skips the fault status registers: {}", e),
+            }
+        }
+
+        info!(origin="probe", "Dumped {} registers and {} memory regions of core {}", registers.len(), regions.len(), index);
+
+        Ok( CoreDump { coretype, endianness, pcaddress, registers, regions, elf: None } )
+    }
+
+    /// Unwinds the call stack of the halted core.
+    fn backtrace(&mut self, elf: &ElfFile) -> Result<Vec<Frame>, Error> {
+        // Only the ARM exception model is unwound.
+        self.coretype()?;
+
+        let index = self.core;
+
+        let mut core = self.getcore()?;
+
+        Self::corehalted(&mut *core)?;
+
+        let mut r = [None; 16];
+
+        for (n, register) in r.iter_mut().enumerate() {
+            *register = Some( Self::rdregister(&mut *core, n as u16)? );
+        }
+
+        // Threads may run on the process stack.
+        let registers = unwind::Registers {
+            r,
+            xpsr: Self::rdregister(&mut *core, 16)?,
+            psp: core.read_core_reg(18).ok(),
+        };
+
+        // Reads outside of the target memory end the unwinding without errors.
+        let frames = unwind::backtrace(elf, registers, &mut |address| core.read_word_32(address).ok());
+
+        info!(origin="probe", "Unwound {} frames of core {}", frames.len(), index);
+
+        Ok( frames )
+    }
+
+    /// Analyzes the fault handled by the halted core.
+    /// Returns `None` if the core is not in a fault handler. The stacked frame is
+    /// found through `EXC_RETURN` in LR at the entry of the handler. Once the handler
+    /// has called other functions the faulting instruction is found by unwinding.
+    fn fault(&mut self, elf: Option<&ElfFile>) -> Result<Option<Fault>, Error> {
+        let armv6m = self.coretype()? == CoreType::Armv6m;
+
+        let index = self.core;
+
+        let mut core = self.getcore()?;
+
+        let status = Self::corestatus(&mut *core)?;
+
+        Self::corehalted(&mut *core)?;
+
+        // The active exception is in IPSR.
+        let xpsr = Self::rdregister(&mut *core, 16)?;
+        let exception = (xpsr & 0x1FF) as u16;
+
+        let vectorcatch = matches!(status, CoreStatus::Halted(HaltReason::Exception)) && (exception != 0);
+
+        if !Fault::isfault(exception) && !vectorcatch {
+            return Ok( None );
+        }
+
+        // ARMv6-M has no fault status registers.
+        let registers = match armv6m {
+            true => None,
+            _ => {
+                let mut read = |address| Self::rdword32(&mut *core, address).ok();
+
+                match (read(fault::CFSR), read(fault::HFSR), read(fault::MMFAR), read(fault::BFAR), read(fault::AFSR)) {
+                    (Some(cfsr), Some(hfsr), Some(mmfar), Some(bfar), Some(afsr)) => Some( FaultRegisters { cfsr, hfsr, mmfar, bfar, afsr } ),
+                    _ => {
+                        warn!(origin="probe", "Could not read the fault status registers of core {}", index);
+                        None
+                    },
+                }
+            },
+        };
+
+        let sp = Self::rdregister(&mut *core, 13)?;
+        let lr = Self::rdregister(&mut *core, 14)?;
+        let psp = core.read_core_reg(18).ok();
+
+        // At the entry of the handler LR selects the stack of the frame.
+        let frame = match unwind::excreturn(lr) {
+            true => {
+                let address = match lr & (1 << 2) {
+                    0 => Some(sp),
+                    _ => psp,
+                };
+
+                address.and_then(|address| {
+                    let mut words = [0u32; 8];
+
+                    for (i, word) in words.iter_mut().enumerate() {
+                        *word = core.read_word_32(address + (i as u32 * 4)).ok()?;
+                    }
+
+                    Some( StackedFrame::new(address, words) )
+                })
+            },
+            _ => None,
+        };
+
+        let pc = match (frame, elf) {
+            (Some(frame), _) => Some(frame.pc & !1),
+
+            // Deeper in the handler the interrupted frame is found by unwinding.
+            (None, Some(elf)) => {
+                let mut r = [None; 16];
+
+                for (n, register) in r.iter_mut().enumerate() {
+                    *register = core.read_core_reg(n as u16).ok();
+                }
+
+                let registers = unwind::Registers { r, xpsr, psp };
+
+                unwind::backtrace(elf, registers, &mut |address| core.read_word_32(address).ok())
+                    .into_iter()
+                    .find(|frame| matches!(frame.kind, FrameKind::Exception(_)))
+                    .map(|frame| frame.pc)
+            },
+
+            _ => None,
+        };
+
+        let pc = match pc {
+            Some(pc) => pc,
+            _ => {
+                warn!(origin="probe", "Could not find the faulting instruction of core {}", index);
+                Self::rdregister(&mut *core, 15)? & !1
+            },
+        };
+
+        // Instructions are always little endian. The faulting instruction may be in unreadable memory.
+        let access = match Self::rdrange(&mut *core, pc, pc.saturating_add(2)) {
+            Ok(bytes) if bytes.len() == 2 => Access::of( u16::from_le_bytes([bytes[0], bytes[1]]) ),
+            _ => None,
+        };
+
+        let symbolizer = elf.map(unwind::Symbolizer::new);
+
+        let function = symbolizer.as_ref().and_then(|s| s.function(pc, pc));
+        let source = symbolizer.as_ref().and_then(|s| s.location(pc));
+
+        let fault = Fault { exception, registers, armv6m, frame, pc, access, function, source };
+
+        info!(origin="probe", "Core {} halted in {} at 0x{:08X}", index, fault.name(), pc);
+
+        Ok( Some( fault ) )
+    }
+
+    /// Locates the RTT control block and starts polling its up channels.
+    /// Without an address the RAM of the target is scanned for the control block.
+    fn rttattach(&mut self, address: Option<u32>, events: RttSender) -> Result<Response, Error> {
+        let address = match address {
+            Some(address) => address,
+            _ => self.rttscan()?,
+        };
+
+        let control = self.rttcontrol(address)?;
+
+        info!(origin="probe", "Attached to RTT control block at 0x{:08X} with {} up and {} down channels", address, control.up.len(), control.down.len());
+
+        self.rtt = Some( (control.clone(), events) );
+
+        Ok( Response::Rtt(control) )
+    }
+
+    /// Scans the RAM of the target for the RTT control block.
+    fn rttscan(&mut self) -> Result<u32, Error> {
+        let ram = self.inner.ram();
+
+        let mut core = self.getcore()?;
+
+        for (start, size) in ram {
+            let end = start.saturating_add(size);
+            let mut address = start;
+
+            while address < end {
+                // Overlap the blocks to find an identifier across their boundary.
+                let e = address.saturating_add(rtt::RTT_SCANBLOCK + rtt::RTT_ID.len() as u32).min(end);
+                let data = Self::rdrange(&mut *core, address, e)?;
+
+                if let Some(offset) = rtt::find(&data) {
+                    return Ok( address + offset as u32 );
+                }
+
+                address = match address.checked_add(rtt::RTT_SCANBLOCK) {
+                    Some(a) => a,
+                    _ => break,
+                };
+            }
+        }
+
+        error!(origin="probe", "RTT control block was not found in the RAM of the target");
+        Err( Error::RttNotFound )
+    }
+
+    /// Reads the RTT control block at the given address.
+    fn rttcontrol(&mut self, address: u32) -> Result<RttControl, Error> {
+        let endianness = self.endianness;
+
+        let mut core = self.getcore()?;
+
+        // Read and validate the header.
+        let header = Self::rdrange(&mut *core, address, Self::endaddr(address, rtt::RTT_HEADER as usize)?)?;
+
+        if header[..rtt::RTT_ID.len()] != rtt::RTT_ID[..] {
+            error!(origin="probe", "No RTT control block at 0x{:08X}", address);
+            return Err( Error::RttNotFound );
+        }
+
+        let nup   = endianness.u32(&header[16..])?;
+        let ndown = endianness.u32(&header[20..])?;
+
+        if (nup > rtt::RTT_MAXCHANNELS) || (ndown > rtt::RTT_MAXCHANNELS) {
+            error!(origin="probe", "RTT control block at 0x{:08X} reports {} up and {} down channels", address, nup, ndown);
+            return Err( Error::RttInvalid(address) );
+        }
+
+        // Read the buffer descriptors.
+        let s = RttControl::updescriptor(address, 0);
+        let e = RttControl::downdescriptor(address, nup, ndown);
+        let descriptors = Self::rdrange(&mut *core, s, e)?;
+
+        let mut channels = Vec::new();
+
+        for (i, descriptor) in descriptors.chunks(rtt::RTT_DESCRIPTOR as usize).enumerate() {
+            let name   = endianness.u32(&descriptor[0..])?;
+            let buffer = endianness.u32(&descriptor[4..])?;
+            let size   = endianness.u32(&descriptor[8..])?;
+
+            let number = match (i as u32) < nup {
+                true => i,
+                _ => i - nup as usize,
+            };
+
+            // Unconfigured buffers are skipped.
+            if (buffer == 0) || (size == 0) {
+                continue;
+            }
+
+            // The name is optional, a failed read is not fatal.
+            let name = match name {
+                0 => None,
+                _ => Self::rdrange(&mut *core, name, name.saturating_add(rtt::RTT_MAXNAME)).ok()
+                    .and_then(|data| rtt::cstring(&data)),
+            };
+
+            channels.push( (i as u32 >= nup, RttChannel { number, name, descriptor: s + (i as u32 * rtt::RTT_DESCRIPTOR), buffer, size }) );
+        }
+
+        let (down, up): (Vec<_>, Vec<_>) = channels.into_iter().partition(|(down, _)| *down);
+
+        Ok( RttControl {
+            address,
+            up: up.into_iter().map(|(_, c)| c).collect(),
+            down: down.into_iter().map(|(_, c)| c).collect(),
+        })
+    }
+
+    /// Services a pending semihosting request of the current core.
+    /// Semihosting is disabled if the core cannot be accessed.
+    fn pollsemihosting(&mut self) {
+        let mut host = match self.semihosting.take() {
+            Some(host) => host,
+            _ => return,
+        };
+
+        match self.semihost(&mut host) {
+            Err(e) => error!(origin="probe", "Semihosting disabled: {}", e),
+            _ => self.semihosting = Some(host),
+        }
+    }
+
+    /// Services the semihosting request of the current core, if it is halted in one.
+    fn semihost(&mut self, host: &mut Semihosting) -> Result<(), Error> {
+        let (endianness, n) = (self.endianness, self.core);
+
+        let mut core = self.getcore()?;
+
+        match Self::corestatus(&mut *core)? {
+            CoreStatus::Halted(HaltReason::Breakpoint) => (),
+            _ => return Ok(()),
+        }
+
+        // Check for the semihosting breakpoint.
+        let pcaddress = core.pcaddress();
+        let pc = Self::rdregister(&mut *core, pcaddress)?;
+        let instruction = Self::rdrange(&mut *core, pc, Self::endaddr(pc, 2)?)?;
+
+        if endianness.u16(&instruction)? != semihosting::BKPT_SEMIHOSTING {
+            return Ok(());
+        }
+
+        let op    = Self::rdregister(&mut *core, 0)?;
+        let param = Self::rdregister(&mut *core, 1)?;
+
+        let outcome = host.service(&mut *core, endianness, op, param)?;
+
+        // Skip the breakpoint.
+        let result = match outcome {
+            semihosting::Outcome::Return(value) => core.write_core_reg(0, value),
+            _ => Ok(()),
+        }
+        .and_then(|_| core.write_core_reg(pcaddress, pc + 2));
+
+        if let Err(e) = result {
+            error!(origin="probe", "Could not complete the semihosting operation 0x{:02X}: {}", op, e);
+            return Err( Error::RegisterWriteFailed(pcaddress) );
+        }
+
+        match outcome {
+            semihosting::Outcome::Exit => Ok(()),
+
+            _ => match core.run() {
+                Err(e) => {
+                    error!(origin="probe", "Could not resume core {} after semihosting: {}", n, e);
+                    Err( Error::RunFailed )
+                },
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Configures the TPIU, ITM and DWT of the currently selected core and starts the SWO capture.
+    fn tracestart(&mut self, config: TraceConfig, events: TraceSender) -> Result<Response, Error> {
+        // The ITM is not available in ARMv6-M cores.
+        match self.coretype()? {
+            CoreType::Armv7m | CoreType::Armv7em | CoreType::Armv8m => (),
+            _ => return Err( Error::Unsupported ),
+        }
+
+        let prescaler = match config.prescaler() {
+            Some(prescaler) => prescaler,
+            _ => return Err( Error::InvalidTraceClock(config.clock, config.baud) ),
+        };
+
+        let n = self.core;
+
+        if let Err(e) = self.inner.setup_swo(n, config.clock, config.baud) {
+            error!(origin="probe", "Could not configure the SWO capture of core {}: {}", n, e);
+            return Err( Error::TraceFailed( e.to_string() ) );
+        }
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        // Enable the trace units.
+        let demcr = Self::rdword32(&mut *core, watchpoint::DEMCR)?;
+        Self::wrword32(&mut *core, watchpoint::DEMCR, demcr | watchpoint::DEMCR_TRCENA)?;
+
+        // Output the ITM stream through the SWO pin without the formatter.
+        Self::wrword32(&mut *core, trace::TPIU_ACPR, prescaler)?;
+        Self::wrword32(&mut *core, trace::TPIU_SPPR, trace::TPIU_SPPR_NRZ)?;
+        Self::wrword32(&mut *core, trace::TPIU_FFCR, trace::TPIU_FFCR_BYPASS)?;
+
+        // Configure the DWT packets.
+        let mut ctrl = Self::rdword32(&mut *core, watchpoint::DWT_CTRL)?;
+
+        ctrl &= !(trace::DWT_CTRL_EXCTRCENA | trace::DWT_CTRL_PCSAMPLENA | trace::DWT_CTRL_TAPS);
+        ctrl |= trace::DWT_CTRL_CYCCNTENA | trace::DWT_CTRL_SYNCTAP | trace::DWT_CTRL_POSTPRESET;
+
+        if config.exceptions { ctrl |= trace::DWT_CTRL_EXCTRCENA; }
+        if config.pcsampling { ctrl |= trace::DWT_CTRL_PCSAMPLENA; }
+
+        Self::wrword32(&mut *core, watchpoint::DWT_CTRL, ctrl)?;
+
+        // Enable the ITM and its stimulus ports, unprivileged code may write all of them.
+        Self::wrword32(&mut *core, trace::ITM_LAR, trace::ITM_LAR_KEY)?;
+        Self::wrword32(&mut *core, trace::ITM_TCR, trace::ITM_TCR_ENABLE)?;
+        Self::wrword32(&mut *core, trace::ITM_TER, config.ports)?;
+        Self::wrword32(&mut *core, trace::ITM_TPR, 0)?;
+
+        drop(core);
+
+        info!(origin="probe", "SWO trace of core {} started at {} baud", n, config.baud);
+
+        self.trace = Some( (trace::Decoder::new(), events) );
+
+        Ok( Response::Trace(true) )
+    }
+
+    /// Stops the SWO capture and disables the ITM and DWT packets of the currently selected core.
+    fn tracestop(&mut self) -> Result<Response, Error> {
+        if self.trace.take().is_none() {
+            return Ok( Response::Trace(false) );
+        }
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        let ctrl = Self::rdword32(&mut *core, watchpoint::DWT_CTRL)?;
+        Self::wrword32(&mut *core, watchpoint::DWT_CTRL, ctrl & !(trace::DWT_CTRL_EXCTRCENA | trace::DWT_CTRL_PCSAMPLENA))?;
+
+        Self::wrword32(&mut *core, trace::ITM_TCR, 0)?;
+
+        info!(origin="probe", "SWO trace stopped");
+
+        Ok( Response::Trace(false) )
+    }
+
+    /// Reads the SWO capture and sends its decoded packets to the session.
+    /// The capture is stopped if the probe cannot read it or nobody listens to it.
+    fn polltrace(&mut self) {
+        let (mut decoder, events) = match self.trace.take() {
+            Some(trace) => trace,
+            _ => return,
+        };
+
+        match self.inner.read_swo() {
+            Ok(data) => {
+                let packets = decoder.push(&data);
+
+                if !packets.is_empty() && events.send(packets).is_err() {
+                    info!(origin="probe", "Trace session closed by the host");
+                    return;
+                }
+
+                self.trace = Some( (decoder, events) );
+            },
+
+            Err(e) => error!(origin="probe", "SWO capture lost: {}", e),
+        }
+    }
+
+    /// Starts sampling the variables of the currently selected core while it runs.
+    /// Replaces the previous live watch.
+    fn livestart(&mut self, vars: Vec<(u32, Datatype)>, period: Duration, events: LiveSender) -> Result<Response, Error> {
+        self.live = None;
+
+        self.backgroundaccess()?;
+
+        for (address, datatype) in vars.iter() {
+            Self::endaddr(*address, datatype.size())?;
+        }
+
+        let period = period.max(POLLPERIOD);
+
+        info!(origin="probe", "Sampling {} variables of core {} every {} ms", vars.len(), self.core, period.as_millis());
+
+        self.live = Some( live::LiveWatch::new(vars, period, events) );
+
+        Ok( Response::Live(true) )
+    }
+
+    /// Checks that the currently selected core can read memory without halting.
+    /// ARM cores read through the access port of the bus while they run, RISC-V cores
+    /// read through the abstract commands of the debug module, which require a halt.
+    fn backgroundaccess(&mut self) -> Result<(), Error> {
+        match self.inner.list_cores().into_iter().find(|(n, _)| *n == self.core) {
+            Some((_, CoreType::Riscv)) => Err( Error::NoBackgroundAccess(self.core) ),
+            Some(_) => Ok(()),
+            _ => Err( Error::CoreNotFound(self.core) ),
+        }
+    }
+
+    /// Samples the watched variables if a sample is due.
+    /// The live watch is stopped if the core cannot be accessed or nobody listens to it.
+    fn polllive(&mut self) {
+        let mut watch = match self.live.take() {
+            Some(watch) => watch,
+            _ => return,
+        };
+
+        let time = match watch.due() {
+            Some(time) => time,
+            _ => {
+                self.live = Some(watch);
+                return;
+            },
+        };
+
+        let endianness = self.endianness;
+
+        let values = match self.getcore() {
+            Ok(mut core) => watch.vars.iter()
+                .map(|(address, datatype)| {
+                    let end = Self::endaddr(*address, datatype.size())?;
+                    let data = Self::rdrange(&mut *core, *address, end)?;
+
+                    decode::decode(*datatype, &data, endianness)
+                })
+                .collect(),
+
+            Err(e) => {
+                error!(origin="probe", "Live watch stopped: {}", e);
+                return;
+            },
+        };
+
+        match watch.events.send( LiveSample { time, values } ) {
+            Err(_) => info!(origin="probe", "Live watch closed by the host"),
+            _ => self.live = Some(watch),
+        }
+    }
+
+    /// Reads the pending data of the RTT up channels and sends it to the session.
+    /// The session is closed if the control block cannot be read or nobody listens to it.
+    fn pollrtt(&mut self) {
+        let (control, events) = match self.rtt.take() {
+            Some(rtt) => rtt,
+            _ => return,
+        };
+
+        match self.rttread(&control) {
+            Ok(data) => {
+                for (channel, bytes) in data {
+                    if events.send( RttEvent::Data(channel, bytes) ).is_err() {
+                        info!(origin="probe", "RTT session closed by the host");
+                        return;
+                    }
+                }
+
+                self.rtt = Some( (control, events) );
+            },
+
+            Err(e) => {
+                error!(origin="probe", "RTT session lost: {}", e);
+                let _ = events.send( RttEvent::Lost );
+            },
+        }
+    }
+
+    /// Reads the pending data of the RTT up channels and frees it in the target.
+    fn rttread(&mut self, control: &RttControl) -> Result<Vec<(usize, Vec<u8>)>, Error> {
+        let endianness = self.endianness;
+
+        let mut core = self.getcore()?;
+
+        let mut out = Vec::new();
+
+        for channel in control.up.iter() {
+            let offsets = Self::rdrange(&mut *core, channel.wroff(), channel.rdoff() + 4)?;
+            let write = endianness.u32(&offsets[0..])?;
+            let read  = endianness.u32(&offsets[4..])?;
+
+            if (write >= channel.size) || (read >= channel.size) {
+                error!(origin="probe", "RTT up channel {} has offsets outside of its buffer", channel.number);
+                return Err( Error::RttInvalid(control.address) );
+            }
+
+            let mut data = Vec::new();
+
+            for (offset, len) in channel.pending(read, write) {
+                let s = channel.buffer + offset;
+                data.extend( Self::rdrange(&mut *core, s, s + len)? );
+            }
+
+            if data.len() > 0 {
+                Self::wrrange(&mut *core, channel.rdoff(), &endianness.encode32(write))?;
+                out.push( (channel.number, data) );
+            }
+        }
+
+        Ok( out )
+    }
+
+    /// Writes as many bytes as fit into the given RTT down channel.
+    fn rttwrite(&mut self, number: usize, data: &[u8]) -> Result<Response, Error> {
+        let endianness = self.endianness;
+
+        let (address, channel) = match &self.rtt {
+            Some((control, _)) => match control.down.iter().find(|c| c.number == number) {
+                Some(channel) => (control.address, channel.clone()),
+                _ => {
+                    error!(origin="probe", "RTT down channel {} does not exist", number);
+                    return Err( Error::RttChannelNotFound(number) );
+                },
+            },
+
+            _ => {
+                error!(origin="probe", "Cannot write to RTT channel {} without an RTT session", number);
+                return Err( Error::RttNotAttached );
+            },
+        };
+
+        let mut core = self.getcore()?;
+
+        let offsets = Self::rdrange(&mut *core, channel.wroff(), channel.rdoff() + 4)?;
+        let write = endianness.u32(&offsets[0..])?;
+        let read  = endianness.u32(&offsets[4..])?;
+
+        if (write >= channel.size) || (read >= channel.size) {
+            error!(origin="probe", "RTT down channel {} has offsets outside of its buffer", number);
+            return Err( Error::RttInvalid(address) );
+        }
+
+        let mut written = 0;
+
+        for (offset, len) in channel.free(read, write, data.len() as u32) {
+            let len = len as usize;
+
+            Self::wrrange(&mut *core, channel.buffer + offset, &data[written..written + len])?;
+            written += len;
+        }
+
+        let write = (write + written as u32) % channel.size;
+        Self::wrrange(&mut *core, channel.wroff(), &endianness.encode32(write))?;
+
+        if written < data.len() {
+            warn!(origin="probe", "RTT down channel {} is full, {} of {} bytes were written", number, written, data.len());
+        }
+
+        Ok( Response::RttWritten(number, written) )
+    }
+
+    /// Writes the given bytes at the given address and reads them back to verify them.
+    fn write(&mut self, address: u32, data: &[u8]) -> Result<Response, Error> {
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        // Check if the core is in the correct state.
+        Self::corehalted(&mut *core)?;
+
+        // Perform the write.
+        Self::wrrange(&mut *core, address, data)?;
+
+        // Read back the written range.
+        let end = Self::endaddr(address, data.len())?;
+        let readback = Self::rdrange(&mut *core, address, end)?;
+
+        // Collect the addresses that did not verify.
+        let mismatches: Vec<u32> = data.iter()
+            .zip(readback.iter())
+            .enumerate()
+            .filter(|(_, (w, r))| w != r)
+            .map(|(i, _)| address + i as u32)
+            .collect();
+
+        if mismatches.len() > 0 {
+            warn!(origin="probe", "Write of {} bytes at 0x{:08X} failed verification in {} bytes", data.len(), address, mismatches.len());
+        }
+
+        Ok( Response::Written(address, data.len(), mismatches) )
+    }
+
+    /// Halts the currently selected core.
+    fn halt(&mut self) -> Result<CoreStatus, Error> {
+        let index = self.core;
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.halt(HALTTIMEOUT) {
+            Err(e) => {
+                error!(origin="probe", "Could not halt core {}: {}", index, e);
+                return Err( Error::HaltFailed );
+            },
+            Ok(pc) => debug!(origin="probe", "Core {} halted at PC 0x{:08X}", index, pc),
+        }
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Resumes execution of the currently selected core.
+    fn resume(&mut self) -> Result<CoreStatus, Error> {
+        let index = self.core;
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.run() {
+            Err(e) => {
+                error!(origin="probe", "Could not resume core {}: {}", index, e);
+                return Err( Error::RunFailed );
+            },
+            _ => debug!(origin="probe", "Core {} resumed", index),
+        }
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Resets the currently selected core.
+    fn reset(&mut self) -> Result<CoreStatus, Error> {
+        let index = self.core;
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.reset() {
+            Err(e) => {
+                error!(origin="probe", "Could not reset core {}: {}", index, e);
+                return Err( Error::ResetFailed );
+            },
+            _ => debug!(origin="probe", "Core {} reset", index),
+        }
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Resets the currently selected core and halts it before the first instruction.
+    fn resethalt(&mut self) -> Result<CoreStatus, Error> {
+        let index = self.core;
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.reset_and_halt(HALTTIMEOUT) {
+            Err(e) => {
+                error!(origin="probe", "Could not reset and halt core {}: {}", index, e);
+                return Err( Error::ResetFailed );
+            },
+            Ok(pc) => debug!(origin="probe", "Core {} reset and halted at PC 0x{:08X}", index, pc),
+        }
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Steps the currently selected core one instruction.
+    fn step(&mut self) -> Result<CoreStatus, Error> {
+        let index = self.core;
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        // Check if the core is in the correct state.
+        Self::corehalted(&mut *core)?;
+
+        match core.step() {
+            Err(e) => {
+                error!(origin="probe", "Could not step core {}: {}", index, e);
+                return Err( Error::StepFailed );
+            },
+            Ok(pc) => debug!(origin="probe", "Core {} stepped to PC 0x{:08X}", index, pc),
+        }
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Returns the status of the currently selected core.
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        // A semihosting request is not a halt of the core.
+        self.pollsemihosting();
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        Self::corestatus(&mut *core)
+    }
+
+    /// Lists the cores of the session with their run state.
+    fn cores(&mut self) -> Response {
+        let cores = self.inner.list_cores().into_iter()
+            .map(|(index, coretype)| {
+                let status = match self.inner.core(index) {
+                    Ok(mut core) => Self::corestatus(&mut *core).ok(),
+                    Err(e) => {
+                        warn!(origin="probe", "Could not attach to core {}: {}", index, e);
+                        None
+                    },
+                };
+
+                CoreInfo { index, coretype, status }
+            })
+            .collect();
+
+        Response::Cores(self.core, cores)
+    }
+
+    /// Switches the active core.
+    fn selectcore(&mut self, core: usize) -> Result<Response, Error> {
+        if !self.inner.list_cores().iter().any(|(n, _)| *n == core) {
+            error!(origin="probe", "Core {} does not exist in the target", core);
+            return Err( Error::CoreNotFound(core) );
+        }
+
+        self.core = core;
+
+        info!(origin="probe", "Selected core {}", core);
+
+        Ok( self.cores() )
+    }
+
+    /// Sets a hardware breakpoint in the currently selected core.
+    fn setbreakpoint(&mut self, address: u32) -> Result<Response, Error> {
+        if self.breakpoints.contains(&(self.core, address)) {
+            return self.breakpoints();
+        }
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.set_hw_breakpoint(address) {
+            Err(e) => {
+                error!(origin="probe", "Could not set breakpoint at 0x{:08X}: {}", address, e);
+                return Err( Error::BreakpointFailed(address) );
+            },
+            _ => debug!(origin="probe", "Set breakpoint at 0x{:08X}", address),
+        }
+
+        drop(core);
+
+        self.breakpoints.push( (self.core, address) );
+
+        self.breakpoints()
+    }
+
+    /// Clears a hardware breakpoint in the currently selected core.
+    fn clearbreakpoint(&mut self, address: u32) -> Result<Response, Error> {
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        match core.clear_hw_breakpoint(address) {
+            Err(e) => {
+                error!(origin="probe", "Could not clear breakpoint at 0x{:08X}: {}", address, e);
+                return Err( Error::BreakpointFailed(address) );
+            },
+            _ => debug!(origin="probe", "Cleared breakpoint at 0x{:08X}", address),
+        }
+
+        drop(core);
 
-    config::TargetSelector,
-};
+        let current = self.core;
+        self.breakpoints.retain(|bp| *bp != (current, address));
 
-use tokio::{
-    sync::{
-        mpsc, oneshot,
-    },
-};
+        self.breakpoints()
+    }
 
-use tracing::{
-    debug, error, info, warn,
-};
+    /// Clears all hardware breakpoints in the currently selected core.
+    fn clearbreakpoints(&mut self) -> Result<Response, Error> {
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
 
+        match core.clear_all_hw_breakpoints() {
+            Err(e) => {
+                error!(origin="probe", "Could not clear all breakpoints: {}", e);
+                return Err( Error::BreakpointFailed(0) );
+            },
+            _ => debug!(origin="probe", "Cleared all breakpoints"),
+        }
 
-/// Asynchronous manager of a probe.
-pub struct OpenProbe {
-    /// Inner probe.
-    inner: Session,
+        drop(core);
 
-    /// A receiver of commands and response channels.
-    cmds: mpsc::UnboundedReceiver<(Command, oneshot::Sender<Response>)>,
+        let current = self.core;
+        self.breakpoints.retain(|(c, _)| *c != current);
 
-    /// Currently selected core.
-    core: usize,
-}
+        self.breakpoints()
+    }
 
-impl OpenProbe {
-    /// Creates a new open probe from the given `DebugProbeInfo`.
-    pub fn create(info: DebugProbeInfo, target: impl Into<TargetSelector>) -> Result<(Self, mpsc::UnboundedSender<(Command, oneshot::Sender<Response>)>), ProbeError> {
-        // Open the probe.
-        let probe = info.open()?;
+    /// Lists the breakpoint units and the breakpoints of the currently selected core.
+    fn breakpoints(&mut self) -> Result<Response, Error> {
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
 
-        // Create a session.
-        let inner = probe.attach(target)?;
+        let units = match core.available_breakpoint_units() {
+            Err(e) => {
+                error!(origin="probe", "Could not get the number of breakpoint units: {}", e);
+                return Err( Error::NoBreakpointUnits );
+            },
+            Ok(u) => u,
+        };
 
-        // Create the channels.
-        let (tx, cmds) = mpsc::unbounded_channel();
+        drop(core);
 
-        // Create the open probe.
-        let openprobe = OpenProbe { inner, cmds, core: 0 };
+        let active = self.breakpoints.iter()
+            .filter(|(c, _)| *c == self.core)
+            .map(|(_, address)| *address)
+            .collect();
 
-        Ok((openprobe, tx))
+        Ok( Response::Breakpoints(units, active) )
     }
 
-    /// Runs in a loop until the quit signal is received.
-    pub async fn run(&mut self) {
-        loop {
-            match self.cmds.recv().await {
-                None => {},
+    /// Sets a data watchpoint in a free DWT comparator of the currently selected core.
+    fn setwatchpoint(&mut self, address: u32, size: u32, kind: WatchKind) -> Result<Response, Error> {
+        // The watched range must be a naturally aligned power of two.
+        if !size.is_power_of_two() || (address & (size - 1)) != 0 {
+            return Err( Error::InvalidWatchpoint(address, size) );
+        }
 
-                Some(_) => {},
-            }
+        // Watchpoints are only available in ARM cores.
+        let v8 = self.coretype()? == CoreType::Armv8m;
+
+        // ARMv8-M comparators can only match a single access.
+        if v8 && (size > 4) {
+            return Err( Error::InvalidWatchpoint(address, size) );
+        }
+
+        // Find a free comparator.
+        let units = self.dwtunits()?;
+        let current = self.core;
+
+        let comparator = match (0..units).find(|n| !self.watchpoints.iter().any(|(c, w)| (*c == current) && (w.comparator == *n))) {
+            Some(n) => n,
+            _ => return Err( Error::NoWatchpointUnits ),
+        };
+
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        // Enable the DWT unit.
+        let demcr = Self::rdword32(&mut *core, watchpoint::DEMCR)?;
+        Self::wrword32(&mut *core, watchpoint::DEMCR, demcr | watchpoint::DEMCR_TRCENA)?;
+
+        // Configure the comparator.
+        let comp = Watchpoint::comp(comparator);
+
+        match v8 {
+            true => {
+                Self::wrword32(&mut *core, comp, address)?;
+                Self::wrword32(&mut *core, comp + watchpoint::DWT_FUNCTION_OFFSET, kind.v8function(size))?;
+            },
+
+            false => {
+                let mask = size.trailing_zeros();
+
+                Self::wrword32(&mut *core, comp, address)?;
+                Self::wrword32(&mut *core, comp + watchpoint::DWT_MASK_OFFSET, mask)?;
+
+                // Check that the comparator supports the mask size.
+                if Self::rdword32(&mut *core, comp + watchpoint::DWT_MASK_OFFSET)? != mask {
+                    Self::wrword32(&mut *core, comp + watchpoint::DWT_MASK_OFFSET, 0)?;
+                    return Err( Error::InvalidWatchpoint(address, size) );
+                }
+
+                Self::wrword32(&mut *core, comp + watchpoint::DWT_FUNCTION_OFFSET, kind.v7function())?;
+            },
         }
+
+        debug!(origin="probe", "Set {} watchpoint of {} bytes at 0x{:08X} in comparator {}", kind, size, address, comparator);
+
+        drop(core);
+
+        self.watchpoints.push( (current, Watchpoint { comparator, address, size, kind }) );
+
+        self.watchpoints()
     }
 
-    /// Reads `f32` bits from the given address.
-    fn readf32(&mut self, address: u32) -> Result<f32, Error> {
+    /// Clears the watchpoint in the given DWT comparator of the currently selected core.
+    fn clearwatchpoint(&mut self, comparator: usize) -> Result<Response, Error> {
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
-        // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        // Disable the comparator.
+        let comp = Watchpoint::comp(comparator);
+        Self::wrword32(&mut *core, comp + watchpoint::DWT_FUNCTION_OFFSET, 0)?;
 
-        // Perform the read.
-        unsafe { core::mem::transmute( Self::rdword32(&mut core, address) ) }
+        debug!(origin="probe", "Cleared watchpoint in comparator {}", comparator);
+
+        drop(core);
+
+        let current = self.core;
+        self.watchpoints.retain(|(c, w)| !((*c == current) && (w.comparator == comparator)));
+
+        self.watchpoints()
     }
 
-    /// Reads `u32` bits from the given address.
-    fn readu32(&mut self, address: u32) -> Result<u32, Error> {
+    /// Lists the DWT comparators and the watchpoints of the currently selected core.
+    fn watchpoints(&mut self) -> Result<Response, Error> {
+        let units = self.dwtunits()?;
+
+        let active = self.watchpoints.iter()
+            .filter(|(c, _)| *c == self.core)
+            .map(|(_, w)| *w)
+            .collect();
+
+        Ok( Response::Watchpoints(units as u32, active) )
+    }
+
+    /// Finds the watchpoint that halted the currently selected core and the PC at the halt.
+    fn watchpointhit(&mut self) -> Result<Response, Error> {
+        let current = self.core;
+
+        let watchpoints: Vec<Watchpoint> = self.watchpoints.iter()
+            .filter(|(c, _)| *c == current)
+            .map(|(_, w)| *w)
+            .collect();
+
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
 
-        // Perform the read.
-        Self::rdword32(&mut core, address)
+        let pc = core.pcaddress();
+        let pc = Self::rdregister(&mut *core, pc)?;
+
+        // Find the comparator that matched. Reading the function register clears the flag.
+        let mut hit = None;
+
+        for watchpoint in watchpoints.iter() {
+            let function = Self::rdword32(&mut *core, Watchpoint::comp(watchpoint.comparator) + watchpoint::DWT_FUNCTION_OFFSET)?;
+
+            if (function & watchpoint::DWT_FUNCTION_MATCHED) != 0 {
+                hit = Some(*watchpoint);
+            }
+        }
+
+        match hit {
+            Some(w) => info!(origin="probe", "Watchpoint {} at 0x{:08X} fired at PC 0x{:08X}", w.comparator, w.address, pc),
+            _ => warn!(origin="probe", "Could not find the watchpoint that fired at PC 0x{:08X}", pc),
+        }
+
+        Ok( Response::WatchpointHit(hit, pc) )
     }
 
-    /// Reads `u16` from the given address.
-    fn readu16(&mut self, address: u32) -> Result<u16, Error> {
+    /// Returns the number of DWT comparators of the currently selected core.
+    fn dwtunits(&mut self) -> Result<usize, Error> {
+        // Get the currently selected core.
+        let mut core = self.getcore()?;
+
+        let ctrl = Self::rdword32(&mut *core, watchpoint::DWT_CTRL)?;
+
+        Ok( (ctrl >> 28) as usize )
+    }
+
+    /// Returns the architecture of the currently selected core.
+    /// Fails with `Unsupported` for RISC-V cores, which have no ARM debug units.
+    fn coretype(&mut self) -> Result<CoreType, Error> {
+        match self.inner.list_cores().into_iter().find(|(n, _)| *n == self.core) {
+            Some((_, CoreType::Riscv)) => Err( Error::Unsupported ),
+            Some((_, coretype)) => Ok( coretype ),
+            _ => Err( Error::CoreNotFound(self.core) ),
+        }
+    }
+
+    /// Reads all the registers of the currently selected core.
+    fn registers(&mut self) -> Result<Vec<Register>, Error> {
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
 
-        // Perform the reads.
-        let lo = Self::rdword8(&mut core, address)?;
-        let hi = Self::rdword8(&mut core, address + 2)?;
+        // Read all the registers of the core's architecture.
+        let descriptors = core.descriptors();
 
-        Ok( ((hi as u16) << 8) | (lo as u16) )
+        let mut registers: Vec<Register> = Vec::with_capacity(descriptors.len());
+
+        for descriptor in descriptors {
+            let value = Self::rdregister(&mut *core, descriptor.address)?;
+
+            registers.push( Register { name: descriptor.name, address: descriptor.address, value, group: descriptor.group } );
+        }
+
+        Ok( registers )
     }
 
-    /// Reads `u8` from the given address.
-    fn readu8(&mut self, address: u32) -> Result<u8, Error> {
+    /// Writes the given register of the currently selected core and reads it back.
+    fn wrregister(&mut self, address: u16, value: u32) -> Result<Register, Error> {
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
 
-        // Perform the read.
-        Self::rdword8(&mut core, address)
+        // Find the register in the register file.
+        let (name, group) = match core.descriptors().into_iter().find(|d| d.address == address) {
+            None => return Err( Error::UnknownRegister(address) ),
+            Some(d) => (d.name, d.group),
+        };
+
+        match core.write_core_reg(address, value) {
+            Err(e) => {
+                error!(origin="probe", "Could not write register {}: {}", name, e);
+                return Err( Error::RegisterWriteFailed(address) );
+            },
+            _ => debug!(origin="probe", "Wrote 0x{:08X} to register {}", value, name),
+        }
+
+        let value = Self::rdregister(&mut *core, address)?;
+
+        Ok( Register { name, address, value, group } )
     }
 
-    /// Reads `i32` bits from the given address.
-    fn readi32(&mut self, address: u32) -> Result<i32, Error> {
+    /// Performs a read of the given core register.
+    /// Assumes all validation is performed.
+    fn rdregister(core: &mut dyn CoreInterface, address: u16) -> Result<u32, Error> {
+        match core.read_core_reg(address) {
+            Err(e) => {
+                error!(origin="probe", "Failed to read core register {}: {}", address, e);
+                Err( Error::RegisterReadFailed(address) )
+            },
+            Ok(v) => Ok(v),
+        }
+    }
+
+    /// Reads a value of the given datatype from the given address.
+    fn read(&mut self, datatype: Datatype, address: u32) -> Result<Value, Error> {
+        let data = self.readbytes(address, datatype.size())?;
+
+        decode::decode(datatype, &data, self.endianness)
+    }
+
+    /// Reads `u64` from the given address.
+    fn readu64(&mut self, address: u32) -> Result<u64, Error> {
+        self.endianness.u64( &self.readbytes(address, 8)? )
+    }
+
+    /// Reads `u32` from the given address.
+    fn readu32(&mut self, address: u32) -> Result<u32, Error> {
+        self.endianness.u32( &self.readbytes(address, 4)? )
+    }
+
+    /// Reads the words at the given aligned addresses.
+    fn readwords(&mut self, addresses: Vec<u32>) -> Result<Vec<(u32, u32)>, Error> {
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
 
-        // Perform the read.
-        unsafe { core::mem::transmute( Self::rdword32(&mut core, address) ) }
+        addresses.into_iter()
+            .map(|address| address & !3)
+            .map(|address| Ok( (address, Self::rdword32(&mut *core, address)?) ))
+            .collect()
     }
 
-    /// Reads `i16` from the given address.
-    fn readi16(&mut self, address: u32) -> Result<i16, Error> {
+    /// Writes a 32 bit word of the halted core, keeping the bits of the mask from a read of it.
+    /// Words without kept bits are neither read nor read back.
+    fn modify(&mut self, address: u32, keep: u32, value: u32) -> Result<Option<u32>, Error> {
+        let address = address & !3;
+
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
+
+        if keep == 0 {
+            Self::wrword32(&mut *core, address, value)?;
+
+            info!(origin="probe", "Wrote word at 0x{:08X} to 0x{:08X} without reading it", address, value);
+
+            return Ok( None );
+        }
+
+        let old = Self::rdword32(&mut *core, address)?;
+        let new = (old & keep) | (value & !keep);
+
+        Self::wrword32(&mut *core, address, new)?;
+
+        // Bits cleared or toggled by the write do not read back as written.
+        let readback = Self::rdword32(&mut *core, address)?;
+
+        info!(origin="probe", "Modified word at 0x{:08X} from 0x{:08X} to 0x{:08X}, reads back 0x{:08X}", address, old, new, readback);
+
+        Ok( Some( readback ) )
+    }
+
+    /// Reads `u16` from the given address.
+    fn readu16(&mut self, address: u32) -> Result<u16, Error> {
+        self.endianness.u16( &self.readbytes(address, 2)? )
+    }
+
+    /// Reads `u8` from the given address.
+    fn readu8(&mut self, address: u32) -> Result<u8, Error> {
+        Ok( self.readbytes(address, 1)?[0] )
+    }
 
-        // Perform the reads.
-        let lo = Self::rdword8(&mut core, address)?;
-        let hi = Self::rdword8(&mut core, address + 2)?;
+    /// Reads `i64` from the given address.
+    fn readi64(&mut self, address: u32) -> Result<i64, Error> {
+        Ok( self.readu64(address)? as i64 )
+    }
 
-        let out = ((hi as u16) << 8) | (lo as u16);
+    /// Reads `i32` from the given address.
+    fn readi32(&mut self, address: u32) -> Result<i32, Error> {
+        Ok( self.readu32(address)? as i32 )
+    }
 
-        unsafe { Ok( core::mem::transmute( out ) ) }
+    /// Reads `i16` from the given address.
+    fn readi16(&mut self, address: u32) -> Result<i16, Error> {
+        Ok( self.readu16(address)? as i16 )
     }
 
     /// Reads `i8` from the given address.
     fn readi8(&mut self, address: u32) -> Result<i8, Error> {
+        Ok( self.readu8(address)? as i8 )
+    }
+
+    /// Reads `n` raw bytes starting at the given address.
+    fn readbytes(&mut self, address: u32, n: usize) -> Result<Vec<u8>, Error> {
         // Get the currently selected core.
         let mut core = self.getcore()?;
 
         // Check if the core is in the correct state.
-        Self::corehalted(&mut core)?;
+        Self::corehalted(&mut *core)?;
 
         // Perform the read.
-        unsafe { core::mem::transmute( Self::rdword8(&mut core, address) ) }
+        let end = Self::endaddr(address, n)?;
+
+        Self::rdrange(&mut *core, address, end)
     }
 
     /// Gets the currently selected core.
-    fn getcore(&mut self) -> Result<Core, Error> {
-        match self.inner.core(self.core) {
+    fn getcore(&mut self) -> Result<Box<dyn CoreInterface + '_>, Error> {
+        Self::attach(&mut *self.inner, self.core)
+    }
+
+    /// Attaches to the given core of the backend.
+    fn attach(inner: &mut dyn Backend, n: usize) -> Result<Box<dyn CoreInterface + '_>, Error> {
+        match inner.core(n) {
             Err(e) => {
-                error!(origin="probe", "Could not attach to core {}: {}", self.core, e);
-                return Err( Error::CoreNotFound(self.core) );
+                error!(origin="probe", "Could not attach to core {}: {}", n, e);
+                return Err( Error::CoreNotFound(n) );
             },
             Ok(c) => Ok(c),
         }
     }
 
+    /// Gets the status of the core.
+    fn corestatus(core: &mut dyn CoreInterface) -> Result<CoreStatus, Error> {
+        match core.status() {
+            Err(e) => {
+                error!(origin="probe", "Could not get core status: {}", e);
+                Err( Error::UnknownCoreStatus )
+            },
+            Ok(s) => Ok(s),
+        }
+    }
+
     /// Checks that the core is halted.
-    fn corehalted(core: &mut Core) -> Result<(), Error> {
+    fn corehalted(core: &mut dyn CoreInterface) -> Result<(), Error> {
         match core.status() {
             Err(e) => {
                 error!(origin="probe", "Could not get core status: {}", e);
@@ -211,7 +1728,7 @@ impl OpenProbe {
 
     /// Performs a read of a 32 bit word at the given address.
     /// Assumes all validation is performed.
-    fn rdword32(core: &mut Core, address: u32) -> Result<u32, Error> {
+    fn rdword32(core: &mut dyn CoreInterface, address: u32) -> Result<u32, Error> {
         match core.read_word_32(address) {
             Err(e) => {
                 error!("Failed to read 32 bit word at address {}: {}", address, e);
@@ -223,7 +1740,7 @@ impl OpenProbe {
 
     /// Performs a read of a 8 bit word at the given address.
     /// Assumes all validation is performed.
-    fn rdword8(core: &mut Core, address: u32) -> Result<u8, Error> {
+    fn rdword8(core: &mut dyn CoreInterface, address: u32) -> Result<u8, Error> {
         match core.read_word_8(address) {
             Err(e) => {
                 error!("Failed to read 8 bit word at address {}: {}", address, e);
@@ -233,9 +1750,11 @@ impl OpenProbe {
         }
     }
 
-    /// Performs a read of a 8 bit word at the given address.
+    /// Performs a read of the given address range.
+    /// The bytes are returned in address order for any target endianness, the aligned
+    /// words hold the lowest address in their lowest byte lane.
     /// Assumes all validation is performed.
-    fn rdrange(&mut self, core: &mut Core, s: u32, e: u32) -> Result<Vec<u8>, Error> {
+    fn rdrange(core: &mut dyn CoreInterface, s: u32, e: u32) -> Result<Vec<u8>, Error> {
         // Check that the start and end are valid.
         if e < s  { return Err( Error::EndBeforeStart ) }
         if e == s { return Ok( Vec::new() )             }
@@ -243,71 +1762,139 @@ impl OpenProbe {
         // Create an output array.
         let mut out = Vec::with_capacity(e as usize - s as usize);
 
-        // Create an aligned start and end and get the length.
-        let sa = match s & 0b11 {
-            0 => s,
-            _ => (s & !(0b11)) + 4,
-        };
-
-        let ea = match e & 0b11 {
-            0 => e,
-            _ => (e & !(0b11)) + 4,
-        };
+        // Create an aligned start and end.
+        let (sa, ea) = Self::align(s, e);
 
-        let la = ea - sa;
+        // The range does not contain a full aligned word.
+        if sa >= ea {
+            for address in s..e {
+                out.push( Self::rdword8(core, address)? );
+            }
 
+            return Ok( out );
+        }
 
         // Check if an unaligned read is necessary at the beginning.
-        if sa > s {
-            // Create a temp variable to iterate the address.
-            let mut st = s;
-
-            // Perform the aligned read.
-            while st < sa {
-                match Self::rdword8(core, st) {
-                    Err(e) => return Err( e ),
-                    Ok(b) => out.push(b),
-                }
-
-                st += 1;
-            }
+        for address in s..sa {
+            out.push( Self::rdword8(core, address)? );
         }
 
-        // Check if an aligned read is necessary.
-        if la > 0 {
-            // Create the buffer to store the memory.
-            let mut buf = vec![0u8; la as usize];
+        // Create the buffer to store the memory.
+        let mut buf = vec![0u32; ((ea - sa) >> 2) as usize];
 
-            // Perform the aligned read.
-            match core.read_mem_32bit(s, &mut buf) {
-                Err(e) => {
-                    error!("Failed to read {} bytes in 32 bit aligned mode from address {}: {}", la, sa, e);
-                    return Err( Error::ReadRange32Failed(sa, ea) );
-                },
-                _ => (),
-            }
+        // Perform the aligned read.
+        match core.read_32(sa, &mut buf) {
+            Err(e) => {
+                error!("Failed to read {} bytes in 32 bit aligned mode from address {}: {}", ea - sa, sa, e);
+                return Err( Error::ReadRange32Failed(sa, ea) );
+            },
+            _ => (),
+        }
 
-            // Push the data into the output.
-            out.extend_from_slice(&buf);
+        // Push the data into the output in byte lane order.
+        for word in buf.iter() {
+            out.extend_from_slice( &word.to_le_bytes() );
         }
 
         // Check if an unaligned read is necessary at the end.
-        if ea < e {
-            // Create a temp variable to iterate the address.
-            let mut et = ea;
-
-            // Perform the aligned read.
-            while et < e {
-                match Self::rdword8(core, et) {
-                    Err(e) => return Err( e ),
-                    Ok(b) => out.push(b),
-                }
+        for address in ea..e {
+            out.push( Self::rdword8(core, address)? );
+        }
+
+        Ok( out )
+    }
+
+    /// Performs a write of a 32 bit word at the given address.
+    /// Assumes all validation is performed.
+    fn wrword32(core: &mut dyn CoreInterface, address: u32, data: u32) -> Result<(), Error> {
+        match core.write_word_32(address, data) {
+            Err(e) => {
+                error!("Failed to write 32 bit word at address {}: {}", address, e);
+                Err( Error::Write32Failed(address) )
+            },
+            Ok(_) => Ok(())
+        }
+    }
 
-                et += 1;
+    /// Performs a write of a 8 bit word at the given address.
+    /// Assumes all validation is performed.
+    fn wrword8(core: &mut dyn CoreInterface, address: u32, data: u8) -> Result<(), Error> {
+        match core.write_word_8(address, data) {
+            Err(e) => {
+                error!("Failed to write 8 bit word at address {}: {}", address, e);
+                Err( Error::Write8Failed(address) )
+            },
+            Ok(_) => Ok(())
+        }
+    }
+
+    /// Performs a write of the given data starting at the given address.
+    /// Uses the same alignment and byte lane order as `rdrange`.
+    /// Assumes all validation is performed.
+    fn wrrange(core: &mut dyn CoreInterface, s: u32, data: &[u8]) -> Result<(), Error> {
+        // Get the end of the range.
+        let e = Self::endaddr(s, data.len())?;
+
+        if e == s { return Ok(()) }
+
+        // Create an aligned start and end.
+        let (sa, ea) = Self::align(s, e);
+
+        // The range does not contain a full aligned word.
+        if sa >= ea {
+            for (address, byte) in (s..e).zip(data.iter()) {
+                Self::wrword8(core, address, *byte)?;
             }
+
+            return Ok(());
         }
 
-        Ok( out )
+        // Get the offsets of the aligned section in the data.
+        let (so, eo) = ((sa - s) as usize, (ea - s) as usize);
+
+        // Check if an unaligned write is necessary at the beginning.
+        for (address, byte) in (s..sa).zip(data[..so].iter()) {
+            Self::wrword8(core, address, *byte)?;
+        }
+
+        // Pack the aligned section into words in byte lane order.
+        let buf: Vec<u32> = data[so..eo].chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+
+        // Perform the aligned write.
+        match core.write_32(sa, &buf) {
+            Err(e) => {
+                error!("Failed to write {} bytes in 32 bit aligned mode to address {}: {}", ea - sa, sa, e);
+                return Err( Error::WriteRange32Failed(sa, ea) );
+            },
+            _ => (),
+        }
+
+        // Check if an unaligned write is necessary at the end.
+        for (address, byte) in (ea..e).zip(data[eo..].iter()) {
+            Self::wrword8(core, address, *byte)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the 32 bit aligned start and end contained within the given range.
+    fn align(s: u32, e: u32) -> (u32, u32) {
+        let sa = match s & 0b11 {
+            0 => s,
+            _ => (s & !(0b11)).saturating_add(4),
+        };
+
+        (sa, e & !(0b11))
+    }
+
+    /// Returns the end address of a range of the given length.
+    fn endaddr(s: u32, len: usize) -> Result<u32, Error> {
+        match s.checked_add(len as u32) {
+            Some(e) if len <= u32::MAX as usize => Ok(e),
+            _ => Err( Error::AddressOverflow(s, len) ),
+        }
     }
 }
 
@@ -328,39 +1915,137 @@ pub enum Error {
 
     ReadRange32Failed(u32, u32),
 
+    Write32Failed(u32),
+
+    Write8Failed(u32),
+
+    WriteRange32Failed(u32, u32),
+
     EndBeforeStart,
 
-}
+    AddressOverflow(u32, usize),
 
+    NotEnoughData(usize, usize),
 
+    HaltFailed,
 
-pub enum Response {
+    RunFailed,
 
-}
+    ResetFailed,
+
+    StepFailed,
+
+    Disconnected,
+
+    AttachFailed(String),
+
+    UnknownRegister(u16),
+
+    RegisterReadFailed(u16),
+
+    RegisterWriteFailed(u16),
+
+    BreakpointFailed(u32),
+
+    NoBreakpointUnits,
+
+    InvalidWatchpoint(u32, u32),
+
+    NoWatchpointUnits,
+
+    Unsupported,
 
+    NothingToFlash,
 
-pub enum Command {
-    /// Reads an `i8` at the given address.
-    ReadI8(u32),
+    FlashFailed(String),
 
-    /// Reads an `u8` at the given address.
-    ReadU8(u32),
+    FlashCancelled,
 
-    /// Reads an `i16` at the given address.
-    ReadI16(u32),
+    RttNotFound,
 
-    /// Reads an `u16` at the given address.
-    ReadU16(u32),
+    RttInvalid(u32),
 
-    /// Reads an `i32` at the given address.
-    ReadI32(u32),
+    RttNotAttached,
 
-    /// Reads an `u32` at the given address.
-    ReadU32(u32),
+    RttChannelNotFound(usize),
 
-    /// Reads an `i64` at the given address.
-    ReadI64(u32),
+    InvalidTraceClock(u32, u32),
 
-    /// Reads an `u64` at the given address.
-    ReadU64(u32),
+    TraceFailed(String),
+
+    NoBackgroundAccess(usize),
+
+    NoRam,
+
+    Flashing,
+
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            Error::CoreNotFound(core) => write!(f, "Core {} was not found", core),
+            Error::UnknownCoreStatus => write!(f, "Could not get the status of the core"),
+            Error::CoreNotHalted => write!(f, "The core is not halted"),
+            Error::Read32Failed(address) => write!(f, "Failed to read 32 bit word at address 0x{:08X}", address),
+            Error::Read8Failed(address) => write!(f, "Failed to read 8 bit word at address 0x{:08X}", address),
+            Error::ReadRange32Failed(s, e) => write!(f, "Failed to read range 0x{:08X} - 0x{:08X}", s, e),
+            Error::Write32Failed(address) => write!(f, "Failed to write 32 bit word at address 0x{:08X}", address),
+            Error::Write8Failed(address) => write!(f, "Failed to write 8 bit word at address 0x{:08X}", address),
+            Error::WriteRange32Failed(s, e) => write!(f, "Failed to write range 0x{:08X} - 0x{:08X}", s, e),
+            Error::EndBeforeStart => write!(f, "End address is before the start address"),
+            Error::NotEnoughData(expected, found) => write!(f, "Expected {} bytes of data, found {}", expected, found),
+            Error::HaltFailed => write!(f, "Failed to halt the core"),
+            Error::RunFailed => write!(f, "Failed to resume the core"),
+            Error::ResetFailed => write!(f, "Failed to reset the core"),
+            Error::StepFailed => write!(f, "Failed to step the core"),
+            Error::Disconnected => write!(f, "The probe is disconnected"),
+            Error::AttachFailed(ref e) => write!(f, "Could not attach to the target: {}", e),
+            Error::UnknownRegister(r) => write!(f, "Register {} does not exist in this core", r),
+            Error::RegisterReadFailed(r) => write!(f, "Failed to read register {}", r),
+            Error::RegisterWriteFailed(r) => write!(f, "Failed to write register {}", r),
+            Error::BreakpointFailed(address) => write!(f, "Failed to modify breakpoint at 0x{:08X}", address),
+            Error::NoBreakpointUnits => write!(f, "Could not get the breakpoint units of the core"),
+            Error::InvalidWatchpoint(address, size) => write!(f, "Cannot watch {} bytes at 0x{:08X}, the range must be a supported naturally aligned power of two", size, address),
+            Error::NoWatchpointUnits => write!(f, "All the watchpoint comparators of the core are in use"),
+            Error::Unsupported => write!(f, "The operation is not supported by this core"),
+            Error::NothingToFlash => write!(f, "The ELF file has no loadable segments"),
+            Error::FlashFailed(ref e) => write!(f, "Flashing failed: {}", e),
+            Error::FlashCancelled => write!(f, "Flashing was cancelled"),
+            Error::RttNotFound => write!(f, "The RTT control block was not found"),
+            Error::RttInvalid(address) => write!(f, "The RTT control block at 0x{:08X} is corrupted", address),
+            Error::RttNotAttached => write!(f, "There is no RTT session"),
+            Error::RttChannelNotFound(n) => write!(f, "RTT channel {} does not exist", n),
+            Error::InvalidTraceClock(clock, baud) => write!(f, "A trace clock of {} Hz cannot output SWO at {} baud", clock, baud),
+            Error::TraceFailed(ref e) => write!(f, "Could not start the SWO capture: {}", e),
+            Error::NoBackgroundAccess(core) => write!(f, "Core {} cannot read memory while it runs, halt it to read its memory", core),
+            Error::NoRam => write!(f, "The memory map of the target has no RAM regions"),
+            Error::Flashing => write!(f, "The target is being flashed"),
+            Error::AddressOverflow(s, len) => write!(f, "A range of {} bytes at 0x{:08X} overflows the address space", len, s),
+        }
+    }
+}
+
+
+
+/// Sends a command to an `OpenProbe` and waits for its response.
+pub async fn request(channel: CommandSender, cmd: Command) -> Response {
+    // Create the response channel.
+    let (tx, rx) = oneshot::channel();
+
+    match channel.send((cmd, tx)) {
+        Err(_) => {
+            error!(origin="probe", "Could not send command, the probe command loop is closed");
+            return Response::Error( Error::Disconnected );
+        },
+        _ => (),
+    }
+
+    match rx.await {
+        Err(_) => {
+            error!(origin="probe", "Response channel closed before a response was received");
+            Response::Error( Error::Disconnected )
+        },
+        Ok(r) => r,
+    }
 }
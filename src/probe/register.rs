@@ -0,0 +1,34 @@
+//! Core register abstractions.
+
+
+
+/// Group a core register belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterGroup {
+    /// General purpose registers.
+    General,
+
+    /// Special registers (stack pointers, status registers, ...).
+    Special,
+
+    /// Floating point registers.
+    Float,
+}
+
+
+
+/// A core register and its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    /// Name of the register as given by the core architecture.
+    pub name: String,
+
+    /// Debug address of the register.
+    pub address: u16,
+
+    /// Value of the register.
+    pub value: u32,
+
+    /// Group of the register.
+    pub group: RegisterGroup,
+}
@@ -0,0 +1,164 @@
+//! Real-Time Transfer.
+//! The target keeps a control block in RAM that describes a set of ring buffers.
+//! Up channels carry data from the target to the host, down channels from the host to the target.
+
+
+
+use tokio::sync::mpsc::UnboundedSender;
+
+
+
+/// Identifier at the start of the control block.
+pub(super) const RTT_ID: &[u8; 16] = b"SEGGER RTT\0\0\0\0\0\0";
+
+/// Size of the control block header.
+pub(super) const RTT_HEADER: u32 = 24;
+
+/// Size of a buffer descriptor.
+pub(super) const RTT_DESCRIPTOR: u32 = 24;
+
+/// Offset of the write offset in a buffer descriptor.
+pub(super) const RTT_WROFF: u32 = 12;
+
+/// Offset of the read offset in a buffer descriptor.
+pub(super) const RTT_RDOFF: u32 = 16;
+
+/// Maximum number of channels in each direction accepted as a valid control block.
+pub(super) const RTT_MAXCHANNELS: u32 = 32;
+
+/// Maximum length of a channel name.
+pub(super) const RTT_MAXNAME: u32 = 32;
+
+/// Size of the blocks of RAM read while scanning for the control block.
+pub(super) const RTT_SCANBLOCK: u32 = 0x1000;
+
+
+
+/// Channel to send the events of an RTT session.
+pub type RttSender = UnboundedSender<RttEvent>;
+
+
+
+/// Event of an RTT session.
+#[derive(Debug, Clone)]
+pub enum RttEvent {
+    /// Data received from the given up channel.
+    Data(usize, Vec<u8>),
+
+    /// The control block could no longer be read, the session was closed.
+    Lost,
+}
+
+
+
+/// Control block of an RTT session.
+#[derive(Debug, Clone)]
+pub struct RttControl {
+    /// Address of the control block.
+    pub address: u32,
+
+    /// Channels from the target to the host.
+    pub up: Vec<RttChannel>,
+
+    /// Channels from the host to the target.
+    pub down: Vec<RttChannel>,
+}
+
+impl RttControl {
+    /// Returns the address of the descriptor of the given up channel.
+    pub(super) fn updescriptor(address: u32, n: u32) -> u32 {
+        address + RTT_HEADER + (n * RTT_DESCRIPTOR)
+    }
+
+    /// Returns the address of the descriptor of the given down channel.
+    pub(super) fn downdescriptor(address: u32, up: u32, n: u32) -> u32 {
+        address + RTT_HEADER + ((up + n) * RTT_DESCRIPTOR)
+    }
+}
+
+
+
+/// A configured ring buffer of the control block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RttChannel {
+    /// Number of the channel.
+    pub number: usize,
+
+    /// Name of the channel, if the target set one.
+    pub name: Option<String>,
+
+    /// Address of the descriptor of the channel.
+    pub descriptor: u32,
+
+    /// Address of the ring buffer.
+    pub buffer: u32,
+
+    /// Size of the ring buffer.
+    pub size: u32,
+}
+
+impl RttChannel {
+    /// Returns the name of the channel or its number.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}: {}", self.number, name),
+            _ => format!("Channel {}", self.number),
+        }
+    }
+
+    /// Returns the address of the write offset of the channel.
+    pub(super) fn wroff(&self) -> u32 {
+        self.descriptor + RTT_WROFF
+    }
+
+    /// Returns the address of the read offset of the channel.
+    pub(super) fn rdoff(&self) -> u32 {
+        self.descriptor + RTT_RDOFF
+    }
+
+    /// Returns the ranges of the buffer, as (offset, length), that contain unread data.
+    pub(super) fn pending(&self, read: u32, write: u32) -> Vec<(u32, u32)> {
+        match read <= write {
+            true => vec![ (read, write - read) ],
+            _ => vec![ (read, self.size - read), (0, write) ],
+        }
+        .into_iter()
+        .filter(|(_, len)| *len > 0)
+        .collect()
+    }
+
+    /// Returns the ranges of the buffer, as (offset, length), that can be written with up to `len` bytes.
+    /// One byte is always kept free to distinguish a full buffer from an empty one.
+    pub(super) fn free(&self, read: u32, write: u32, len: u32) -> Vec<(u32, u32)> {
+        let available = match read > write {
+            true => read - write - 1,
+            _ => self.size - (write - read) - 1,
+        };
+
+        let len = len.min(available);
+        let first = len.min(self.size - write);
+
+        vec![ (write, first), (0, len - first) ]
+            .into_iter()
+            .filter(|(_, len)| *len > 0)
+            .collect()
+    }
+}
+
+
+
+/// Returns the offset of the control block identifier in the data.
+pub(super) fn find(data: &[u8]) -> Option<usize> {
+    data.windows(RTT_ID.len())
+        .position(|w| w == RTT_ID)
+}
+
+/// Extracts a NUL terminated string from the data.
+pub(super) fn cstring(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+
+    match end {
+        0 => None,
+        _ => Some( String::from_utf8_lossy(&data[..end]).into_owned() ),
+    }
+}
@@ -0,0 +1,460 @@
+//! ARM semihosting.
+//! The target requests a host service with a `BKPT 0xAB` instruction, the operation in R0
+//! and a pointer to its parameter block in R1. The result of the operation is returned in R0.
+
+
+
+use std::{
+    collections::HashMap,
+    fs::{ File, OpenOptions },
+    io::{ Read, Seek, SeekFrom, Write },
+    path::{ Component, Path, PathBuf },
+    time::{ Instant, SystemTime, UNIX_EPOCH },
+};
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use tracing::{
+    debug, info, warn,
+};
+
+use super::{ CoreInterface, Endianness, Error, OpenProbe };
+
+
+
+/// Thumb encoding of `BKPT 0xAB`.
+pub(super) const BKPT_SEMIHOSTING: u16 = 0xBEAB;
+
+const SYS_OPEN: u32 = 0x01;
+const SYS_CLOSE: u32 = 0x02;
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_WRITE: u32 = 0x05;
+const SYS_READ: u32 = 0x06;
+const SYS_ISERROR: u32 = 0x08;
+const SYS_ISTTY: u32 = 0x09;
+const SYS_SEEK: u32 = 0x0A;
+const SYS_FLEN: u32 = 0x0C;
+const SYS_REMOVE: u32 = 0x0E;
+const SYS_RENAME: u32 = 0x0F;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_TIME: u32 = 0x11;
+const SYS_ERRNO: u32 = 0x13;
+const SYS_GET_CMDLINE: u32 = 0x15;
+const SYS_HEAPINFO: u32 = 0x16;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// Reason of a `SYS_EXIT` at the normal end of the application.
+const ADP_STOPPED_APPLICATIONEXIT: u32 = 0x20026;
+
+/// Name of the console pseudo file.
+const TT: &'static [u8] = b":tt";
+
+/// Handle of the console input.
+const STDIN: u32 = 1;
+
+/// Handle of the console output.
+const STDOUT: u32 = 2;
+
+/// Handle of the console error output.
+const STDERR: u32 = 3;
+
+/// Maximum length of a string read from the target.
+const MAXSTRING: u32 = 1024;
+
+/// Error codes returned by `SYS_ERRNO`.
+const ENOENT: u32 = 2;
+const EBADF: u32 = 9;
+const EACCES: u32 = 13;
+const EINVAL: u32 = 22;
+const EIO: u32 = 5;
+
+
+
+/// Channel to send the events of the semihosting host.
+pub type SemihostSender = UnboundedSender<SemihostEvent>;
+
+
+
+/// Event of the semihosting host.
+#[derive(Debug, Clone)]
+pub enum SemihostEvent {
+    /// Output of the target to the console.
+    Output(Vec<u8>),
+
+    /// The application exited with the given exit code, if reported.
+    Exit(Option<u32>),
+}
+
+
+
+/// Result of a semihosting operation.
+pub(super) enum Outcome {
+    /// Return the value in R0 and resume.
+    Return(u32),
+
+    /// Resume without modifying R0.
+    Resume,
+
+    /// The application exited, leave the core halted.
+    Exit,
+}
+
+
+
+/// Host side of the semihosting requests of a target.
+pub struct Semihosting {
+    /// Channel of the console output and exit events.
+    events: SemihostSender,
+
+    /// Directory that contains all the files opened by the target.
+    root: PathBuf,
+
+    /// Open files by handle.
+    files: HashMap<u32, File>,
+
+    /// Next file handle.
+    next: u32,
+
+    /// Error code of the last failed operation.
+    errno: u32,
+
+    /// Start of the execution, reference of `SYS_CLOCK`.
+    start: Instant,
+}
+
+impl Semihosting {
+    /// Creates a host with all the files sandboxed to the given directory.
+    pub fn new(root: PathBuf, events: SemihostSender) -> Self {
+        Semihosting {
+            events,
+            root,
+            files: HashMap::new(),
+            next: STDERR + 1,
+            errno: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Services the operation with the given parameter.
+    pub(super) fn service(&mut self, core: &mut dyn CoreInterface, endianness: Endianness, op: u32, param: u32) -> Result<Outcome, Error> {
+        debug!(origin="probe", "Semihosting operation 0x{:02X} with parameter 0x{:08X}", op, param);
+
+        let outcome = match op {
+            SYS_WRITEC => {
+                let c = OpenProbe::rdrange(core, param, param + 1)?;
+                self.output(c);
+                Outcome::Resume
+            },
+
+            SYS_WRITE0 => {
+                let s = Self::cstring(core, param)?;
+                self.output(s);
+                Outcome::Resume
+            },
+
+            SYS_WRITE => {
+                let [handle, buffer, len] = Self::params::<3>(core, endianness, param)?;
+                let data = OpenProbe::rdrange(core, buffer, OpenProbe::endaddr(buffer, len as usize)?)?;
+
+                Outcome::Return( self.write(handle, data) )
+            },
+
+            SYS_READ => {
+                let [handle, buffer, len] = Self::params::<3>(core, endianness, param)?;
+
+                match self.read(handle, len) {
+                    Some(data) => {
+                        OpenProbe::wrrange(core, buffer, &data)?;
+                        Outcome::Return( len - data.len() as u32 )
+                    },
+                    _ => Outcome::Return( u32::MAX ),
+                }
+            },
+
+            SYS_OPEN => {
+                let [name, mode, len] = Self::params::<3>(core, endianness, param)?;
+                let name = OpenProbe::rdrange(core, name, OpenProbe::endaddr(name, len.min(MAXSTRING) as usize)?)?;
+
+                Outcome::Return( self.open(&name, mode) )
+            },
+
+            SYS_CLOSE => {
+                let [handle] = Self::params::<1>(core, endianness, param)?;
+
+                match (handle, self.files.remove(&handle)) {
+                    (STDIN..=STDERR, _) | (_, Some(_)) => Outcome::Return(0),
+                    _ => self.fail(EBADF),
+                }
+            },
+
+            SYS_ISERROR => {
+                let [status] = Self::params::<1>(core, endianness, param)?;
+                Outcome::Return( ((status as i32) < 0) as u32 )
+            },
+
+            SYS_ISTTY => {
+                let [handle] = Self::params::<1>(core, endianness, param)?;
+                Outcome::Return( (STDIN..=STDERR).contains(&handle) as u32 )
+            },
+
+            SYS_SEEK => {
+                let [handle, position] = Self::params::<2>(core, endianness, param)?;
+
+                match self.files.get_mut(&handle).map(|f| f.seek(SeekFrom::Start(position as u64))) {
+                    Some(Ok(_)) => Outcome::Return(0),
+                    Some(Err(_)) => self.fail(EIO),
+                    None => self.fail(EBADF),
+                }
+            },
+
+            SYS_FLEN => {
+                let [handle] = Self::params::<1>(core, endianness, param)?;
+
+                match self.files.get(&handle).map(|f| f.metadata()) {
+                    Some(Ok(metadata)) => Outcome::Return( metadata.len() as u32 ),
+                    Some(Err(_)) => self.fail(EIO),
+                    None => self.fail(EBADF),
+                }
+            },
+
+            SYS_REMOVE => {
+                let [name, len] = Self::params::<2>(core, endianness, param)?;
+                let name = OpenProbe::rdrange(core, name, OpenProbe::endaddr(name, len.min(MAXSTRING) as usize)?)?;
+
+                match self.sandbox(&name).map(std::fs::remove_file) {
+                    Some(Ok(_)) => Outcome::Return(0),
+                    Some(Err(_)) => self.fail(ENOENT),
+                    None => self.fail(EACCES),
+                }
+            },
+
+            SYS_RENAME => {
+                let [old, oldlen, new, newlen] = Self::params::<4>(core, endianness, param)?;
+                let old = OpenProbe::rdrange(core, old, OpenProbe::endaddr(old, oldlen.min(MAXSTRING) as usize)?)?;
+                let new = OpenProbe::rdrange(core, new, OpenProbe::endaddr(new, newlen.min(MAXSTRING) as usize)?)?;
+
+                match (self.sandbox(&old), self.sandbox(&new)) {
+                    (Some(old), Some(new)) => match std::fs::rename(old, new) {
+                        Ok(_) => Outcome::Return(0),
+                        _ => self.fail(ENOENT),
+                    },
+                    _ => self.fail(EACCES),
+                }
+            },
+
+            SYS_CLOCK => Outcome::Return( (self.start.elapsed().as_millis() / 10) as u32 ),
+
+            SYS_TIME => Outcome::Return( SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0) ),
+
+            SYS_ERRNO => Outcome::Return( self.errno ),
+
+            SYS_GET_CMDLINE => {
+                let [buffer, len] = Self::params::<2>(core, endianness, param)?;
+
+                // An empty command line.
+                match len {
+                    0 => self.fail(EINVAL),
+                    _ => {
+                        OpenProbe::wrrange(core, buffer, &[0])?;
+                        OpenProbe::wrrange(core, param + 4, &endianness.encode32(0))?;
+                        Outcome::Return(0)
+                    },
+                }
+            },
+
+            SYS_HEAPINFO => {
+                // Let the target use its linker defaults.
+                let [block] = Self::params::<1>(core, endianness, param)?;
+                OpenProbe::wrrange(core, block, &[0; 16])?;
+                Outcome::Resume
+            },
+
+            SYS_EXIT => {
+                let code = match param {
+                    ADP_STOPPED_APPLICATIONEXIT => Some(0),
+                    _ => None,
+                };
+
+                self.exit(param, code)
+            },
+
+            SYS_EXIT_EXTENDED => {
+                let [reason, code] = Self::params::<2>(core, endianness, param)?;
+
+                self.exit(reason, Some(code))
+            },
+
+            _ => {
+                warn!(origin="probe", "Unsupported semihosting operation 0x{:02X}", op);
+                self.fail(EINVAL)
+            },
+        };
+
+        Ok( outcome )
+    }
+
+    /// Sends output of the target to the console.
+    fn output(&self, data: Vec<u8>) {
+        if self.events.send( SemihostEvent::Output(data) ).is_err() {
+            warn!(origin="probe", "Semihosting console closed, output of the target was dropped");
+        }
+    }
+
+    /// Reports the exit of the application.
+    fn exit(&self, reason: u32, code: Option<u32>) -> Outcome {
+        match code {
+            Some(code) => info!(origin="probe", "Application exited with code {}", code),
+            _ => info!(origin="probe", "Application stopped with reason 0x{:X}", reason),
+        }
+
+        let _ = self.events.send( SemihostEvent::Exit(code) );
+
+        Outcome::Exit
+    }
+
+    /// Records the error code of a failed operation.
+    fn fail(&mut self, errno: u32) -> Outcome {
+        self.errno = errno;
+        Outcome::Return( u32::MAX )
+    }
+
+    /// Opens a file or the console. Returns the handle or -1.
+    fn open(&mut self, name: &[u8], mode: u32) -> u32 {
+        // The console is opened for reading, writing or appending.
+        if name == TT {
+            return match mode {
+                0..=3 => STDIN,
+                4..=7 => STDOUT,
+                _ => STDERR,
+            };
+        }
+
+        let path = match self.sandbox(name) {
+            Some(path) => path,
+            _ => {
+                warn!(origin="probe", "Target tried to open {} outside of the semihosting directory", String::from_utf8_lossy(name));
+                self.errno = EACCES;
+                return u32::MAX;
+            },
+        };
+
+        let mut options = OpenOptions::new();
+
+        match mode >> 2 {
+            0 => options.read(true).write((mode & 0b10) != 0),
+            1 => options.write(true).create(true).truncate(true).read((mode & 0b10) != 0),
+            _ => options.append(true).create(true).read((mode & 0b10) != 0),
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&self.root) {
+            warn!(origin="probe", "Could not create the semihosting directory {}: {}", self.root.display(), e);
+        }
+
+        match options.open(&path) {
+            Ok(file) => {
+                let handle = self.next;
+                self.next += 1;
+                self.files.insert(handle, file);
+
+                debug!(origin="probe", "Target opened {} as handle {}", path.display(), handle);
+
+                handle
+            },
+
+            Err(e) => {
+                warn!(origin="probe", "Target could not open {}: {}", path.display(), e);
+                self.errno = ENOENT;
+                u32::MAX
+            },
+        }
+    }
+
+    /// Writes to a file or the console. Returns the number of bytes not written.
+    fn write(&mut self, handle: u32, data: Vec<u8>) -> u32 {
+        let len = data.len() as u32;
+
+        match handle {
+            STDOUT | STDERR => {
+                self.output(data);
+                0
+            },
+
+            _ => match self.files.get_mut(&handle).map(|f| f.write_all(&data)) {
+                Some(Ok(_)) => 0,
+                Some(Err(_)) => { self.errno = EIO; len },
+                None => { self.errno = EBADF; len },
+            },
+        }
+    }
+
+    /// Reads from a file. The console has no input, it always reports the end of file.
+    fn read(&mut self, handle: u32, len: u32) -> Option<Vec<u8>> {
+        if handle == STDIN {
+            return Some( Vec::new() );
+        }
+
+        let file = match self.files.get_mut(&handle) {
+            Some(file) => file,
+            _ => {
+                self.errno = EBADF;
+                return None;
+            },
+        };
+
+        let mut data = Vec::new();
+
+        match file.take(len as u64).read_to_end(&mut data) {
+            Ok(_) => Some(data),
+            Err(_) => {
+                self.errno = EIO;
+                None
+            },
+        }
+    }
+
+    /// Resolves a file name of the target inside of the semihosting directory.
+    /// Absolute paths and parent components are rejected.
+    pub(super) fn sandbox(&self, name: &[u8]) -> Option<PathBuf> {
+        let name = std::str::from_utf8(name).ok()?;
+        let path = Path::new(name);
+
+        let inside = path.components().all(|c| match c {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false,
+        });
+
+        match inside && !name.is_empty() {
+            true => Some( self.root.join(path) ),
+            _ => None,
+        }
+    }
+
+    /// Reads the words of a parameter block.
+    fn params<const N: usize>(core: &mut dyn CoreInterface, endianness: Endianness, address: u32) -> Result<[u32; N], Error> {
+        let data = OpenProbe::rdrange(core, address, OpenProbe::endaddr(address, 4 * N)?)?;
+
+        let mut out = [0u32; N];
+
+        for (word, bytes) in out.iter_mut().zip(data.chunks(4)) {
+            *word = endianness.u32(bytes)?;
+        }
+
+        Ok( out )
+    }
+
+    /// Reads a NUL terminated string.
+    fn cstring(core: &mut dyn CoreInterface, address: u32) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+
+        for address in address..address.saturating_add(MAXSTRING) {
+            match OpenProbe::rdword8(core, address)? {
+                0 => break,
+                c => out.push(c),
+            }
+        }
+
+        Ok( out )
+    }
+}
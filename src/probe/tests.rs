@@ -0,0 +1,611 @@
+//! Tests of the probe command loop over the simulated backend.
+
+
+
+use crate::elf::ElfFile;
+
+use probe_rs::{ CoreStatus, CoreType, HaltReason };
+
+use std::{
+    future::Future,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{
+    backend::{ Backend, DumpBackend, ScriptedHalt, SimBackend },
+    flash::FLASHCHUNK,
+    request, Access, Command, CommandSender, CoreDump, Datatype, Endianness, Error, FlashEvent, FlashHandle, FrameKind, Mismatch, OpenProbe, Response, RttEvent, SemihostEvent, Semihosting, TraceConfig, TracePacket, Value,
+};
+
+
+
+const RAM: u32 = 0x2000_0000;
+
+const ROM: u32 = 0x1000_0000;
+
+
+
+/// Runs the test against an `OpenProbe` over the given little endian target.
+fn session<B, F, Fut>(backend: B, test: F)
+    where B: Backend + 'static, F: FnOnce(CommandSender) -> Fut, Fut: Future<Output = ()>
+{
+    endiansession(backend, Endianness::Little, test)
+}
+
+/// Runs the test against an `OpenProbe` over the given target with the given endianness.
+fn endiansession<B, F, Fut>(backend: B, endianness: Endianness, test: F)
+    where B: Backend + 'static, F: FnOnce(CommandSender) -> Fut, Fut: Future<Output = ()>
+{
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("Could not build the test runtime");
+
+    let (mut probe, channel) = OpenProbe::new(Box::new(backend), endianness);
+
+    runtime.block_on(async move {
+        futures::join!(probe.run(), test(channel));
+    });
+}
+
+/// Builds a 32 bit ARM ELF with a single loadable segment.
+fn elf(address: u32, data: &[u8]) -> Arc<ElfFile> {
+    let mut file = Vec::new();
+
+    // File header.
+    file.extend_from_slice(&[0x7F, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    file.extend_from_slice(&2u16.to_le_bytes());
+    file.extend_from_slice(&40u16.to_le_bytes());
+    file.extend_from_slice(&1u32.to_le_bytes());
+    file.extend_from_slice(&address.to_le_bytes());
+    file.extend_from_slice(&52u32.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&52u16.to_le_bytes());
+    file.extend_from_slice(&32u16.to_le_bytes());
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&40u16.to_le_bytes());
+    file.extend_from_slice(&0u16.to_le_bytes());
+    file.extend_from_slice(&0u16.to_le_bytes());
+
+    // Program header.
+    for word in [1, 84, address, address, data.len() as u32, data.len() as u32, 5, 4].iter() {
+        file.extend_from_slice(&word.to_le_bytes());
+    }
+
+    file.extend_from_slice(data);
+
+    Arc::new( ElfFile::parse(PathBuf::from("test.elf"), file).expect("Could not parse the test ELF") )
+}
+
+
+
+#[test]
+fn memory_roundtrip() {
+    session(SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x100), |channel| async move {
+        match request(channel.clone(), Command::WriteU32(RAM, 0xDEADBEEF)).await {
+            Response::Written(RAM, 4, mismatches) => assert!(mismatches.is_empty()),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM)).await, Response::U32(0xDEADBEEF)));
+        assert!(matches!(request(channel.clone(), Command::Read(Datatype::UInt16, RAM + 2)).await, Response::Value(Value::U16(0xDEAD))));
+
+        // Unaligned range with head, aligned body and tail.
+        let data: Vec<u8> = (1..=9).collect();
+
+        match request(channel.clone(), Command::WriteRange(RAM + 0x11, data)).await {
+            Response::Written(_, 9, mismatches) => assert!(mismatches.is_empty()),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::ReadU8(RAM + 0x11)).await, Response::U8(1)));
+        assert!(matches!(request(channel.clone(), Command::ReadU64(RAM + 0x12)).await, Response::U64(0x0908070605040302)));
+    });
+}
+
+#[test]
+fn big_endian() {
+    endiansession(SimBackend::new(CoreType::Armv7m, 1).ram(RAM, 0x20), Endianness::Big, |channel| async move {
+        // The aligned word is stored most significant byte first.
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM + 4, 0x1122_3344)).await, Response::Written(_, 4, _)));
+        assert!(matches!(request(channel.clone(), Command::ReadU8(RAM + 4)).await, Response::U8(0x11)));
+        assert!(matches!(request(channel.clone(), Command::ReadU8(RAM + 7)).await, Response::U8(0x44)));
+
+        // Unaligned ranges with head, aligned body and tail keep the address order.
+        assert!(matches!(request(channel.clone(), Command::ReadU16(RAM + 5)).await, Response::U16(0x2233)));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM + 4)).await, Response::U32(0x1122_3344)));
+
+        let data: Vec<u8> = (1..=6).collect();
+        assert!(matches!(request(channel.clone(), Command::WriteRange(RAM + 0xA, data)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM + 0xC)).await, Response::U32(0x0304_0506)));
+    });
+}
+
+#[test]
+fn unmapped_memory() {
+    session(SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x100), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::ReadU32(ROM)).await, Response::Error(Error::ReadRange32Failed(ROM, _))));
+        assert!(matches!(request(channel.clone(), Command::ReadU8(RAM + 0x100)).await, Response::Error(Error::Read8Failed(_))));
+    });
+}
+
+#[test]
+fn scripted_halt() {
+    let halt = ScriptedHalt { polls: 3, reason: HaltReason::Breakpoint, pc: ROM + 0x100 };
+    let backend = SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x100).script(0, halt);
+
+    session(backend, |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+
+        // Memory and stepping require a halted core.
+        assert!(matches!(request(channel.clone(), Command::ReadU8(RAM)).await, Response::Error(Error::CoreNotHalted)));
+        assert!(matches!(request(channel.clone(), Command::Step).await, Response::Error(Error::CoreNotHalted)));
+
+        assert!(matches!(request(channel.clone(), Command::Status).await, Response::Status(CoreStatus::Halted(HaltReason::Breakpoint))));
+
+        match request(channel.clone(), Command::Registers).await {
+            Response::Registers(registers) => {
+                let pc = registers.iter().find(|r| r.name == "PC").expect("No PC register");
+                assert_eq!(pc.value, ROM + 0x100);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn step_after_reset() {
+    session(SimBackend::new(CoreType::Armv6m, 1).resetpc(0, ROM), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::ResetAndHalt).await, Response::Status(CoreStatus::Halted(_))));
+        assert!(matches!(request(channel.clone(), Command::Step).await, Response::Status(CoreStatus::Halted(HaltReason::Step))));
+
+        match request(channel.clone(), Command::Registers).await {
+            Response::Registers(registers) => {
+                let pc = registers.iter().find(|r| r.name == "PC").expect("No PC register");
+                assert_eq!(pc.value, ROM + 2);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn registers() {
+    session(SimBackend::new(CoreType::Armv6m, 1), |channel| async move {
+        match request(channel.clone(), Command::WriteRegister(3, 0x1234)).await {
+            Response::Register(register) => {
+                assert_eq!(register.name, "R3");
+                assert_eq!(register.value, 0x1234);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(99, 1)).await, Response::Error(Error::UnknownRegister(99))));
+    });
+}
+
+#[test]
+fn breakpoint_units() {
+    session(SimBackend::new(CoreType::Armv6m, 1).breakpoints(2), |channel| async move {
+        match request(channel.clone(), Command::SetBreakpoint(0x100)).await {
+            Response::Breakpoints(2, active) => assert_eq!(active, vec![0x100]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::SetBreakpoint(0x200)).await, Response::Breakpoints(2, _)));
+        assert!(matches!(request(channel.clone(), Command::SetBreakpoint(0x300)).await, Response::Error(Error::BreakpointFailed(0x300))));
+
+        match request(channel.clone(), Command::ClearBreakpoint(0x100)).await {
+            Response::Breakpoints(2, active) => assert_eq!(active, vec![0x200]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn core_selection() {
+    session(SimBackend::new(CoreType::Armv6m, 2), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::SelectCore(1)).await, Response::Cores(1, _)));
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+
+        match request(channel.clone(), Command::Cores).await {
+            Response::Cores(1, cores) => {
+                assert_eq!(cores.len(), 2);
+                assert!(matches!(cores[0].status, Some(CoreStatus::Halted(_))));
+                assert!(matches!(cores[1].status, Some(CoreStatus::Running)));
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::SelectCore(2)).await, Response::Error(Error::CoreNotFound(2))));
+    });
+}
+
+#[test]
+fn flash_and_verify() {
+    // The image crosses a block boundary.
+    let address = ROM + FLASHCHUNK - 0x10;
+    let data: Vec<u8> = (0..0x20).collect();
+
+    let image = elf(address, &data);
+
+    let mut modified = data.clone();
+    modified[4] = 0xAA;
+    modified[5] = 0xBB;
+
+    let stale = elf(address, &modified);
+
+    session(SimBackend::new(CoreType::Armv6m, 1).rom(ROM, 2 * FLASHCHUNK), |channel| async move {
+        let (handle, mut events) = FlashHandle::new();
+
+        match request(channel.clone(), Command::Flash(image, handle)).await {
+            Response::Flashed(0x20, mismatches) => assert!(mismatches.is_empty()),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        let mut verified = 0;
+
+        while let Ok(event) = events.try_recv() {
+            if let FlashEvent::Verified(n) = event {
+                verified += n;
+            }
+        }
+
+        assert_eq!(verified, 0x20);
+
+        match request(channel.clone(), Command::Verify(stale)).await {
+            Response::Verified(mismatches) => assert_eq!(mismatches, vec![ Mismatch { address: address + 4, size: 2, section: None } ]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn flash_cancel() {
+    let image = elf(ROM, &[0u8; 16]);
+
+    session(SimBackend::new(CoreType::Armv6m, 1).rom(ROM, FLASHCHUNK), |channel| async move {
+        let (handle, _events) = FlashHandle::new();
+        handle.cancel();
+
+        assert!(matches!(request(channel.clone(), Command::Flash(image, handle)).await, Response::Error(Error::FlashCancelled)));
+    });
+}
+
+#[test]
+fn rtt() {
+    let control = RAM + 0x100;
+    let (up, down) = (RAM + 0x300, RAM + 0x380);
+    let (updesc, downdesc) = (control + 24, control + 48);
+
+    // Control block with a named up channel and an unnamed down channel.
+    let mut block = b"SEGGER RTT\0\0\0\0\0\0".to_vec();
+
+    for word in [1, 1, RAM + 0x200, up, 16, 0, 0, 0, 0, down, 8, 0, 0, 0].iter() {
+        block.extend_from_slice(&word.to_le_bytes());
+    }
+
+    session(SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x1000), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::WriteRange(RAM + 0x200, b"Terminal\0".to_vec())).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteRange(control, block)).await, Response::Written(..)));
+
+        let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+
+        // The control block is found by scanning the RAM.
+        match request(channel.clone(), Command::RttAttach(None, tx)).await {
+            Response::Rtt(rtt) => {
+                assert_eq!(rtt.address, control);
+                assert_eq!(rtt.up.len(), 1);
+                assert_eq!(rtt.up[0].name.as_deref(), Some("Terminal"));
+                assert_eq!(rtt.up[0].size, 16);
+                assert_eq!(rtt.down.len(), 1);
+                assert_eq!(rtt.down[0].name, None);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        // Data wrapping around the end of the up buffer.
+        assert!(matches!(request(channel.clone(), Command::WriteRange(up + 12, b"abcd".to_vec())).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteRange(up, b"ef".to_vec())).await, Response::Written(..)));
+
+        let mut offsets = 2u32.to_le_bytes().to_vec();
+        offsets.extend_from_slice(&12u32.to_le_bytes());
+        assert!(matches!(request(channel.clone(), Command::WriteRange(updesc + 12, offsets)).await, Response::Written(..)));
+
+        match events.recv().await {
+            Some(RttEvent::Data(0, data)) => assert_eq!(data, b"abcdef".to_vec()),
+            e => panic!("Unexpected event {:?}", e),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::ReadU32(updesc + 16)).await, Response::U32(2)));
+
+        // The down buffer keeps one byte free.
+        assert!(matches!(request(channel.clone(), Command::RttWrite(0, b"0123456789".to_vec())).await, Response::RttWritten(0, 7)));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(downdesc + 12)).await, Response::U32(7)));
+        assert!(matches!(request(channel.clone(), Command::ReadU8(down + 6)).await, Response::U8(b'6')));
+
+        assert!(matches!(request(channel.clone(), Command::RttWrite(3, Vec::new())).await, Response::Error(Error::RttChannelNotFound(3))));
+    });
+}
+
+#[test]
+fn semihosting() {
+    let bkpt = RAM + 0x10;
+    let halt = ScriptedHalt { polls: 0, reason: HaltReason::Breakpoint, pc: bkpt };
+    let backend = SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x100).script(0, halt);
+
+    session(backend, |channel| async move {
+        // SYS_WRITE0 of a string.
+        assert!(matches!(request(channel.clone(), Command::WriteRange(bkpt, vec![0xAB, 0xBE])).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteRange(RAM + 0x40, b"hello\0".to_vec())).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(0, 0x04)).await, Response::Register(_)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(1, RAM + 0x40)).await, Response::Register(_)));
+
+        let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+        let root = std::env::temp_dir().join("si4p-semihosting");
+
+        assert!(matches!(request(channel.clone(), Command::Semihosting(Some((root, tx)))).await, Response::Semihosting(true)));
+
+        // The request is serviced and the core resumed.
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(_)));
+        assert!(matches!(request(channel.clone(), Command::Status).await, Response::Status(CoreStatus::Running)));
+
+        match events.recv().await {
+            Some(SemihostEvent::Output(data)) => assert_eq!(data, b"hello".to_vec()),
+            e => panic!("Unexpected event {:?}", e),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::Halt).await, Response::Status(CoreStatus::Halted(_))));
+
+        match request(channel.clone(), Command::Registers).await {
+            Response::Registers(registers) => {
+                let pc = registers.iter().find(|r| r.name == "PC").expect("No PC register");
+                assert_eq!(pc.value, bkpt + 2);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn semihosting_sandbox() {
+    let (tx, _) = tokio::sync::mpsc::unbounded_channel();
+    let host = Semihosting::new(PathBuf::from("/project/files"), tx);
+
+    assert_eq!(host.sandbox(b"log.txt"), Some(PathBuf::from("/project/files/log.txt")));
+    assert_eq!(host.sandbox(b"out/./data.bin"), Some(PathBuf::from("/project/files/out/./data.bin")));
+    assert_eq!(host.sandbox(b"../secret"), None);
+    assert_eq!(host.sandbox(b"/etc/passwd"), None);
+    assert_eq!(host.sandbox(b""), None);
+}
+
+#[test]
+fn trace() {
+    // System control space of the trace units.
+    let backend = SimBackend::new(CoreType::Armv7em, 1)
+        .ram(0xE000_0000, 0x10000)
+        .ram(0xE004_0000, 0x1000)
+        .swo(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x03, b'o', b'k', b'\n', b'\0']);
+
+    session(backend, |channel| async move {
+        let (tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+
+        let config = TraceConfig { clock: 64_000_000, baud: 2_000_000, ports: 0x1, exceptions: true, pcsampling: false };
+
+        assert!(matches!(request(channel.clone(), Command::TraceStart(config, tx)).await, Response::Trace(true)));
+
+        // TPIU prescaler and enabled stimulus ports.
+        assert!(matches!(request(channel.clone(), Command::ReadU32(0xE004_0010)).await, Response::U32(31)));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(0xE000_0E00)).await, Response::U32(1)));
+
+        match events.recv().await {
+            Some(packets) => assert_eq!(packets, vec![
+                TracePacket::Sync,
+                TracePacket::Instrumentation { port: 0, payload: b"ok\n\0".to_vec() },
+            ]),
+            e => panic!("Unexpected packets {:?}", e),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::TraceStop).await, Response::Trace(false)));
+
+        // The baud rate must be reachable from the trace clock.
+        let (tx, _) = tokio::sync::mpsc::unbounded_channel();
+        let config = TraceConfig { clock: 1_000_000, baud: 2_000_000, ..config };
+
+        assert!(matches!(request(channel.clone(), Command::TraceStart(config, tx)).await, Response::Error(Error::InvalidTraceClock(..))));
+    });
+}
+
+#[test]
+fn live_watch() {
+    session(SimBackend::new(CoreType::Armv7em, 1).ram(RAM, 0x100), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM, 0xCAFE_F00D)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteI16(RAM + 4, -2)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+
+        // Plain reads need a halted core.
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM)).await, Response::Error(Error::CoreNotHalted)));
+
+        let (tx, mut samples) = tokio::sync::mpsc::unbounded_channel();
+        let vars = vec![ (RAM, Datatype::UInt32), (RAM + 4, Datatype::Int16), (0xFFFF_FFF0, Datatype::UInt32) ];
+
+        assert!(matches!(request(channel.clone(), Command::LiveStart(vars, Duration::from_millis(1), tx)).await, Response::Live(true)));
+
+        // Unmapped variables fail without stopping the sampling.
+        for _ in 0..2 {
+            let sample = samples.recv().await.expect("The live watch stopped");
+
+            assert!(matches!(sample.values[0], Ok(Value::U32(0xCAFE_F00D))));
+            assert!(matches!(sample.values[1], Ok(Value::I16(-2))));
+            assert!(sample.values[2].is_err());
+        }
+
+        assert!(matches!(request(channel.clone(), Command::LiveStop).await, Response::Live(false)));
+    });
+}
+
+#[test]
+fn live_watch_unsupported() {
+    session(SimBackend::new(CoreType::Riscv, 1).ram(RAM, 0x100), |channel| async move {
+        let (tx, _) = tokio::sync::mpsc::unbounded_channel();
+
+        match request(channel.clone(), Command::LiveStart(vec![ (RAM, Datatype::UInt8) ], Duration::from_millis(10), tx)).await {
+            Response::Error(Error::NoBackgroundAccess(0)) => (),
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+}
+
+#[test]
+fn words() {
+    session(SimBackend::new(CoreType::Armv7em, 1).ram(RAM, 0x20), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM + 4, 0x1122_3344)).await, Response::Written(..)));
+
+        // Unaligned addresses read the word that holds them.
+        match request(channel.clone(), Command::ReadWords(vec![ RAM, RAM + 6 ])).await {
+            Response::Words(words) => assert_eq!(words, vec![ (RAM, 0), (RAM + 4, 0x1122_3344) ]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::ReadWords(vec![ RAM + 0x40 ])).await, Response::Error(_)));
+
+        // Only the bits outside of the mask change.
+        match request(channel.clone(), Command::Modify(RAM + 4, 0xFFFF_00FF, 0xFFFF_AAFF)).await {
+            Response::Words(words) => assert_eq!(words, vec![ (RAM + 4, 0x1122_AA44) ]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        // Without kept bits the word is written and not read back.
+        match request(channel.clone(), Command::Modify(RAM + 8, 0, 0x0000_0010)).await {
+            Response::Words(words) => assert!(words.is_empty()),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM + 8)).await, Response::U32(0x10)));
+
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+        assert!(matches!(request(channel.clone(), Command::Modify(RAM + 4, 1, 1)).await, Response::Error(Error::CoreNotHalted)));
+    });
+}
+
+#[test]
+fn snapshot() {
+    session(SimBackend::new(CoreType::Armv6m, 1).ram(RAM, 0x40).ram(RAM + 0x100, 0x10), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM + 0x104, 0x0403_0201)).await, Response::Written(..)));
+
+        // All the RAM regions.
+        match request(channel.clone(), Command::Snapshot(None)).await {
+            Response::Snapshot(regions) => {
+                assert_eq!(regions.iter().map(|(a, d)| (*a, d.len())).collect::<Vec<_>>(), vec![ (RAM, 0x40), (RAM + 0x100, 0x10) ]);
+                assert_eq!(&regions[1].1[4..8], &[1, 2, 3, 4]);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        // An unaligned range.
+        match request(channel.clone(), Command::Snapshot(Some(vec![ (RAM + 0x105, RAM + 0x107) ]))).await {
+            Response::Snapshot(regions) => assert_eq!(regions, vec![ (RAM + 0x105, vec![2, 3]) ]),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+        assert!(matches!(request(channel.clone(), Command::Snapshot(None)).await, Response::Error(Error::CoreNotHalted)));
+    });
+}
+
+#[test]
+fn coredump() {
+    let mut saved = None;
+    let slot = &mut saved;
+
+    session(SimBackend::new(CoreType::Armv7em, 1).ram(RAM, 0x40), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM + 8, 0x1234_5678)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(0, 0xAB)).await, Response::Register(_)));
+
+        match request(channel.clone(), Command::CoreDump).await {
+            Response::CoreDump(dump) => *slot = Some(dump),
+            r => panic!("Unexpected response {:?}", r),
+        }
+    });
+
+    // The dump survives its file format.
+    let dump = saved.expect("The core was not dumped");
+    let dump = CoreDump::parse(&dump.serialize()).expect("Could not parse the core dump");
+
+    assert_eq!(dump.regions.len(), 1);
+
+    session(DumpBackend::new(dump), |channel| async move {
+        assert!(matches!(request(channel.clone(), Command::Status).await, Response::Status(CoreStatus::Halted(_))));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(RAM + 8)).await, Response::U32(0x1234_5678)));
+
+        match request(channel.clone(), Command::Registers).await {
+            Response::Registers(registers) => assert_eq!(registers.iter().find(|r| r.address == 0).map(|r| r.value), Some(0xAB)),
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        // The virtual target is read-only.
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Error(_)));
+        assert!(matches!(request(channel.clone(), Command::WriteU8(RAM, 1)).await, Response::Error(_)));
+        assert!(matches!(request(channel.clone(), Command::ReadU32(ROM)).await, Response::Error(_)));
+    });
+}
+
+#[test]
+fn backtrace() {
+    session(SimBackend::new(CoreType::Armv7m, 1).ram(RAM, 0x40), |channel| async move {
+        let elf = elf(ROM, &[0; 16]);
+
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(13, RAM + 0x20)).await, Response::Register(_)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(15, ROM + 4)).await, Response::Register(_)));
+
+        // Without symbols the unwinding stops at the halted frame.
+        match request(channel.clone(), Command::Backtrace(elf.clone())).await {
+            Response::Backtrace(frames) => {
+                assert_eq!(frames.len(), 1);
+                assert_eq!((frames[0].pc, frames[0].sp, frames[0].kind), (ROM + 4, RAM + 0x20, FrameKind::Halted));
+                assert_eq!(frames[0].function, None);
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+        assert!(matches!(request(channel.clone(), Command::Backtrace(elf)).await, Response::Error(Error::CoreNotHalted)));
+    });
+}
+
+#[test]
+fn fault() {
+    // The fault status registers are plain memory in the simulated target.
+    session(SimBackend::new(CoreType::Armv7m, 1).ram(RAM, 0x40).ram(super::fault::CFSR, 0x18), |channel| async move {
+        // Not in a fault handler.
+        assert!(matches!(request(channel.clone(), Command::Fault(None)).await, Response::Fault(None)));
+
+        // Forced precise bus fault with a valid address.
+        assert!(matches!(request(channel.clone(), Command::WriteU32(super::fault::CFSR, (1 << 9) | (1 << 15))).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteU32(super::fault::HFSR, 1 << 30)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteU32(super::fault::BFAR, 0x4000_1000)).await, Response::Written(..)));
+
+        // LDR r0, [r1] stacked on the main stack at the entry of HardFault.
+        assert!(matches!(request(channel.clone(), Command::WriteU16(RAM + 0x10, 0x6808)).await, Response::Written(..)));
+        assert!(matches!(request(channel.clone(), Command::WriteU32(RAM + 0x38, RAM + 0x10)).await, Response::Written(..)));
+
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(13, RAM + 0x20)).await, Response::Register(_)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(14, 0xFFFF_FFF9)).await, Response::Register(_)));
+        assert!(matches!(request(channel.clone(), Command::WriteRegister(16, 3)).await, Response::Register(_)));
+
+        match request(channel.clone(), Command::Fault(None)).await {
+            Response::Fault(Some(fault)) => {
+                assert_eq!((fault.exception, fault.pc, fault.access), (3, RAM + 0x10, Some(Access::Read)));
+                assert_eq!(fault.frame.map(|f| f.address), Some(RAM + 0x20));
+                assert_eq!(fault.explain()[0], format!("Precise bus fault reading 0x40001000 at PC 0x{:08X}", RAM + 0x10));
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        assert!(matches!(request(channel.clone(), Command::Run).await, Response::Status(CoreStatus::Running)));
+        assert!(matches!(request(channel.clone(), Command::Fault(None)).await, Response::Error(Error::CoreNotHalted)));
+    });
+}
@@ -0,0 +1,445 @@
+//! SWO trace of the Cortex-M ITM and DWT units.
+//! Configures the trace units of the target and decodes the ITM packet stream.
+
+
+
+use std::path::Path;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+
+
+/// ITM trace enable register.
+pub(super) const ITM_TER: u32 = 0xE000_0E00;
+
+/// ITM trace privilege register.
+pub(super) const ITM_TPR: u32 = 0xE000_0E40;
+
+/// ITM trace control register.
+pub(super) const ITM_TCR: u32 = 0xE000_0E80;
+
+/// ITM lock access register.
+pub(super) const ITM_LAR: u32 = 0xE000_0FB0;
+
+/// Key that unlocks the ITM registers.
+pub(super) const ITM_LAR_KEY: u32 = 0xC5AC_CE55;
+
+/// ITM enable, local timestamps, synchronization packets and DWT forwarding on trace bus 1.
+pub(super) const ITM_TCR_ENABLE: u32 = (1 << 16) | (1 << 3) | (1 << 2) | (1 << 1) | (1 << 0);
+
+/// Exception trace enable bit of the DWT control register.
+pub(super) const DWT_CTRL_EXCTRCENA: u32 = 1 << 16;
+
+/// PC sampling enable bit of the DWT control register.
+pub(super) const DWT_CTRL_PCSAMPLENA: u32 = 1 << 12;
+
+/// Synchronization tap of the DWT control register.
+pub(super) const DWT_CTRL_SYNCTAP: u32 = 0b01 << 10;
+
+/// Post counter reload for a PC sample every 1024 cycles.
+pub(super) const DWT_CTRL_POSTPRESET: u32 = 0xF << 1;
+
+/// Synchronization tap, cycle counter tap and post counter reload fields of the DWT control register.
+pub(super) const DWT_CTRL_TAPS: u32 = (0b11 << 10) | (1 << 9) | (0xF << 1);
+
+/// Cycle counter enable bit of the DWT control register.
+pub(super) const DWT_CTRL_CYCCNTENA: u32 = 1 << 0;
+
+/// TPIU asynchronous clock prescaler register.
+pub(super) const TPIU_ACPR: u32 = 0xE004_0010;
+
+/// TPIU selected pin protocol register.
+pub(super) const TPIU_SPPR: u32 = 0xE004_00F0;
+
+/// Asynchronous NRZ (UART) pin protocol.
+pub(super) const TPIU_SPPR_NRZ: u32 = 0b10;
+
+/// TPIU formatter and flush control register.
+pub(super) const TPIU_FFCR: u32 = 0xE004_0304;
+
+/// Formatter bypass, continuous mode off.
+pub(super) const TPIU_FFCR_BYPASS: u32 = 0x100;
+
+
+
+/// Channel to send the decoded trace packets.
+pub type TraceSender = UnboundedSender<Vec<TracePacket>>;
+
+
+
+/// Configuration of a trace capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// Frequency of the trace clock in Hz, usually the core clock.
+    pub clock: u32,
+
+    /// Baud rate of the SWO pin.
+    pub baud: u32,
+
+    /// Mask of the enabled ITM stimulus ports.
+    pub ports: u32,
+
+    /// Enables the DWT exception trace.
+    pub exceptions: bool,
+
+    /// Enables the DWT PC sampling.
+    pub pcsampling: bool,
+}
+
+impl TraceConfig {
+    /// Returns the prescaler of the TPIU for the baud rate.
+    pub(super) fn prescaler(&self) -> Option<u32> {
+        match self.baud {
+            0 => None,
+            baud => (self.clock / baud).checked_sub(1),
+        }
+    }
+}
+
+
+
+/// Action of the core on an exception.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionAction {
+    Entered,
+    Exited,
+    Returned,
+}
+
+impl core::fmt::Display for ExceptionAction {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match *self {
+            ExceptionAction::Entered  => write!(f, "entered"),
+            ExceptionAction::Exited   => write!(f, "exited"),
+            ExceptionAction::Returned => write!(f, "returned to"),
+        }
+    }
+}
+
+
+
+/// A packet of the ITM stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TracePacket {
+    /// Synchronization packet.
+    Sync,
+
+    /// The ITM or DWT dropped packets.
+    Overflow,
+
+    /// Software write to an ITM stimulus port.
+    Instrumentation { port: u8, payload: Vec<u8> },
+
+    /// Cycles since the previous local timestamp.
+    LocalTimestamp { delta: u32, delayed: bool },
+
+    /// Bits of the global timestamp. `high` marks the upper bits.
+    GlobalTimestamp { high: bool, value: u64 },
+
+    /// Exception entry, exit or return.
+    Exception { number: u16, action: ExceptionAction },
+
+    /// Periodic sample of the PC, `None` if the core was sleeping.
+    PcSample(Option<u32>),
+
+    /// Wrap of the DWT event counters.
+    EventCounter(u8),
+
+    /// Data trace of a DWT comparator with its discriminator and value.
+    DataTrace { id: u8, value: u32 },
+
+    /// Extension packet with its page information.
+    Extension(u32),
+
+    /// Hardware source packet with an unknown discriminator.
+    Hardware { id: u8, value: u32 },
+
+    /// Byte that does not start a valid packet.
+    Invalid(u8),
+}
+
+impl core::fmt::Display for TracePacket {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            TracePacket::Sync => write!(f, "Synchronization"),
+            TracePacket::Overflow => write!(f, "Overflow, packets were lost"),
+            TracePacket::Instrumentation { port, payload } => write!(f, "ITM port {}: {:02X?}", port, payload),
+            TracePacket::LocalTimestamp { delta, .. } => write!(f, "Timestamp +{}", delta),
+            TracePacket::GlobalTimestamp { value, .. } => write!(f, "Global timestamp {}", value),
+            TracePacket::Exception { number, action } => write!(f, "Exception {} {}", exceptionname(*number), action),
+            TracePacket::PcSample(Some(pc)) => write!(f, "PC sample 0x{:08X}", pc),
+            TracePacket::PcSample(None) => write!(f, "PC sample (sleeping)"),
+            TracePacket::EventCounter(counters) => write!(f, "Event counter wrap 0b{:06b}", counters),
+            TracePacket::DataTrace { id, value } => write!(f, "Data trace {} 0x{:08X}", id, value),
+            TracePacket::Extension(page) => write!(f, "Extension {}", page),
+            TracePacket::Hardware { id, value } => write!(f, "Hardware source {} 0x{:08X}", id, value),
+            TracePacket::Invalid(byte) => write!(f, "Invalid byte 0x{:02X}", byte),
+        }
+    }
+}
+
+/// Returns the name of a Cortex-M exception.
+pub fn exceptionname(number: u16) -> String {
+    match number {
+        0  => String::from("Thread"),
+        1  => String::from("Reset"),
+        2  => String::from("NMI"),
+        3  => String::from("HardFault"),
+        4  => String::from("MemManage"),
+        5  => String::from("BusFault"),
+        6  => String::from("UsageFault"),
+        7  => String::from("SecureFault"),
+        11 => String::from("SVCall"),
+        12 => String::from("DebugMonitor"),
+        14 => String::from("PendSV"),
+        15 => String::from("SysTick"),
+        n if n >= 16 => format!("IRQ {}", n - 16),
+        n => format!("Exception {}", n),
+    }
+}
+
+
+
+/// Streaming decoder of the ITM protocol.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    /// Bytes of an incomplete packet.
+    pending: Vec<u8>,
+
+    /// Number of consecutive zero bytes.
+    zeros: usize,
+}
+
+impl Decoder {
+    /// Creates a decoder, packets are decoded from the first byte.
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    /// Decodes a raw capture stored in a file.
+    pub async fn file(path: &Path) -> Result<Vec<TracePacket>, String> {
+        match tokio::fs::read(path).await {
+            Ok(data) => Ok( Decoder::new().push(&data) ),
+            Err(e) => Err( format!("Could not read trace capture {}: {}", path.display(), e) ),
+        }
+    }
+
+    /// Decodes the bytes of the stream. Incomplete packets are kept for the next bytes.
+    pub fn push(&mut self, data: &[u8]) -> Vec<TracePacket> {
+        let mut packets = Vec::new();
+
+        for byte in data.iter() {
+            // Synchronization is a run of at least 47 zero bits followed by a one.
+            if self.pending.is_empty() {
+                match (*byte, self.zeros) {
+                    (0x00, _) => {
+                        self.zeros += 1;
+                        continue;
+                    },
+
+                    (0x80, z) if z >= 5 => {
+                        self.zeros = 0;
+                        packets.push( TracePacket::Sync );
+                        continue;
+                    },
+
+                    _ => self.zeros = 0,
+                }
+            }
+
+            self.pending.push(*byte);
+
+            if let Some(packet) = Self::packet(&self.pending) {
+                packets.push(packet);
+                self.pending.clear();
+            }
+        }
+
+        packets
+    }
+
+    /// Decodes a packet from its bytes. Returns `None` if more bytes are required.
+    fn packet(bytes: &[u8]) -> Option<TracePacket> {
+        let header = bytes[0];
+
+        // Source packets.
+        if (header & 0b11) != 0 {
+            let size = match header & 0b11 {
+                0b01 => 1,
+                0b10 => 2,
+                _    => 4,
+            };
+
+            if bytes.len() < size + 1 { return None }
+
+            let payload = &bytes[1..];
+            let id = header >> 3;
+
+            if (header & 0b100) == 0 {
+                return Some( TracePacket::Instrumentation { port: id, payload: payload.to_vec() } );
+            }
+
+            let value = payload.iter().rev().fold(0u32, |v, b| (v << 8) | (*b as u32));
+
+            return Some( match (id, size) {
+                (0, 1) => TracePacket::EventCounter(payload[0]),
+
+                (1, 2) => {
+                    let action = match (payload[1] >> 4) & 0b11 {
+                        0b01 => ExceptionAction::Entered,
+                        0b10 => ExceptionAction::Exited,
+                        _    => ExceptionAction::Returned,
+                    };
+
+                    TracePacket::Exception { number: (value & 0x1FF) as u16, action }
+                },
+
+                (2, 1) => TracePacket::PcSample(None),
+                (2, 4) => TracePacket::PcSample(Some(value)),
+
+                (8..=23, _) => TracePacket::DataTrace { id, value },
+
+                _ => TracePacket::Hardware { id, value },
+            });
+        }
+
+        match header {
+            0x70 => Some( TracePacket::Overflow ),
+
+            // Local timestamp in the header.
+            h if ((h & 0x8F) == 0) && (h != 0) => Some( TracePacket::LocalTimestamp { delta: ((h >> 4) & 0b111) as u32, delayed: false } ),
+
+            // Local timestamp with continuation bytes.
+            h if (h & 0xCF) == 0xC0 => {
+                let value = Self::continuation(&bytes[1..], 4)?;
+                Some( TracePacket::LocalTimestamp { delta: value as u32, delayed: ((h >> 4) & 0b11) != 0 } )
+            },
+
+            // Global timestamps.
+            0x94 => Self::continuation(&bytes[1..], 4).map(|value| TracePacket::GlobalTimestamp { high: false, value }),
+            0xB4 => Self::continuation(&bytes[1..], 5).map(|value| TracePacket::GlobalTimestamp { high: true, value }),
+
+            // Extension.
+            h if (h & 0x0B) == 0x08 => match (h & 0x80) != 0 {
+                true => Self::continuation(&bytes[1..], 4).map(|value| TracePacket::Extension( ((value as u32) << 3) | ((h >> 4) & 0b111) as u32 )),
+                _ => Some( TracePacket::Extension( ((h >> 4) & 0b111) as u32 ) ),
+            },
+
+            h => Some( TracePacket::Invalid(h) ),
+        }
+    }
+
+    /// Decodes a little endian value of 7 bit groups, terminated by a byte without the continuation bit.
+    /// Returns `None` if the value is incomplete.
+    fn continuation(bytes: &[u8], max: usize) -> Option<u64> {
+        let end = bytes.iter().position(|b| (b & 0x80) == 0)
+            .or(match bytes.len() >= max { true => Some(max - 1), _ => None })?;
+
+        let value = bytes[..=end].iter().rev()
+            .fold(0u64, |v, b| (v << 7) | (b & 0x7F) as u64);
+
+        Some( value )
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_and_overflow() {
+        let mut decoder = Decoder::new();
+
+        assert_eq!(decoder.push(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x70]), vec![ TracePacket::Sync, TracePacket::Overflow ]);
+    }
+
+    #[test]
+    fn instrumentation() {
+        let mut decoder = Decoder::new();
+
+        // 1 byte write to port 0 and 4 byte write to port 3.
+        let packets = decoder.push(&[0x01, b'H', 0x1B, 0x01, 0x02, 0x03, 0x04]);
+
+        assert_eq!(packets, vec![
+            TracePacket::Instrumentation { port: 0, payload: vec![b'H'] },
+            TracePacket::Instrumentation { port: 3, payload: vec![0x01, 0x02, 0x03, 0x04] },
+        ]);
+    }
+
+    #[test]
+    fn split_packets() {
+        let mut decoder = Decoder::new();
+
+        // A packet split across reads of the probe.
+        assert_eq!(decoder.push(&[0x02, b'o']), vec![]);
+        assert_eq!(decoder.push(&[b'k']), vec![ TracePacket::Instrumentation { port: 0, payload: b"ok".to_vec() } ]);
+    }
+
+    #[test]
+    fn hardware() {
+        let mut decoder = Decoder::new();
+
+        let packets = decoder.push(&[
+            // Exception 15 (SysTick) entered and returned.
+            0x0E, 0x0F, 0x10,
+            0x0E, 0x0F, 0x30,
+            // PC sample and sleeping sample.
+            0x17, 0x34, 0x12, 0x00, 0x08,
+            0x15, 0x00,
+        ]);
+
+        assert_eq!(packets, vec![
+            TracePacket::Exception { number: 15, action: ExceptionAction::Entered },
+            TracePacket::Exception { number: 15, action: ExceptionAction::Returned },
+            TracePacket::PcSample(Some(0x0800_1234)),
+            TracePacket::PcSample(None),
+        ]);
+
+        // Exception numbers use 9 bits.
+        assert_eq!(decoder.push(&[0x0E, 0x10, 0x21]), vec![ TracePacket::Exception { number: 0x110, action: ExceptionAction::Exited } ]);
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut decoder = Decoder::new();
+
+        let packets = decoder.push(&[
+            // Short local timestamp.
+            0x30,
+            // Local timestamp of 300 cycles in two bytes.
+            0xC0, 0xAC, 0x02,
+            // Global timestamp.
+            0x94, 0x81, 0x01,
+        ]);
+
+        assert_eq!(packets, vec![
+            TracePacket::LocalTimestamp { delta: 3, delayed: false },
+            TracePacket::LocalTimestamp { delta: 300, delayed: false },
+            TracePacket::GlobalTimestamp { high: false, value: 129 },
+        ]);
+    }
+
+    #[test]
+    fn capture_file() {
+        let path = std::env::temp_dir().join("si4p-trace-capture.bin");
+
+        std::fs::write(&path, &[0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, b'A', 0x70, 0xFF])
+            .expect("Could not write the test capture");
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Could not build the test runtime");
+
+        let packets = runtime.block_on( Decoder::file(&path) ).expect("Could not decode the test capture");
+
+        assert_eq!(packets, vec![
+            TracePacket::Sync,
+            TracePacket::Instrumentation { port: 0, payload: vec![b'A'] },
+            TracePacket::Overflow,
+        ]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
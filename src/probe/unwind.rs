@@ -0,0 +1,451 @@
+//! Call stack unwinding of ARM Cortex-M cores.
+//! The frames are unwound with the `.debug_frame` CFI of the ELF. Functions
+//! without CFI fall back to the link register for the halted frame and to a
+//! scan of the stack for return addresses otherwise. Exception frames are
+//! crossed through the registers stacked by the hardware.
+
+
+
+use addr2line::Context;
+
+use crate::elf::{ ElfFile, SymbolKind };
+
+use gimli::{
+    BaseAddresses, CfaRule, DebugFrame, EndianRcSlice, EndianSlice, LittleEndian, Register, RegisterRule,
+    RunTimeEndian, UnwindContext, UnwindSection,
+};
+
+
+
+/// Maximum number of unwound frames.
+const MAXFRAMES: usize = 64;
+
+/// Number of stack words scanned for a return address.
+const SCANWORDS: u32 = 256;
+
+/// Stack pointer.
+const SP: usize = 13;
+
+/// Link register.
+const LR: usize = 14;
+
+/// Program counter.
+const PC: usize = 15;
+
+
+
+/// A frame of the call stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Address of the instruction being executed in this frame.
+    pub pc: u32,
+
+    /// Stack pointer of the frame.
+    pub sp: u32,
+
+    /// How the frame was found.
+    pub kind: FrameKind,
+
+    /// Function of the frame and offset of the PC in it.
+    pub function: Option<String>,
+
+    /// Source file and line of the PC.
+    pub location: Option<String>,
+}
+
+impl core::fmt::Display for Frame {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if let FrameKind::Exception(n) = self.kind {
+            write!(f, "<exception {}> ", n)?;
+        }
+
+        write!(f, "0x{:08X} in {}", self.pc, self.function.as_deref().unwrap_or("??"))?;
+
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+
+        write!(f, " (SP 0x{:08X})", self.sp)?;
+
+        match self.kind {
+            FrameKind::Heuristic => write!(f, " [heuristic]"),
+            _ => Ok(()),
+        }
+    }
+}
+
+
+
+/// How a frame was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// The frame where the core halted.
+    Halted,
+
+    /// Unwound with the CFI of the callee.
+    Cfi,
+
+    /// Found without CFI, the frame may be wrong.
+    Heuristic,
+
+    /// Interrupted by the exception with the given number.
+    Exception(u16),
+}
+
+
+
+/// Registers of a frame, `None` if they could not be recovered.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Registers {
+    /// R0 to R15.
+    pub(super) r: [Option<u32>; 16],
+
+    /// Program status register.
+    pub(super) xpsr: u32,
+
+    /// Process stack pointer.
+    pub(super) psp: Option<u32>,
+}
+
+
+
+/// Unwinder of the call stack of a firmware.
+pub(super) struct Unwinder<'a> {
+    /// CFI of the firmware.
+    cfi: Option<DebugFrame<EndianSlice<'a, LittleEndian>>>,
+
+    /// Returns `true` if there is code at the address.
+    code: &'a dyn Fn(u32) -> bool,
+}
+
+impl<'a> Unwinder<'a> {
+    /// Creates an unwinder with the `.debug_frame` contents, if any.
+    pub(super) fn new(debugframe: Option<&'a [u8]>, code: &'a dyn Fn(u32) -> bool) -> Self {
+        let cfi = debugframe.map(|data| {
+            let mut cfi = DebugFrame::new(data, LittleEndian);
+            cfi.set_address_size(4);
+            cfi
+        });
+
+        Unwinder { cfi, code }
+    }
+
+    /// Unwinds the stack from the registers of the halted core.
+    pub(super) fn unwind(&self, registers: Registers, read: &mut dyn FnMut(u32) -> Option<u32>) -> Vec<Frame> {
+        let mut frames = Vec::new();
+
+        let mut regs = registers;
+        let mut kind = FrameKind::Halted;
+
+        while frames.len() < MAXFRAMES {
+            let (pc, sp) = match (regs.r[PC], regs.r[SP]) {
+                (Some(pc), Some(sp)) => (pc & !1, sp),
+                _ => break,
+            };
+
+            frames.push( Frame { pc, sp, kind, function: None, location: None } );
+
+            // Return addresses point after the call.
+            let lookup = match kind {
+                FrameKind::Halted | FrameKind::Exception(_) => pc,
+                _ => pc.wrapping_sub(1),
+            };
+
+            let (mut caller, mut next) = match self.cfi(&regs, lookup, read) {
+                Some(caller) => (caller, FrameKind::Cfi),
+
+                // A halted leaf function has the return address in LR.
+                None if kind == FrameKind::Halted => {
+                    let mut caller = regs;
+                    caller.r[PC] = regs.r[LR];
+                    (caller, FrameKind::Heuristic)
+                },
+
+                None => match self.scan(sp, read) {
+                    Some((pc, sp)) => {
+                        let mut caller = Registers { r: [None; 16], ..regs };
+                        caller.r[PC] = Some(pc);
+                        caller.r[SP] = Some(sp);
+                        (caller, FrameKind::Heuristic)
+                    },
+                    _ => break,
+                },
+            };
+
+            // Cross the exception frame stacked by the hardware.
+            if let Some(excreturn) = caller.r[PC].filter(|pc| excreturn(*pc)) {
+                caller = match Self::unstack(&caller, excreturn, read) {
+                    Some(caller) => caller,
+                    _ => break,
+                };
+
+                next = FrameKind::Exception( (regs.xpsr & 0x1FF) as u16 );
+            }
+
+            // Stop at the reset value of LR, outside the code or without progress.
+            match (caller.r[PC], caller.r[SP]) {
+                (Some(pc), Some(sp)) if (self.code)(pc & !1) && ((sp > regs.r[SP].unwrap_or(0)) || (pc & !1) != (regs.r[PC].unwrap_or(0) & !1)) => (),
+                _ => break,
+            }
+
+            regs = caller;
+            kind = next;
+        }
+
+        frames
+    }
+
+    /// Recovers the registers of the caller with the CFI of the function at the address.
+    fn cfi(&self, regs: &Registers, address: u32, read: &mut dyn FnMut(u32) -> Option<u32>) -> Option<Registers> {
+        let cfi = self.cfi.as_ref()?;
+
+        let bases = BaseAddresses::default();
+        let mut ctx = UnwindContext::new();
+
+        let row = cfi.unwind_info_for_address(&bases, &mut ctx, address as u64, DebugFrame::cie_from_offset).ok()?;
+
+        let cfa = match row.cfa() {
+            CfaRule::RegisterAndOffset { register, offset } => regs.r.get(register.0 as usize).cloned()??.wrapping_add(*offset as u32),
+            _ => return None,
+        };
+
+        let mut caller = *regs;
+
+        // Registers without a rule were not modified by the function.
+        for n in 0..16 {
+            caller.r[n] = match row.register( Register(n as u16) ) {
+                RegisterRule::Undefined | RegisterRule::SameValue => regs.r[n],
+                RegisterRule::Offset(offset) => read( cfa.wrapping_add(offset as u32) ),
+                RegisterRule::ValOffset(offset) => Some( cfa.wrapping_add(offset as u32) ),
+                RegisterRule::Register(r) => regs.r.get(r.0 as usize).cloned().flatten(),
+                _ => None,
+            };
+        }
+
+        // The caller resumes at the return address with the stack of the call.
+        caller.r[PC] = caller.r[LR];
+        caller.r[SP] = Some(cfa);
+
+        Some( caller )
+    }
+
+    /// Scans the stack for a return address into the code.
+    /// Returns the return address and the stack pointer after it.
+    fn scan(&self, sp: u32, read: &mut dyn FnMut(u32) -> Option<u32>) -> Option<(u32, u32)> {
+        for i in 0..SCANWORDS {
+            let address = sp.checked_add(i * 4)?;
+            let word = read(address)?;
+
+            // Thumb return addresses have the lowest bit set.
+            if ((word & 1) == 1) && !excreturn(word) && (self.code)(word & !1) {
+                return Some( (word & !1, address + 4) );
+            }
+        }
+
+        None
+    }
+
+    /// Restores the registers stacked on exception entry.
+    fn unstack(regs: &Registers, excreturn: u32, read: &mut dyn FnMut(u32) -> Option<u32>) -> Option<Registers> {
+        // The frame is in the stack that was active before the exception.
+        // The main stack is the stack of the handler, already unwound to the frame.
+        let sp = match excreturn & (1 << 2) {
+            0 => regs.r[SP]?,
+            _ => regs.psp.or(regs.r[SP])?,
+        };
+
+        let mut stacked = [0u32; 8];
+
+        for (i, word) in stacked.iter_mut().enumerate() {
+            *word = read(sp + (i as u32 * 4))?;
+        }
+
+        let mut caller = *regs;
+
+        for (n, value) in [0, 1, 2, 3, 12, LR, PC].iter().zip(stacked.iter()) {
+            caller.r[*n] = Some(*value);
+        }
+
+        caller.xpsr = stacked[7];
+
+        // Extended frames hold the FP context, and the stack may have been realigned.
+        let size = match excreturn & (1 << 4) {
+            0 => 0x68,
+            _ => 0x20,
+        };
+
+        let align = match stacked[7] & (1 << 9) {
+            0 => 0,
+            _ => 4,
+        };
+
+        caller.r[SP] = Some( sp + size + align );
+
+        Some( caller )
+    }
+}
+
+/// Returns `true` if the address is an `EXC_RETURN` value.
+pub(super) fn excreturn(address: u32) -> bool {
+    (address & 0xFF00_0000) == 0xFF00_0000
+}
+
+
+
+/// Names code addresses with the symbols and the line table of an ELF.
+pub(super) struct Symbolizer<'a> {
+    /// ELF of the firmware.
+    elf: &'a ElfFile,
+
+    /// Line table of the ELF, if it has DWARF.
+    context: Option<Context<EndianRcSlice<RunTimeEndian>>>,
+}
+
+impl<'a> Symbolizer<'a> {
+    /// Creates a symbolizer of the ELF.
+    pub(super) fn new(elf: &'a ElfFile) -> Self {
+        // The line table is optional.
+        let context = object::File::parse(elf.data()).ok()
+            .and_then(|file| Context::new(&file).ok());
+
+        Symbolizer { elf, context }
+    }
+
+    /// Returns the function that contains the address and the offset of the PC in it.
+    pub(super) fn function(&self, address: u32, pc: u32) -> Option<String> {
+        self.elf.symbolat(address)
+            .filter(|s| s.kind == SymbolKind::Function)
+            .map(|s| format!("{}+0x{:X}", s.name, pc - s.address))
+    }
+
+    /// Returns the source file and line of the address.
+    pub(super) fn location(&self, address: u32) -> Option<(String, Option<u32>)> {
+        let location = self.context.as_ref()?.find_location(address as u64).ok()??;
+
+        Some( (String::from(location.file?), location.line) )
+    }
+}
+
+
+
+/// Unwinds the stack with the CFI of the ELF and names the frames.
+pub(super) fn backtrace(elf: &ElfFile, registers: Registers, read: &mut dyn FnMut(u32) -> Option<u32>) -> Vec<Frame> {
+    let code = |address: u32| elf.symbolat(address).map(|s| s.kind == SymbolKind::Function).unwrap_or(false);
+
+    let mut frames = Unwinder::new(elf.sectiondata(".debug_frame"), &code).unwind(registers, read);
+
+    let symbolizer = Symbolizer::new(elf);
+
+    for frame in frames.iter_mut() {
+        let lookup = match frame.kind {
+            FrameKind::Halted | FrameKind::Exception(_) => frame.pc,
+            _ => frame.pc.wrapping_sub(1),
+        };
+
+        frame.function = symbolizer.function(lookup, frame.pc);
+
+        frame.location = symbolizer.location(lookup)
+            .map(|(file, line)| match line {
+                Some(line) => format!("{}:{}", file, line),
+                _ => file,
+            });
+    }
+
+    frames
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    /// CFI of a function at 0x100 that pushes R7 and LR.
+    const DEBUGFRAME: [u8; 40] = [
+        // CIE: version 1, code alignment 2, data alignment -4, return address in R14, CFA = SP.
+        0x0C, 0x00, 0x00, 0x00,  0xFF, 0xFF, 0xFF, 0xFF,  0x01, 0x00, 0x02, 0x7C,  0x0E, 0x0C, 0x0D, 0x00,
+
+        // FDE of 0x100 - 0x140: after 2 bytes CFA = SP + 8, LR at CFA - 4, R7 at CFA - 8.
+        0x14, 0x00, 0x00, 0x00,  0x00, 0x00, 0x00, 0x00,  0x00, 0x01, 0x00, 0x00,  0x40, 0x00, 0x00, 0x00,
+        0x41, 0x0E, 0x08, 0x8E,  0x01, 0x87, 0x02, 0x00,
+    ];
+
+    /// Functions at 0x100, 0x200 and 0x300.
+    fn code(address: u32) -> bool {
+        [0x100, 0x200, 0x300].iter().any(|f| (address >= *f) && (address < *f + 0x40))
+    }
+
+    fn registers(pc: u32, sp: u32, lr: u32, xpsr: u32) -> Registers {
+        let mut r = [Some(0); 16];
+        r[PC] = Some(pc);
+        r[SP] = Some(sp);
+        r[LR] = Some(lr);
+        r[7] = Some(0x7777);
+
+        Registers { r, xpsr, psp: None }
+    }
+
+    fn frames(regs: Registers, memory: &[(u32, u32)]) -> Vec<(u32, u32, FrameKind)> {
+        let memory: HashMap<u32, u32> = memory.iter().cloned().collect();
+
+        Unwinder::new(Some(&DEBUGFRAME[..]), &code)
+            .unwind(regs, &mut |address| memory.get(&address).cloned())
+            .into_iter()
+            .map(|f| (f.pc, f.sp, f.kind))
+            .collect()
+    }
+
+    #[test]
+    fn cfi() {
+        let memory = [ (0x2000_0FF0, 0x1234), (0x2000_0FF4, 0x0000_0211), (0x2000_0FF8, 0xFFFF_FFFF) ];
+
+        assert_eq!(frames(registers(0x110, 0x2000_0FF0, 0, 0), &memory), vec![
+            (0x110, 0x2000_0FF0, FrameKind::Halted),
+            (0x210, 0x2000_0FF8, FrameKind::Cfi),
+        ]);
+
+        // Before the prologue the return address is still in LR.
+        assert_eq!(frames(registers(0x100, 0x2000_0FF0, 0x0000_0211, 0), &[]), vec![
+            (0x100, 0x2000_0FF0, FrameKind::Halted),
+            (0x210, 0x2000_0FF0, FrameKind::Cfi),
+        ]);
+    }
+
+    #[test]
+    fn exception() {
+        let memory = [
+            // Basic frame stacked by the SysTick exception, interrupting 0x120.
+            (0x2000_0F00, 0), (0x2000_0F04, 1), (0x2000_0F08, 2), (0x2000_0F0C, 3),
+            (0x2000_0F10, 12), (0x2000_0F14, 0x0000_0131), (0x2000_0F18, 0x0000_0120), (0x2000_0F1C, 0x0100_0000),
+
+            // Pushed by the function at 0x100.
+            (0x2000_0F20, 0x1234), (0x2000_0F24, 0x0000_0201),
+
+            // A value and a return address into 0x300 in the stack of 0x200, without CFI.
+            (0x2000_0F28, 0x1234_5678), (0x2000_0F2C, 0x0000_0305),
+        ];
+
+        // The handler at 0x300 has no CFI and returns to the interrupted code.
+        assert_eq!(frames(registers(0x300, 0x2000_0F00, 0xFFFF_FFF9, 15), &memory), vec![
+            (0x300, 0x2000_0F00, FrameKind::Halted),
+            (0x120, 0x2000_0F20, FrameKind::Exception(15)),
+            (0x200, 0x2000_0F28, FrameKind::Cfi),
+            (0x304, 0x2000_0F30, FrameKind::Heuristic),
+        ]);
+    }
+
+    #[test]
+    fn display() {
+        let frame = Frame {
+            pc: 0x0800_0120,
+            sp: 0x2000_0F20,
+            kind: FrameKind::Exception(15),
+            function: Some( String::from("main+0x20") ),
+            location: Some( String::from("src/main.rs:42") ),
+        };
+
+        assert_eq!(format!("{}", frame), "<exception 15> 0x08000120 in main+0x20 at src/main.rs:42 (SP 0x20000F20)");
+    }
+}
@@ -0,0 +1,104 @@
+//! Data watchpoints through the Cortex-M Data Watchpoint and Trace unit.
+
+
+
+/// DWT control register.
+pub(super) const DWT_CTRL: u32 = 0xE000_1000;
+
+/// Base of the DWT comparator registers.
+pub(super) const DWT_COMP: u32 = 0xE000_1020;
+
+/// Offset of the mask register from its comparator register.
+pub(super) const DWT_MASK_OFFSET: u32 = 0x04;
+
+/// Offset of the function register from its comparator register.
+pub(super) const DWT_FUNCTION_OFFSET: u32 = 0x08;
+
+/// Stride between DWT comparators.
+pub(super) const DWT_STRIDE: u32 = 0x10;
+
+/// Debug Exception and Monitor Control Register.
+pub(super) const DEMCR: u32 = 0xE000_EDFC;
+
+/// Trace enable bit of the DEMCR.
+pub(super) const DEMCR_TRCENA: u32 = 1 << 24;
+
+/// Matched bit of the DWT function registers.
+pub(super) const DWT_FUNCTION_MATCHED: u32 = 1 << 24;
+
+
+
+/// Kind of data access that triggers a watchpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl WatchKind {
+    /// Value of the DWT function register for ARMv6-M and ARMv7-M.
+    pub(super) fn v7function(&self) -> u32 {
+        match *self {
+            WatchKind::Read   => 0b0101,
+            WatchKind::Write  => 0b0110,
+            WatchKind::Access => 0b0111,
+        }
+    }
+
+    /// Value of the DWT function register for ARMv8-M.
+    /// Generates a debug event with the given access size.
+    pub(super) fn v8function(&self, size: u32) -> u32 {
+        let r#match = match *self {
+            WatchKind::Access => 0b0100,
+            WatchKind::Write  => 0b0101,
+            WatchKind::Read   => 0b0110,
+        };
+
+        let action = 0b01 << 4;
+        let datavsize = size.trailing_zeros() << 10;
+
+        datavsize | action | r#match
+    }
+}
+
+impl core::fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", match *self {
+            WatchKind::Read => "read",
+            WatchKind::Write => "write",
+            WatchKind::Access => "access",
+        })
+    }
+}
+
+pub const WATCHKINDS: [WatchKind; 3] = [
+    WatchKind::Read,
+    WatchKind::Write,
+    WatchKind::Access,
+];
+
+
+
+/// A watchpoint set in a DWT comparator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// Index of the DWT comparator.
+    pub comparator: usize,
+
+    /// Watched address.
+    pub address: u32,
+
+    /// Size in bytes of the watched range.
+    pub size: u32,
+
+    /// Kind of access watched.
+    pub kind: WatchKind,
+}
+
+impl Watchpoint {
+    /// Address of the comparator register of the watchpoint.
+    pub(super) fn comp(comparator: usize) -> u32 {
+        DWT_COMP + (comparator as u32 * DWT_STRIDE)
+    }
+}
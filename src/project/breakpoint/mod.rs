@@ -0,0 +1,47 @@
+//! Breakpoints of a project.
+
+
+
+use serde::{ Deserialize, Serialize };
+
+
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BreakpointInfo {
+    /// Location of the breakpoint.
+    pub location: Location,
+
+    /// Indicates if the breakpoint is set in the target.
+    pub enabled: bool,
+}
+
+impl BreakpointInfo {
+    /// Creates a new enabled breakpoint at the given location.
+    pub fn new(location: Location) -> Self {
+        BreakpointInfo {
+            location,
+            enabled: true,
+        }
+    }
+}
+
+
+
+/// Location of a breakpoint or watchpoint.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Location {
+    /// A raw address.
+    Address(u32),
+
+    /// An ELF symbol.
+    Symbol(String),
+}
+
+impl core::fmt::Display for Location {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Location::Address(address) => write!(f, "0x{:08X}", address),
+            Location::Symbol(name) => write!(f, "{}", name),
+        }
+    }
+}
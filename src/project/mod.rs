@@ -7,17 +7,25 @@
 
 
 
+mod breakpoint;
 mod info;
+mod snapshot;
 mod target;
+mod watch;
 
 
 
 use serde::{ Deserialize, Serialize };
 
+use std::path::PathBuf;
 
 
+
+pub use self::breakpoint::{ BreakpointInfo, Location };
 pub use self::info::ProjectInfo;
+pub use self::snapshot::{ Change, SnapshotInfo };
 pub use self::target::TargetInfo;
+pub use self::watch::WatchInfo;
 
 
 
@@ -28,6 +36,22 @@ pub struct ProjectSerial {
 
     /// All targets selectable by this project.
     pub targets: Vec<TargetInfo>,
+
+    /// Hardware breakpoints of the project.
+    #[serde(default)]
+    pub breakpoints: Vec<BreakpointInfo>,
+
+    /// Variables of the live watch.
+    #[serde(default)]
+    pub watches: Vec<WatchInfo>,
+
+    /// Named memory snapshots.
+    #[serde(default)]
+    pub snapshots: Vec<SnapshotInfo>,
+
+    /// Path of the file this project was loaded from.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 impl ProjectSerial {
@@ -36,6 +60,10 @@ impl ProjectSerial {
         ProjectSerial {
             info: ProjectInfo::new(),
             targets: Vec::new(),
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            snapshots: Vec::new(),
+            path: None,
         }
     }
 
@@ -46,6 +74,11 @@ impl ProjectSerial {
         ron::from_str(&ronfile)
     }
 
+    /// Serializes the project.
+    pub fn serialize(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
     /// Returns the name of the project.
     pub fn name(&self) -> &String {
         &self.info.name
@@ -55,4 +88,10 @@ impl ProjectSerial {
     pub fn description(&self) -> &String {
         &self.info.description
     }
+
+    /// Returns the directory of the files created by the target through semihosting.
+    /// It is placed next to the project file.
+    pub fn semihostingdir(&self) -> Option<PathBuf> {
+        self.path.as_ref().map(|path| path.with_extension("semihosting"))
+    }
 }
@@ -0,0 +1,278 @@
+//! Memory snapshots of a project.
+//! Snapshots are compared byte-wise or, with an ELF, grouped by the variables that changed.
+
+
+
+use crate::elf::{ ElfFile, Symbol, SymbolKind };
+
+use serde::{ Deserialize, Serialize };
+
+
+
+/// Maximum number of bytes shown for each side of a change.
+const MAXSHOWN: usize = 16;
+
+
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    /// Name of the snapshot.
+    pub name: String,
+
+    /// Captured memory regions.
+    pub regions: Vec<Region>,
+}
+
+impl SnapshotInfo {
+    /// Creates a snapshot of the given `(address, contents)` regions.
+    pub fn new(name: String, regions: Vec<(u32, Vec<u8>)>) -> Self {
+        SnapshotInfo {
+            name,
+            regions: regions.into_iter().map(|(address, data)| Region { address, data }).collect(),
+        }
+    }
+
+    /// Returns the number of captured bytes.
+    pub fn size(&self) -> usize {
+        self.regions.iter().map(|r| r.data.len()).sum()
+    }
+
+    /// Returns the bytes of the range, if a single region contains all of it.
+    fn bytes(&self, start: u32, end: u32) -> Option<&[u8]> {
+        self.regions.iter()
+            .find(|r| (start >= r.address) && (end <= r.end()))
+            .map(|r| &r.data[(start - r.address) as usize..(end - r.address) as usize])
+    }
+
+    /// Compares this snapshot with a later one in the memory captured by both.
+    /// With an ELF the changed bytes are grouped by the variables that contain them.
+    pub fn diff(&self, later: &SnapshotInfo, elf: Option<&ElfFile>) -> Vec<Change> {
+        self.changes(later, |address| {
+            elf.and_then(|elf| elf.symbolat(address))
+                .filter(|symbol| symbol.kind == SymbolKind::Data)
+        })
+    }
+
+    /// Compares this snapshot with a later one, grouping the changes by the symbols found.
+    fn changes<'a, F>(&self, later: &SnapshotInfo, symbolat: F) -> Vec<Change>
+        where F: Fn(u32) -> Option<&'a Symbol>
+    {
+        // Runs of changed bytes with the bounds of the memory captured by both snapshots.
+        let mut runs = Vec::new();
+
+        for a in self.regions.iter() {
+            for b in later.regions.iter() {
+                let (lo, hi) = (a.address.max(b.address), a.end().min(b.end()));
+
+                if lo >= hi {
+                    continue;
+                }
+
+                let old = &a.data[(lo - a.address) as usize..(hi - a.address) as usize];
+                let new = &b.data[(lo - b.address) as usize..(hi - b.address) as usize];
+
+                let mut run = None;
+
+                for (i, (o, n)) in old.iter().zip(new.iter()).enumerate() {
+                    let address = lo + i as u32;
+
+                    match (o != n, run) {
+                        (true, None) => run = Some(address),
+                        (false, Some(start)) => {
+                            runs.push( (start, address, (lo, hi)) );
+                            run = None;
+                        },
+                        _ => (),
+                    }
+                }
+
+                if let Some(start) = run {
+                    runs.push( (start, hi, (lo, hi)) );
+                }
+            }
+        }
+
+        runs.sort_by_key(|(start, _, _)| *start);
+
+        // Split the runs at the symbol boundaries and extend them to the whole symbol.
+        let mut changes: Vec<Change> = Vec::new();
+
+        for (start, end, (lo, hi)) in runs {
+            let mut address = start;
+
+            while address < end {
+                let (s, e, symbol) = match symbolat(address) {
+                    Some(symbol) => {
+                        let e = symbol.address.saturating_add(symbol.size.max(1)).min(hi);
+                        (symbol.address.max(lo), e, Some(symbol.name.clone()))
+                    },
+
+                    _ => {
+                        let e = (address..end).find(|a| symbolat(*a).is_some()).unwrap_or(end);
+                        (address, e, None)
+                    },
+                };
+
+                address = e;
+
+                // A symbol may span several runs.
+                if changes.last().map_or(false, |c| (c.address == s) && (c.symbol == symbol)) {
+                    continue;
+                }
+
+                changes.push( Change {
+                    address: s,
+                    symbol,
+                    old: self.bytes(s, e).map(|b| b.to_vec()).unwrap_or_default(),
+                    new: later.bytes(s, e).map(|b| b.to_vec()).unwrap_or_default(),
+                });
+            }
+        }
+
+        changes
+    }
+}
+
+
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Region {
+    /// Start address of the region.
+    pub address: u32,
+
+    /// Contents of the region, stored as hexadecimal text.
+    #[serde(with = "hex")]
+    pub data: Vec<u8>,
+}
+
+impl Region {
+    /// Returns the address after the end of the region.
+    pub fn end(&self) -> u32 {
+        self.address.saturating_add(self.data.len() as u32)
+    }
+}
+
+
+
+/// A range of memory that differs between two snapshots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Start address of the range.
+    pub address: u32,
+
+    /// Variable that contains the range.
+    pub symbol: Option<String>,
+
+    /// Contents in the earlier snapshot.
+    pub old: Vec<u8>,
+
+    /// Contents in the later snapshot.
+    pub new: Vec<u8>,
+}
+
+impl core::fmt::Display for Change {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.symbol {
+            Some(name) => write!(f, "0x{:08X} {} ({} bytes): ", self.address, name, self.old.len())?,
+            _ => write!(f, "0x{:08X} ({} bytes): ", self.address, self.old.len())?,
+        }
+
+        write!(f, "{} -> {}", hexbytes(&self.old), hexbytes(&self.new))
+    }
+}
+
+/// Formats the first bytes of a range.
+fn hexbytes(data: &[u8]) -> String {
+    let mut text = data.iter()
+        .take(MAXSHOWN)
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if data.len() > MAXSHOWN {
+        text.push_str(" ..");
+    }
+
+    text
+}
+
+
+
+/// Serialization of byte arrays as hexadecimal text.
+mod hex {
+    use serde::{ Deserialize, Deserializer, Serializer, de::Error };
+
+    pub fn serialize<S: Serializer>(data: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        let text: String = data.iter().map(|b| format!("{:02X}", b)).collect();
+
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        if !text.is_ascii() || (text.len() % 2 != 0) {
+            return Err( D::Error::custom("snapshot data is not hexadecimal text") );
+        }
+
+        (0..text.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i+2], 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, address: u32, size: u32) -> Symbol {
+        Symbol { name: String::from(name), raw: String::from(name), address, size, kind: SymbolKind::Data }
+    }
+
+    #[test]
+    fn bytewise() {
+        let a = SnapshotInfo::new(String::from("a"), vec![ (0x100, vec![0, 1, 2, 3, 4, 5, 6, 7]), (0x200, vec![9; 4]) ]);
+        let b = SnapshotInfo::new(String::from("b"), vec![ (0x102, vec![2, 0xFF, 0xFE, 5, 6, 7, 8, 9]) ]);
+
+        // Only the memory captured by both snapshots is compared.
+        let changes = a.changes(&b, |_| None);
+
+        assert_eq!(changes, vec![
+            Change { address: 0x103, symbol: None, old: vec![3, 4], new: vec![0xFF, 0xFE] },
+        ]);
+
+        assert_eq!(format!("{}", changes[0]), "0x00000103 (2 bytes): 03 04 -> FF FE");
+        assert!(a.changes(&a, |_| None).is_empty());
+    }
+
+    #[test]
+    fn symbols() {
+        let symbols = vec![ symbol("counter", 0x100, 4), symbol("flag", 0x104, 1), symbol("buffer", 0x108, 8) ];
+        let symbolat = |address| symbols.iter().find(|s| (address >= s.address) && (address < s.address + s.size));
+
+        let a = SnapshotInfo::new(String::from("a"), vec![ (0x100, vec![0; 16]) ]);
+        let b = SnapshotInfo::new(String::from("b"), vec![ (0x100, vec![1, 0, 0, 0, 0, 0, 7, 0, 0, 1, 0, 0, 0, 2, 0, 0]) ]);
+
+        let changes = a.changes(&b, symbolat);
+
+        // The buffer changed in two runs but is reported once.
+        assert_eq!(changes, vec![
+            Change { address: 0x100, symbol: Some(String::from("counter")), old: vec![0; 4], new: vec![1, 0, 0, 0] },
+            Change { address: 0x106, symbol: None, old: vec![0], new: vec![7] },
+            Change { address: 0x108, symbol: Some(String::from("buffer")), old: vec![0; 8], new: vec![0, 1, 0, 0, 0, 2, 0, 0] },
+        ]);
+    }
+
+    #[test]
+    fn serialization() {
+        let snapshot = SnapshotInfo::new(String::from("boot"), vec![ (0x2000_0000, vec![0x00, 0xAB, 0x7F]) ]);
+
+        let text = ron::to_string(&snapshot).unwrap();
+        assert!(text.contains("\"00AB7F\""));
+
+        assert_eq!(ron::from_str::<SnapshotInfo>(&text).unwrap(), snapshot);
+        assert!(ron::from_str::<SnapshotInfo>("(name: \"x\", regions: [(address: 0, data: \"ABC\")])").is_err());
+    }
+}
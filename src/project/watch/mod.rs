@@ -0,0 +1,30 @@
+//! Live watch variables of a project.
+
+
+
+use crate::probe::Datatype;
+
+use serde::{ Deserialize, Serialize };
+
+use super::Location;
+
+
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct WatchInfo {
+    /// Location of the variable.
+    pub location: Location,
+
+    /// Type of the variable.
+    pub datatype: Datatype,
+}
+
+impl WatchInfo {
+    /// Creates a new watch of the variable at the given location.
+    pub fn new(location: Location, datatype: Datatype) -> Self {
+        WatchInfo {
+            location,
+            datatype,
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! SVD module.
+//! Loads the CMSIS-SVD description of a chip and decodes the values of its peripheral registers.
+
+
+
+use svd_parser::svd::{
+    self, Access, MaybeArray, ModifiedWriteValues, RegisterCluster, RegisterProperties, Usage,
+};
+
+use std::path::PathBuf;
+
+use tracing::{
+    debug, error,
+};
+
+
+
+#[derive(Debug, Clone)]
+pub struct Device {
+    /// Name of the device.
+    pub name: String,
+
+    /// Peripherals of the device sorted by address.
+    pub peripherals: Vec<Peripheral>,
+}
+
+impl Device {
+    /// Loads and parses the SVD file at the given path.
+    pub async fn load(path: PathBuf) -> Result<Self, Error> {
+        let xml = match tokio::fs::read_to_string(&path).await {
+            Err(e) => {
+                error!(origin="svd", "Could not read SVD file {}: {}", path.display(), e);
+                return Err( Error::Io( e.to_string() ) );
+            },
+            Ok(x) => x,
+        };
+
+        Self::parse(&xml)
+    }
+
+    /// Parses the given SVD contents.
+    pub fn parse(xml: &str) -> Result<Self, Error> {
+        let device = match svd_parser::parse(xml) {
+            Err(e) => {
+                error!(origin="svd", "Could not parse SVD file: {:#}", e);
+                return Err( Error::Parse( format!("{:#}", e) ) );
+            },
+            Ok(d) => d,
+        };
+
+        let defaults = Properties::default().inherit(&device.default_register_properties);
+
+        // Peripherals that derive from others take their registers later.
+        let mut peripherals = Vec::new();
+        let mut derived = Vec::new();
+
+        for peripheral in device.peripherals.iter() {
+            for (name, offset) in instances(&peripheral.name, peripheral) {
+                let address = (peripheral.base_address as u32).wrapping_add(offset);
+
+                let mut registers = Vec::new();
+                let properties = defaults.inherit(&peripheral.default_register_properties);
+
+                if let Some(children) = &peripheral.registers {
+                    collect(children, address, "", properties, &mut registers);
+                }
+
+                let new = Peripheral { name, description: description(&peripheral.description), address, registers };
+
+                match &peripheral.derived_from {
+                    Some(base) if new.registers.is_empty() => derived.push( (new, base.clone()) ),
+                    _ => peripherals.push(new),
+                }
+            }
+        }
+
+        for (mut peripheral, base) in derived {
+            match peripherals.iter().find(|p| p.name == base) {
+                Some(base) => {
+                    peripheral.registers = base.registers.iter()
+                        .map(|r| Register { address: r.address - base.address + peripheral.address, ..r.clone() })
+                        .collect();
+
+                    if peripheral.description.is_none() {
+                        peripheral.description = base.description.clone();
+                    }
+                },
+
+                _ => error!(origin="svd", "Peripheral {} derives from unknown peripheral {}", peripheral.name, base),
+            }
+
+            peripherals.push(peripheral);
+        }
+
+        peripherals.sort_by_key(|p| p.address);
+
+        debug!(origin="svd", "Parsed SVD of device {} with {} peripherals", device.name, peripherals.len());
+
+        Ok( Device { name: device.name, peripherals } )
+    }
+
+    /// Returns the peripheral with the given name.
+    pub fn peripheral(&self, name: &str) -> Option<&Peripheral> {
+        self.peripherals.iter().find(|p| p.name == name)
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peripheral {
+    /// Name of the peripheral.
+    pub name: String,
+
+    /// Description of the peripheral.
+    pub description: Option<String>,
+
+    /// Base address of the peripheral.
+    pub address: u32,
+
+    /// Registers of the peripheral sorted by address.
+    pub registers: Vec<Register>,
+}
+
+impl Peripheral {
+    /// Returns the aligned addresses of the words that hold the readable registers.
+    pub fn words(&self) -> Vec<u32> {
+        let mut words: Vec<u32> = self.registers.iter()
+            .filter(|r| r.readable)
+            .map(|r| r.word())
+            .collect();
+
+        words.dedup();
+        words
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    /// Name of the register, prefixed by its clusters.
+    pub name: String,
+
+    /// Description of the register.
+    pub description: Option<String>,
+
+    /// Address of the register.
+    pub address: u32,
+
+    /// Size of the register in bits.
+    pub size: u32,
+
+    /// The register can be read without side effects.
+    pub readable: bool,
+
+    /// The register can be written.
+    pub writable: bool,
+
+    /// Value of the register after reset.
+    pub reset: Option<u32>,
+
+    /// Fields of the register sorted by bit offset.
+    pub fields: Vec<Field>,
+}
+
+impl Register {
+    /// Returns the aligned address of the word that holds the register.
+    pub fn word(&self) -> u32 {
+        self.address & !3
+    }
+
+    /// Extracts the value of the register from the little endian word that holds it.
+    pub fn value(&self, word: u32) -> u32 {
+        let value = word >> ((self.address & 3) * 8);
+
+        match self.size {
+            0 | 32..=u32::MAX => value,
+            size => value & ((1 << size) - 1),
+        }
+    }
+
+    /// Returns the bits of the fields that must be written with a fixed value to leave them
+    /// unchanged, and the ones among them. Fields cleared, set or toggled by ones or zeros
+    /// are written with the value without effect, and fields that cannot be read as zeros.
+    pub fn neutral(&self) -> (u32, u32) {
+        self.fields.iter().fold((0, 0), |(mask, ones), f| match (f.effect, f.readable) {
+            (WriteEffect::ZeroTo, _) => (mask | f.mask(), ones | f.mask()),
+            (WriteEffect::OneTo, _) | (_, false) => (mask | f.mask(), ones),
+            _ => (mask, ones),
+        })
+    }
+
+    /// Builds the write of a value into one of the fields of the register.
+    /// Returns the mask of the bits of the word that are kept from a read of it and the bits
+    /// written, or `None` if the value does not fit in the field. Registers that cannot be
+    /// read are written without reading them.
+    pub fn write(&self, field: &Field, value: u32) -> Option<(u32, u32)> {
+        let bits = field.insert(0, value)?;
+
+        let (fixed, ones) = self.neutral();
+        let fixed = fixed | field.mask();
+        let bits = bits | (ones & !field.mask());
+
+        // The register is written in the word that holds it.
+        let shift = (self.address & 3) * 8;
+
+        let keep = match self.readable {
+            true => !(fixed << shift),
+            _ => 0,
+        };
+
+        Some( (keep, bits << shift) )
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Name of the field.
+    pub name: String,
+
+    /// Description of the field.
+    pub description: Option<String>,
+
+    /// Offset of the lowest bit of the field.
+    pub offset: u32,
+
+    /// Width of the field in bits.
+    pub width: u32,
+
+    /// The field can be read without side effects.
+    pub readable: bool,
+
+    /// The field can be written.
+    pub writable: bool,
+
+    /// Effect of the values written into the field.
+    pub effect: WriteEffect,
+
+    /// Names of the values of the field.
+    pub values: Vec<EnumValue>,
+}
+
+impl Field {
+    /// Returns the mask of the field in the register.
+    pub fn mask(&self) -> u32 {
+        match self.width {
+            0 => 0,
+            32..=u32::MAX => u32::MAX,
+            width => ((1 << width) - 1) << self.offset,
+        }
+    }
+
+    /// Extracts the value of the field from the value of the register.
+    pub fn extract(&self, register: u32) -> u32 {
+        (register & self.mask()).checked_shr(self.offset).unwrap_or(0)
+    }
+
+    /// Returns the largest value of the field.
+    pub fn max(&self) -> u32 {
+        self.mask().checked_shr(self.offset).unwrap_or(0)
+    }
+
+    /// Replaces the field in the value of the register.
+    /// Returns `None` if the value does not fit in the field.
+    pub fn insert(&self, register: u32, value: u32) -> Option<u32> {
+        if value > self.max() {
+            return None;
+        }
+
+        Some( (register & !self.mask()) | (value << self.offset) )
+    }
+
+    /// Returns the name of the given value of the field.
+    pub fn name(&self, value: u32) -> Option<&str> {
+        let read = || self.values.iter().filter(|v| v.read);
+
+        read().find(|v| v.value == Some(value))
+            .or_else(|| read().find(|v| v.value.is_none()))
+            .map(|v| v.name.as_str())
+    }
+
+    /// Returns the named values that can be written to the field.
+    pub fn choices(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.values.iter()
+            .filter(|v| v.write)
+            .filter_map(|v| v.value.map(|value| (v.name.as_str(), value)))
+    }
+
+    /// Returns the bit range of the field.
+    pub fn bits(&self) -> String {
+        match self.width {
+            1 => format!("[{}]", self.offset),
+            width => format!("[{}:{}]", self.offset + width - 1, self.offset),
+        }
+    }
+}
+
+
+
+/// Effect of the values written into a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteEffect {
+    /// The value written replaces the field.
+    Modify,
+
+    /// Ones clear, set or toggle the bits of the field, zeros have no effect.
+    OneTo,
+
+    /// Zeros clear, set or toggle the bits of the field, ones have no effect.
+    ZeroTo,
+
+    /// Any write clears or sets the field.
+    Any,
+}
+
+impl WriteEffect {
+    /// Converts the modified write values of the SVD.
+    fn of(values: Option<ModifiedWriteValues>) -> Self {
+        match values {
+            Some(ModifiedWriteValues::OneToClear) | Some(ModifiedWriteValues::OneToSet) | Some(ModifiedWriteValues::OneToToggle) => WriteEffect::OneTo,
+            Some(ModifiedWriteValues::ZeroToClear) | Some(ModifiedWriteValues::ZeroToSet) | Some(ModifiedWriteValues::ZeroToToggle) => WriteEffect::ZeroTo,
+            Some(ModifiedWriteValues::Clear) | Some(ModifiedWriteValues::Set) => WriteEffect::Any,
+            _ => WriteEffect::Modify,
+        }
+    }
+}
+
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumValue {
+    /// Name of the value.
+    pub name: String,
+
+    /// The value, `None` for the name of all the other values.
+    pub value: Option<u32>,
+
+    /// The name applies to the values read.
+    pub read: bool,
+
+    /// The name applies to the values written.
+    pub write: bool,
+}
+
+
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The file could not be read.
+    Io(String),
+
+    /// The file is not a valid SVD.
+    Parse(String),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "Could not read SVD file: {}", e),
+            Error::Parse(e) => write!(f, "Could not parse SVD file: {}", e),
+        }
+    }
+}
+
+
+
+/// Register properties inherited from the device, peripherals and clusters.
+#[derive(Debug, Clone, Copy, Default)]
+struct Properties {
+    /// Size in bits.
+    size: Option<u32>,
+
+    /// Access rights.
+    access: Option<Access>,
+
+    /// Value after reset.
+    reset: Option<u64>,
+}
+
+impl Properties {
+    /// Overrides these properties with the ones that are set.
+    fn inherit(&self, properties: &RegisterProperties) -> Self {
+        Properties {
+            size: properties.size.or(self.size),
+            access: properties.access.or(self.access),
+            reset: properties.reset_value.or(self.reset),
+        }
+    }
+}
+
+/// Returns `true` if the access rights allow reads.
+fn readable(access: Option<Access>) -> bool {
+    match access {
+        Some(Access::WriteOnly) | Some(Access::WriteOnce) => false,
+        _ => true,
+    }
+}
+
+/// Returns `true` if the access rights allow writes.
+fn writable(access: Option<Access>) -> bool {
+    match access {
+        Some(Access::ReadOnly) => false,
+        _ => true,
+    }
+}
+
+/// Cleans up the whitespace of an SVD description.
+fn description(text: &Option<String>) -> Option<String> {
+    text.as_ref()
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+}
+
+/// Returns the names and offsets of the instances of an array, or of a single element.
+fn instances<T>(name: &str, element: &MaybeArray<T>) -> Vec<(String, u32)> {
+    match element {
+        MaybeArray::Array(_, dim) => (0..dim.dim)
+            .map(|i| {
+                let index = dim.dim_index.as_ref()
+                    .and_then(|indices| indices.get(i as usize).cloned())
+                    .unwrap_or_else(|| i.to_string());
+
+                (name.replace("%s", &index), i * dim.dim_increment)
+            })
+            .collect(),
+
+        _ => vec![ (String::from(name), 0) ],
+    }
+}
+
+/// Collects the registers of the clusters and registers at the given base address.
+fn collect(children: &[RegisterCluster], base: u32, prefix: &str, defaults: Properties, registers: &mut Vec<Register>) {
+    for child in children {
+        match child {
+            RegisterCluster::Cluster(cluster) => {
+                let properties = defaults.inherit(&cluster.default_register_properties);
+
+                for (name, offset) in instances(&cluster.name, cluster) {
+                    let address = base.wrapping_add(cluster.address_offset).wrapping_add(offset);
+                    collect(&cluster.children, address, &format!("{}{}.", prefix, name), properties, registers);
+                }
+            },
+
+            RegisterCluster::Register(register) => {
+                let properties = defaults.inherit(&register.properties);
+
+                // Reads with side effects are never done to display a register.
+                let access = readable(properties.access) && register.read_action.is_none()
+                    && register.fields.iter().flatten().all(|f| f.read_action.is_none());
+
+                let mut fields: Vec<Field> = register.fields.iter()
+                    .flat_map(|fields| fields.iter())
+                    .flat_map(|field| instances(&field.name, field).into_iter().map(move |(name, offset)| (field, name, offset)))
+                    .map(|(field, name, offset)| Field {
+                        name,
+                        description: description(&field.description),
+                        offset: field.bit_range.offset + offset,
+                        width: field.bit_range.width,
+                        readable: readable(field.access.or(properties.access)) && field.read_action.is_none(),
+                        writable: writable(field.access.or(properties.access)),
+                        effect: WriteEffect::of(field.modified_write_values.or(register.modified_write_values)),
+                        values: enumvalues(&field.enumerated_values),
+                    })
+                    .collect();
+
+                fields.sort_by_key(|f| f.offset);
+
+                for (name, offset) in instances(&register.name, register) {
+                    registers.push( Register {
+                        name: format!("{}{}", prefix, name),
+                        description: description(&register.description),
+                        address: base.wrapping_add(register.address_offset).wrapping_add(offset),
+                        size: properties.size.unwrap_or(32),
+                        readable: access,
+                        writable: writable(properties.access),
+                        reset: properties.reset.map(|r| r as u32),
+                        fields: fields.clone(),
+                    });
+                }
+            },
+        }
+    }
+
+    registers.sort_by_key(|r| r.address);
+}
+
+/// Collects the names of the values of a field.
+fn enumvalues(enumerated: &[svd::EnumeratedValues]) -> Vec<EnumValue> {
+    enumerated.iter()
+        .flat_map(|e| e.values.iter().map(move |v| (e.usage, v)))
+        .filter(|(_, v)| v.value.is_some() || v.is_default == Some(true))
+        .map(|(usage, v)| EnumValue {
+            name: v.name.clone(),
+            value: v.value.map(|v| v as u32),
+            read: usage != Some(Usage::Write),
+            write: usage != Some(Usage::Read),
+        })
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVD: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<device schemaVersion="1.3">
+  <name>TEST</name>
+  <version>1.0</version>
+  <description>Test device</description>
+  <addressUnitBits>8</addressUnitBits>
+  <width>32</width>
+  <size>32</size>
+  <access>read-write</access>
+  <resetValue>0x00000000</resetValue>
+  <resetMask>0xFFFFFFFF</resetMask>
+  <peripherals>
+    <peripheral>
+      <name>GPIOB</name>
+      <derivedFrom>GPIOA</derivedFrom>
+      <baseAddress>0x40000400</baseAddress>
+    </peripheral>
+    <peripheral>
+      <name>GPIOA</name>
+      <description>General purpose
+        input output</description>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>MODER</name>
+          <addressOffset>0x00</addressOffset>
+          <resetValue>0xA8000000</resetValue>
+          <fields>
+            <field>
+              <name>MODE%s</name>
+              <bitOffset>0</bitOffset>
+              <bitWidth>2</bitWidth>
+              <dim>2</dim>
+              <dimIncrement>2</dimIncrement>
+              <enumeratedValues>
+                <enumeratedValue><name>Input</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>Output</name><value>1</value></enumeratedValue>
+                <enumeratedValue><name>Other</name><isDefault>true</isDefault></enumeratedValue>
+              </enumeratedValues>
+              <enumeratedValues>
+                <usage>write</usage>
+                <enumeratedValue><name>SetInput</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>SetAnalog</name><value>3</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+          </fields>
+        </register>
+        <register>
+          <name>IDR</name>
+          <addressOffset>0x10</addressOffset>
+          <access>read-only</access>
+          <fields>
+            <field><name>ID0</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth></field>
+          </fields>
+        </register>
+        <register>
+          <name>BSRR</name>
+          <addressOffset>0x18</addressOffset>
+          <access>write-only</access>
+          <fields>
+            <field><name>BS0</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth></field>
+          </fields>
+        </register>
+        <register>
+          <name>AFR%s</name>
+          <addressOffset>0x20</addressOffset>
+          <dim>2</dim>
+          <dimIncrement>4</dimIncrement>
+          <dimIndex>L,H</dimIndex>
+        </register>
+        <cluster>
+          <name>LOCK</name>
+          <addressOffset>0x30</addressOffset>
+          <register>
+            <name>KEY</name>
+            <addressOffset>0x2</addressOffset>
+            <size>16</size>
+            <fields>
+              <field><name>VALUE</name><bitOffset>0</bitOffset><bitWidth>16</bitWidth></field>
+            </fields>
+          </register>
+        </cluster>
+        <register>
+          <name>SR</name>
+          <addressOffset>0x38</addressOffset>
+          <fields>
+            <field><name>READY</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth><access>read-only</access></field>
+            <field><name>OVR</name><bitOffset>1</bitOffset><bitWidth>1</bitWidth><modifiedWriteValues>oneToClear</modifiedWriteValues></field>
+            <field><name>ERR</name><bitOffset>2</bitOffset><bitWidth>1</bitWidth><modifiedWriteValues>oneToClear</modifiedWriteValues></field>
+            <field><name>EN</name><bitOffset>4</bitOffset><bitWidth>1</bitWidth></field>
+          </fields>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+
+    #[test]
+    fn parse() {
+        let device = Device::parse(SVD).expect("Could not parse the test SVD");
+
+        assert_eq!(device.name, "TEST");
+        assert_eq!(device.peripherals.iter().map(|p| (p.name.as_str(), p.address)).collect::<Vec<_>>(), vec![ ("GPIOA", 0x4000_0000), ("GPIOB", 0x4000_0400) ]);
+
+        let gpioa = device.peripheral("GPIOA").unwrap();
+        assert_eq!(gpioa.description.as_deref(), Some("General purpose input output"));
+
+        let registers: Vec<_> = gpioa.registers.iter().map(|r| (r.name.as_str(), r.address, r.size, r.readable)).collect();
+        assert_eq!(registers, vec![
+            ("MODER", 0x4000_0000, 32, true),
+            ("IDR", 0x4000_0010, 32, true),
+            ("BSRR", 0x4000_0018, 32, false),
+            ("AFRL", 0x4000_0020, 32, true),
+            ("AFRH", 0x4000_0024, 32, true),
+            ("LOCK.KEY", 0x4000_0032, 16, true),
+            ("SR", 0x4000_0038, 32, true),
+        ]);
+
+        // The write-only register is not read and the cluster shares a word.
+        assert_eq!(gpioa.words(), vec![ 0x4000_0000, 0x4000_0010, 0x4000_0020, 0x4000_0024, 0x4000_0030, 0x4000_0038 ]);
+
+        // The derived peripheral has the registers of its base.
+        let gpiob = device.peripheral("GPIOB").unwrap();
+        assert_eq!(gpiob.registers[0].address, 0x4000_0400);
+        assert_eq!(gpiob.registers.len(), 7);
+    }
+
+    #[test]
+    fn decode() {
+        let device = Device::parse(SVD).expect("Could not parse the test SVD");
+
+        let gpioa = device.peripheral("GPIOA").unwrap();
+        let moder = &gpioa.registers[0];
+
+        assert_eq!(moder.reset, Some(0xA800_0000));
+        assert_eq!(moder.fields.iter().map(|f| (f.name.as_str(), f.bits())).collect::<Vec<_>>(), vec![ ("MODE0", String::from("[1:0]")), ("MODE1", String::from("[3:2]")) ]);
+
+        let value = 0b0111;
+        let fields: Vec<_> = moder.fields.iter().map(|f| (f.extract(value), f.name(f.extract(value)))).collect();
+        assert_eq!(fields, vec![ (3, Some("Other")), (1, Some("Output")) ]);
+
+        // Registers smaller than a word are extracted from the word that holds them.
+        let key = &gpioa.registers[5];
+        assert_eq!(key.value(0xBEEF_1234), 0xBEEF);
+        assert_eq!(moder.value(0xBEEF_1234), 0xBEEF_1234);
+    }
+
+    #[test]
+    fn write() {
+        let device = Device::parse(SVD).expect("Could not parse the test SVD");
+
+        let gpioa = device.peripheral("GPIOA").unwrap();
+        let (moder, idr) = (&gpioa.registers[0], &gpioa.registers[1]);
+
+        // Read-only registers have read-only fields.
+        assert!(moder.writable && moder.fields[1].writable);
+        assert!(!idr.writable && !idr.fields[0].writable);
+
+        // The names of the written values are separate from the names read.
+        let mode1 = &moder.fields[1];
+        assert_eq!(mode1.choices().collect::<Vec<_>>(), vec![ ("Input", 0), ("Output", 1), ("SetInput", 0), ("SetAnalog", 3) ]);
+        assert_eq!(mode1.name(3), Some("Other"));
+
+        assert_eq!(mode1.max(), 3);
+        assert_eq!(mode1.insert(0xFFFF_FFF3, 1), Some(0xFFFF_FFF7));
+        assert_eq!(mode1.insert(0, 4), None);
+
+        // The other fields of a readable register are kept.
+        assert_eq!(moder.write(mode1, 1), Some( (!0b1100, 0b0100) ));
+        assert_eq!(moder.write(mode1, 4), None);
+
+        // Write-only registers are written without reading them.
+        let bsrr = &gpioa.registers[2];
+        assert_eq!(bsrr.write(&bsrr.fields[0], 1), Some( (0, 1) ));
+
+        // Registers smaller than a word are written in the word that holds them.
+        let key = &gpioa.registers[5];
+        assert_eq!(key.write(&key.fields[0], 0xBEEF), Some( (0x0000_FFFF, 0xBEEF_0000) ));
+    }
+
+    #[test]
+    fn clear() {
+        let device = Device::parse(SVD).expect("Could not parse the test SVD");
+
+        let sr = &device.peripheral("GPIOA").unwrap().registers[6];
+        let (ready, ovr, en) = (&sr.fields[0], &sr.fields[1], &sr.fields[3]);
+
+        assert_eq!((ready.effect, ovr.effect), (WriteEffect::Modify, WriteEffect::OneTo));
+        assert_eq!(sr.neutral(), (0b0110, 0));
+
+        // Pending flags cleared by ones are written as zeros.
+        assert_eq!(sr.write(en, 1), Some( (!0b1_0110, 0b1_0000) ));
+
+        // Only the flag written is cleared.
+        assert_eq!(sr.write(ovr, 1), Some( (!0b0110, 0b0010) ));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Device::parse("<device>").is_err());
+    }
+}